@@ -0,0 +1,316 @@
+//! `rdfviz run viz.toml`: a whole pipeline (sources, filters, styles, rollup and outputs) in
+//! one file, so a setup can be checked in and shared instead of retyped as a long command line
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Contents of a `viz.toml`. Every relative path in it is relative to the file itself.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Ontology files, loaded in order
+    ontologies: Vec<PathBuf>,
+    /// Well-known ontologies, as NAME or NAME@VERSION
+    presets: Vec<String>,
+    /// Data graphs: one, two for a diff, none in the schema and shapes modes
+    data: Vec<PathBuf>,
+    /// Extra `prefix = "namespace"` pairs the CURIEs elsewhere in the file may use
+    prefixes: BTreeMap<String, String>,
+    filters: Filters,
+    styles: Styles,
+    rollup: Option<Rollup>,
+    /// Output paths by format; `d2` is the main output and every other format is one `--out`
+    outputs: BTreeMap<String, PathBuf>,
+    /// Any other command-line option by its long name: `true` for a flag, a list for a
+    /// repeatable option
+    options: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Filters {
+    allow_ns: Vec<String>,
+    deny_ns: Vec<String>,
+    keep_edge: Vec<String>,
+    drop_edge: Vec<String>,
+    min_edge_count: Option<usize>,
+    max_nodes: Option<usize>,
+    focus: Option<String>,
+    hops: Option<usize>,
+    /// A --filter script, in `lang`
+    script: Option<PathBuf>,
+    lang: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Styles {
+    colors: Vec<String>,
+    theme: Option<String>,
+    /// A --style-file
+    file: Option<PathBuf>,
+    /// Styles by class IRI or CURIE, like --class-style
+    classes: BTreeMap<String, String>,
+    /// Styles by predicate IRI or CURIE, like --edge-style
+    edges: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Rollup {
+    class: Option<String>,
+    depth: Option<usize>,
+    properties: bool,
+}
+
+impl Config {
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    /// `value` with a leading prefix of [`Config::prefixes`] expanded to its namespace, and
+    /// a bare prefix expanded to the whole namespace
+    fn expand(&self, value: &str) -> String {
+        if let Some(namespace) = self.prefixes.get(value) {
+            return namespace.clone();
+        }
+        match value.split_once(':') {
+            Some((prefix, local)) if self.prefixes.contains_key(prefix) => {
+                format!("{}{}", self.prefixes[prefix], local)
+            }
+            _ => value.to_owned(),
+        }
+    }
+
+    /// The command line this file stands for, to parse like any other
+    pub fn to_args(&self) -> Result<Vec<OsString>> {
+        // the files go first, so an option with an optional value cannot take one for its own
+        let mut args: Vec<OsString> = vec!["rdfviz".into()];
+        args.extend(self.ontologies.iter().chain(self.data.iter()).map(|path| path.clone().into_os_string()));
+
+        for preset in self.presets.iter() {
+            option(&mut args, "preset", preset);
+        }
+        for namespace in self.filters.allow_ns.iter() {
+            option(&mut args, "allow-ns", self.expand(namespace));
+        }
+        for namespace in self.filters.deny_ns.iter() {
+            option(&mut args, "deny-ns", self.expand(namespace));
+        }
+        for regex in self.filters.keep_edge.iter() {
+            option(&mut args, "keep-edge", regex);
+        }
+        for regex in self.filters.drop_edge.iter() {
+            option(&mut args, "drop-edge", regex);
+        }
+        if let Some(min) = self.filters.min_edge_count {
+            option(&mut args, "min-edge-count", min.to_string());
+        }
+        if let Some(max) = self.filters.max_nodes {
+            option(&mut args, "max-nodes", max.to_string());
+        }
+        if let Some(focus) = &self.filters.focus {
+            option(&mut args, "focus", self.expand(focus));
+        }
+        if let Some(hops) = self.filters.hops {
+            option(&mut args, "hops", hops.to_string());
+        }
+        if let Some(script) = &self.filters.script {
+            option(&mut args, "filter", script);
+        }
+        if let Some(lang) = &self.filters.lang {
+            option(&mut args, "filter-lang", lang);
+        }
+
+        for colors in self.styles.colors.iter() {
+            option(&mut args, "colors", colors);
+        }
+        if let Some(theme) = &self.styles.theme {
+            option(&mut args, "theme", theme);
+        }
+        if let Some(file) = &self.styles.file {
+            option(&mut args, "style-file", file);
+        }
+        for (class, style) in self.styles.classes.iter() {
+            option(&mut args, "class-style", format!("{}={}", self.expand(class), style));
+        }
+        for (predicate, style) in self.styles.edges.iter() {
+            option(&mut args, "edge-style", format!("{}={}", self.expand(predicate), style));
+        }
+
+        if let Some(rollup) = &self.rollup {
+            if let Some(class) = &rollup.class {
+                option(&mut args, "rollup", self.expand(class));
+            }
+            if let Some(depth) = rollup.depth {
+                option(&mut args, "rollup-depth", depth.to_string());
+            }
+            if rollup.properties {
+                flag(&mut args, "rollup-properties");
+            }
+        }
+
+        for (format, path) in self.outputs.iter() {
+            match format.as_str() {
+                "d2" => option(&mut args, "output", path),
+                _ => {
+                    let mut out = OsString::from(format!("{}=", format));
+                    out.push(path);
+                    option(&mut args, "out", out);
+                }
+            }
+        }
+
+        for (name, value) in self.options.iter() {
+            let name = name.replace('_', "-");
+            let values = match value {
+                toml::Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for value in values {
+                match value {
+                    toml::Value::Boolean(true) => flag(&mut args, &name),
+                    toml::Value::Boolean(false) => {}
+                    toml::Value::String(value) => option(&mut args, &name, value),
+                    toml::Value::Integer(value) => option(&mut args, &name, value.to_string()),
+                    toml::Value::Float(value) => option(&mut args, &name, value.to_string()),
+                    _ => bail!("unsupported value for option '{}': {}", name, value),
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+fn flag(args: &mut Vec<OsString>, name: &str) {
+    args.push(format!("--{}", name).into());
+}
+
+fn option(args: &mut Vec<OsString>, name: &str, value: impl AsRef<OsStr>) {
+    flag(args, name);
+    args.push(value.as_ref().to_owned());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(toml: &str) -> Vec<String> {
+        let config: Config = toml::from_str(toml).unwrap();
+        config.to_args().unwrap().into_iter().map(|arg| arg.into_string().unwrap()).collect()
+    }
+
+    #[test]
+    fn files_go_before_options() {
+        let args = args(
+            r#"
+            ontologies = ["Brick.ttl"]
+            data = ["site.ttl"]
+            presets = ["qudt"]
+            "#,
+        );
+        assert_eq!(args, ["rdfviz", "Brick.ttl", "site.ttl", "--preset", "qudt"]);
+    }
+
+    #[test]
+    fn prefixes_expand_in_filters_and_styles() {
+        let args = args(
+            r#"
+            [prefixes]
+            brick = "https://brickschema.org/schema/Brick#"
+
+            [filters]
+            allow_ns = ["brick"]
+            focus = "brick:AHU"
+            hops = 2
+
+            [styles.classes]
+            "brick:Point" = "fill=red"
+            "#,
+        );
+        assert_eq!(
+            args,
+            [
+                "rdfviz",
+                "--allow-ns",
+                "https://brickschema.org/schema/Brick#",
+                "--focus",
+                "https://brickschema.org/schema/Brick#AHU",
+                "--hops",
+                "2",
+                "--class-style",
+                "https://brickschema.org/schema/Brick#Point=fill=red",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_prefixes_are_kept() {
+        let args = args(
+            r#"
+            [filters]
+            deny_ns = ["owl:", "http://example.org/"]
+            "#,
+        );
+        assert_eq!(args, ["rdfviz", "--deny-ns", "owl:", "--deny-ns", "http://example.org/"]);
+    }
+
+    #[test]
+    fn d2_is_the_main_output() {
+        let args = args(
+            r#"
+            [outputs]
+            d2 = "out.d2"
+            json = "out.json"
+            "#,
+        );
+        assert_eq!(args, ["rdfviz", "--output", "out.d2", "--out", "json=out.json"]);
+    }
+
+    #[test]
+    fn rollup_and_options() {
+        let args = args(
+            r#"
+            [rollup]
+            class = "urn:Point"
+            properties = true
+
+            [options]
+            dry_run = true
+            quiet = false
+            layout = "circle"
+            max_label = 20
+            exclude = ["urn:a", "urn:b"]
+            "#,
+        );
+        assert_eq!(
+            args,
+            [
+                "rdfviz",
+                "--rollup",
+                "urn:Point",
+                "--rollup-properties",
+                "--dry-run",
+                "--exclude",
+                "urn:a",
+                "--exclude",
+                "urn:b",
+                "--layout",
+                "circle",
+                "--max-label",
+                "20",
+            ]
+        );
+    }
+
+    #[test]
+    fn tables_are_not_options() {
+        let config: Config = toml::from_str("[options.nested]\nkey = 1\n").unwrap();
+        assert!(config.to_args().is_err());
+        assert!(toml::from_str::<Config>("unknown = 1").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Exit codes and `--diagnostics json`, so CI pipelines can tell what a run found without
+//! scraping the log
+
+use rdf_class_viz::Error;
+use serde_json::json;
+use std::process::ExitCode;
+use std::str::FromStr;
+use tracing::{error, warn};
+
+/// Exit code of a command line clap rejects, such as an unknown flag; sysexits' `EX_USAGE`,
+/// so it cannot be mistaken for a failed run
+pub const USAGE_ERROR: u8 = 64;
+/// Exit code of a run whose inputs are not valid RDF
+pub const PARSE_ERROR: u8 = 2;
+/// Exit code of a run whose class graph came out without any class
+pub const EMPTY_GRAPH: u8 = 3;
+/// Exit code of a run whose --filter script is invalid
+pub const FILTER_ERROR: u8 = 4;
+/// Exit code of a run with a query over --query-timeout or --max-results
+pub const QUERY_LIMIT: u8 = 5;
+
+/// The error a run ends with when its class graph has no classes, after writing its outputs
+#[derive(Debug)]
+pub struct EmptyGraph;
+
+impl std::fmt::Display for EmptyGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the class graph is empty")
+    }
+}
+
+impl std::error::Error for EmptyGraph {}
+
+/// The exit code of a command line clap rejected with `e`; --help and --version end up here
+/// too, and succeed
+pub fn usage_failure(e: &clap::Error) -> u8 {
+    if e.use_stderr() {
+        USAGE_ERROR
+    } else {
+        0
+    }
+}
+
+/// The exit code of a run that failed with `e`, and the diagnostic code naming the failure
+fn failure(e: &anyhow::Error) -> (u8, &'static str) {
+    for cause in e.chain() {
+        if cause.is::<EmptyGraph>() {
+            return (EMPTY_GRAPH, "empty-graph");
+        }
+        if cause.is::<oxigraph::io::read::ParseError>() {
+            return (PARSE_ERROR, "parse-error");
+        }
+        match cause.downcast_ref::<Error>() {
+            Some(Error::Parse(_)) => return (PARSE_ERROR, "parse-error"),
+            Some(Error::Filter(_)) => return (FILTER_ERROR, "filter-error"),
+            Some(Error::Timeout(_)) => return (QUERY_LIMIT, "query-timeout"),
+            Some(Error::TooManyResults(_)) => return (QUERY_LIMIT, "too-many-results"),
+            _ => {}
+        }
+    }
+    (1, "error")
+}
+
+/// How warnings about the class graph and the error a run fails with are reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Diagnostics {
+    /// Logged to stderr like everything else
+    #[default]
+    Text,
+    /// One JSON object per line on stderr, with a `level`, a stable `code`, the `subject` it
+    /// is about and a `message`
+    Json,
+}
+
+impl FromStr for Diagnostics {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Diagnostics::Text),
+            "json" => Ok(Diagnostics::Json),
+            _ => Err(format!("unknown diagnostics format '{}', expected text or json", s)),
+        }
+    }
+}
+
+impl Diagnostics {
+    /// Reports a warning; `code` names its kind, e.g. `deprecated-class`, and `subject` is the
+    /// class it is about
+    pub fn warn(self, code: &str, subject: Option<&str>, message: &str) {
+        match self {
+            Diagnostics::Text => warn!("{}", message),
+            Diagnostics::Json => eprintln!(
+                "{}",
+                json!({ "level": "warning", "code": code, "subject": subject, "message": message })
+            ),
+        }
+    }
+
+    /// Reports the error a run failed with and returns the exit code it maps to
+    pub fn fail(self, e: &anyhow::Error) -> ExitCode {
+        let (exit_code, code) = failure(e);
+        match self {
+            Diagnostics::Text => error!("{:#}", e),
+            Diagnostics::Json => eprintln!(
+                "{}",
+                json!({ "level": "error", "code": code, "exit_code": exit_code, "message": format!("{:#}", e) })
+            ),
+        }
+        ExitCode::from(exit_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Args;
+    use clap::Parser;
+    use oxigraph::io::GraphFormat;
+    use rdf_class_viz::Visualizer;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn exit_code(e: impl Into<anyhow::Error>) -> u8 {
+        failure(&e.into()).0
+    }
+
+    /// The error of loading `data` that is not valid Turtle
+    fn parse_error() -> Error {
+        let mut v = Visualizer::new(|_: &str, _: &str, _: &str| true, HashMap::new()).unwrap();
+        v.add_data(&b"not turtle"[..], GraphFormat::Turtle).unwrap_err()
+    }
+
+    #[test]
+    fn invalid_command_lines_are_usage_errors() {
+        let Err(e) = Args::try_parse_from(["rdfviz", "--no-such-flag"]) else { panic!("parsed") };
+        assert_eq!(usage_failure(&e), USAGE_ERROR);
+        let Err(e) = Args::try_parse_from(["rdfviz", "--help"]) else { panic!("parsed") };
+        assert_eq!(usage_failure(&e), 0);
+    }
+
+    #[test]
+    fn invalid_rdf_is_a_parse_error() {
+        let e = parse_error();
+        assert!(matches!(e, Error::Parse(_)));
+        assert_eq!(failure(&e.into()), (PARSE_ERROR, "parse-error"));
+        let Error::Parse(e) = parse_error() else { unreachable!() };
+        assert_eq!(exit_code(e), PARSE_ERROR);
+    }
+
+    #[test]
+    fn an_empty_graph_has_its_own_code() {
+        assert_eq!(failure(&EmptyGraph.into()), (EMPTY_GRAPH, "empty-graph"));
+    }
+
+    #[test]
+    fn an_invalid_filter_is_a_filter_error() {
+        assert_eq!(failure(&Error::Filter("syntax error".to_owned()).into()), (FILTER_ERROR, "filter-error"));
+    }
+
+    #[test]
+    fn queries_over_a_limit_are_query_limit_errors() {
+        let timeout = Error::Timeout(Duration::from_secs(1));
+        assert_eq!(failure(&timeout.into()), (QUERY_LIMIT, "query-timeout"));
+        assert_eq!(failure(&Error::TooManyResults(10).into()), (QUERY_LIMIT, "too-many-results"));
+        // found under the context added on the way up
+        let e = anyhow::Error::from(Error::TooManyResults(10)).context("failed to build the class graph");
+        assert_eq!(failure(&e), (QUERY_LIMIT, "too-many-results"));
+    }
+
+    #[test]
+    fn anything_else_exits_with_one() {
+        assert_eq!(failure(&anyhow::anyhow!("disk full")), (1, "error"));
+        assert_eq!(exit_code(Error::NotInGraph("urn:ex#Chiller".to_owned())), 1);
+    }
+}
@@ -0,0 +1,163 @@
+//! The GraphQL schema `serve` exposes at `/graphql` over the class summary of its data graph
+
+use juniper::{graphql_object, EmptyMutation, EmptySubscription, GraphQLObject, RootNode};
+use rdf_class_viz::petgraph::visit::EdgeRef;
+use rdf_class_viz::Visualizer;
+
+/// A class of the summary
+#[derive(Debug, Clone, GraphQLObject)]
+pub struct Class {
+    /// Label the class is drawn with, e.g. brick:AHU
+    label: String,
+    iri: Option<String>,
+    /// Fill color of the class, as a hex color
+    color: Option<String>,
+    /// Instances of the class in the data graph
+    instance_count: Option<i32>,
+}
+
+/// An edge of the summary: instances of `from` linked to instances of `to` by a predicate
+#[derive(Debug, Clone, GraphQLObject)]
+pub struct Relationship {
+    from: String,
+    /// Label of the predicate, e.g. brick:feeds
+    predicate: String,
+    predicate_iri: Option<String>,
+    to: String,
+    /// Instance pairs linked by the predicate; missing for edges drawn from the ontology
+    count: Option<i32>,
+}
+
+/// A class linked to another one by a relationship in either direction
+#[derive(Debug, Clone, GraphQLObject)]
+pub struct Neighbor {
+    class: String,
+    predicate: String,
+    /// Whether the relationship goes from the queried class to this one
+    outgoing: bool,
+}
+
+/// Sizes of the summary
+#[derive(Debug, Clone, GraphQLObject)]
+pub struct Counts {
+    classes: i32,
+    relationships: i32,
+    instances: i32,
+}
+
+/// The class summary queries are answered from, taken after every rebuild
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    classes: Vec<Class>,
+    relationships: Vec<Relationship>,
+}
+
+impl juniper::Context for Summary {}
+
+/// GraphQL counts are 32-bit
+fn int(count: usize) -> i32 {
+    i32::try_from(count).unwrap_or(i32::MAX)
+}
+
+impl Summary {
+    pub fn new(v: &Visualizer) -> Self {
+        let g = v.graph();
+        let mut classes: Vec<Class> = g
+            .node_weights()
+            .map(|label| Class {
+                label: label.to_string(),
+                iri: v.node_iris().get(label).map(|iri| iri.to_string()),
+                color: v.colors().get(label).cloned(),
+                instance_count: v.instance_counts().get(&**label).copied().map(int),
+            })
+            .collect();
+        classes.sort_by(|a, b| a.label.cmp(&b.label));
+        let mut relationships: Vec<Relationship> = g
+            .edge_references()
+            .map(|edge| Relationship {
+                from: g[edge.source()].to_string(),
+                predicate: edge.weight().to_string(),
+                predicate_iri: v.edge_predicates().get(&edge.id()).map(|iri| iri.to_string()),
+                to: g[edge.target()].to_string(),
+                count: v.edge_counts().get(&edge.id()).copied().map(int),
+            })
+            .collect();
+        relationships.sort_by(|a, b| (&a.from, &a.predicate, &a.to).cmp(&(&b.from, &b.predicate, &b.to)));
+        Summary { classes, relationships }
+    }
+
+    /// The class with this label or IRI
+    fn find(&self, name: &str) -> Option<&Class> {
+        self.classes
+            .iter()
+            .find(|class| class.label == name || class.iri.as_deref() == Some(name))
+    }
+}
+
+pub struct Query;
+
+#[graphql_object(context = Summary)]
+impl Query {
+    /// Every class of the summary, by label
+    fn classes(context: &Summary) -> Vec<Class> {
+        context.classes.clone()
+    }
+
+    /// The class with this label or IRI
+    fn class(context: &Summary, name: String) -> Option<Class> {
+        context.find(&name).cloned()
+    }
+
+    /// Every relationship, or only those with this predicate label or IRI
+    fn relationships(context: &Summary, predicate: Option<String>) -> Vec<Relationship> {
+        context
+            .relationships
+            .iter()
+            .filter(|relationship| {
+                predicate.as_ref().is_none_or(|predicate| {
+                    relationship.predicate == *predicate || relationship.predicate_iri.as_ref() == Some(predicate)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Numbers of classes, relationships and instances
+    fn counts(context: &Summary) -> Counts {
+        Counts {
+            classes: int(context.classes.len()),
+            relationships: int(context.relationships.len()),
+            instances: context.classes.iter().filter_map(|class| class.instance_count).fold(0, i32::saturating_add),
+        }
+    }
+
+    /// The classes linked to the class with this label or IRI, in either direction
+    fn neighbors(context: &Summary, class: String) -> Vec<Neighbor> {
+        let Some(label) = context.find(&class).map(|class| &class.label) else {
+            return Vec::new();
+        };
+        context
+            .relationships
+            .iter()
+            .flat_map(|relationship| {
+                let outgoing = (relationship.from == *label).then(|| Neighbor {
+                    class: relationship.to.clone(),
+                    predicate: relationship.predicate.clone(),
+                    outgoing: true,
+                });
+                let incoming = (relationship.to == *label).then(|| Neighbor {
+                    class: relationship.from.clone(),
+                    predicate: relationship.predicate.clone(),
+                    outgoing: false,
+                });
+                outgoing.into_iter().chain(incoming)
+            })
+            .collect()
+    }
+}
+
+pub type Schema = RootNode<'static, Query, EmptyMutation<Summary>, EmptySubscription<Summary>>;
+
+pub fn schema() -> Schema {
+    Schema::new(Query, EmptyMutation::new(), EmptySubscription::new())
+}
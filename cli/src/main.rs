@@ -1,50 +1,1550 @@
-use anyhow::Result;
-use std::collections::HashMap;
-use oxigraph::io::GraphFormat;
-use rdf_class_viz::Visualizer;
-use std::env;
+mod config;
+mod diagnostics;
+mod graphql;
+mod preset;
+mod progress;
+mod serve;
+#[cfg(feature = "tui")]
+mod tui;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use config::Config;
+use diagnostics::{Diagnostics, EmptyGraph};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use oxigraph::io::{DatasetFormat, GraphFormat};
+use oxigraph::model::NamedNode;
+use oxigraph::store::Store;
+use preset::Preset;
+use progress::ProgressBars;
+use rdf_class_viz::regex::Regex;
+use rdf_class_viz::{write_trig, Capabilities, ClassDeclaration, ColorMap, D2Layout, Direction, EdgeStyle, Engine, FilterContext, FilterLang, GraphCache, GraphSelection, GraphSource, ImageFormat, LabelPolicy, LabelStyle, Manifest, MultiTypePolicy, NodeRank, NodeShape, NodeStyle, ParsedGraph, SparqlFilter, StyleSheet, TermCharset, Theme, Visualizer, TRIVIAL_CLASSES};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+/// Summarize the classes and relationships of an RDF graph as a d2 diagram
+#[derive(Parser, Default)]
+#[command(subcommand_negates_reqs = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Ontology files followed by the data graph file (only ontologies with --schema)
+    #[arg(required_unless_present_any = ["capabilities", "preset"], num_args = 1.., value_name = "FILES")]
+    files: Vec<PathBuf>,
+
+    /// Load a well-known ontology before FILES and color by its top-level classes: brick,
+    /// brick@1.4, brick@1.3 or s223. Downloaded with curl on first use and cached under
+    /// $XDG_CACHE_HOME/rdfviz; repeatable
+    #[arg(long, value_name = "NAME[@VERSION]")]
+    preset: Vec<Preset>,
+
+    /// Print the supported input/output formats and features as JSON and exit
+    #[arg(long)]
+    capabilities: bool,
+
+    /// Draw edges for OWL restrictions (owl:someValuesFrom, owl:allValuesFrom, qualified
+    /// cardinalities) labeled with the restriction type
+    #[arg(long)]
+    restrictions: bool,
+
+    /// Draw the ontology's schema from rdfs:domain/rdfs:range alone; every file is an ontology
+    #[arg(long)]
+    schema: bool,
+
+    /// Draw SHACL node shapes, with sh:path edges to their sh:class/sh:node; every file is a
+    /// shapes graph or ontology
+    #[arg(long, conflicts_with = "schema")]
+    shapes: bool,
+
+    /// Summarize numeric datatype properties (min/avg/max) on each class node
+    #[arg(long)]
+    literals: bool,
+
+    /// List the datatype properties used by each class inside its node, UML style
+    #[arg(long)]
+    attributes: bool,
+
+    /// Draw dashed shortcut edges implied by owl:propertyChainAxiom definitions
+    #[arg(long)]
+    property_chains: bool,
+
+    /// Label each class with its category (e.g. «Equipment») so it reads without color
+    #[arg(long)]
+    category_chips: bool,
+
+    /// Only summarize these named graphs of an N-Quads/TriG data file, along with its default
+    /// graph (all of them by default); repeatable
+    #[arg(long, value_name = "IRI")]
+    graph: Vec<String>,
+
+    /// Also write one d2 summary per named graph of the data file into this directory
+    #[arg(long, value_name = "DIR")]
+    per_graph: Option<PathBuf>,
+
+    /// Also write one d2 summary per connected component of the class graph into this
+    /// directory, largest first: component-1.d2, component-2.d2, ...
+    #[arg(long, value_name = "DIR")]
+    per_component: Option<PathBuf>,
+
+    /// Draw the types of instances that no ontology declares as a class as dashed "unknown
+    /// class" nodes instead of only warning about them
+    #[arg(long)]
+    unknown_classes: bool,
+
+    /// Color each class by the ontology file that declares it, with the file name in its label
+    #[arg(long)]
+    provenance: bool,
+
+    /// Built-in color map to color classes by: brick (the default), s223, sosa or prov;
+    /// repeatable to combine several
+    #[arg(long, value_name = "NAME")]
+    colors: Vec<ColorMap>,
+
+    /// Palette for the background, default node fill, fonts and lines of every output: light,
+    /// dark or colorblind. The built-in color maps take the theme's category colors;
+    /// --class-style still applies on top
+    #[arg(long, value_name = "THEME")]
+    theme: Option<Theme>,
+
+    /// Color each class by the hue of its category (or topmost superclass), lighter with every
+    /// subclass level below it
+    #[arg(long, conflicts_with = "provenance")]
+    depth_colors: bool,
+
+    /// Also shape classes by their category in the color maps, e.g. Brick points as circles,
+    /// equipment as rectangles and locations as houses
+    #[arg(long)]
+    category_shapes: bool,
+
+    /// Draw edges thicker the more instance pairs support them, on a log scale
+    #[arg(long)]
+    edge_widths: bool,
+
+    /// Write class labels larger the more instances the class has, on a log scale from MIN to
+    /// MAX points (12:32 without a value)
+    #[arg(long, value_name = "MIN:MAX", num_args = 0..=1, default_missing_value = "12:32", value_parser = parse_size_range)]
+    node_sizes: Option<(f64, f64)>,
+
+    /// Show each class's full IRI and skos:definition (or rdfs:comment) when hovering its node
+    /// in the SVG output
+    #[arg(long)]
+    tooltips: bool,
+
+    /// Link every class node to its IRI, or to the URL this template gives for it: {iri},
+    /// {local} and {curie} stand for the class, e.g.
+    /// 'https://ontology.brickschema.org/brick/{local}.html'
+    #[arg(long, value_name = "TEMPLATE", num_args = 0..=1, default_missing_value = "{iri}")]
+    links: Option<String>,
+
+    /// Predicate label style: prefixed, curie, local or human
+    #[arg(long, default_value = "prefixed")]
+    labels: LabelStyle,
+
+    /// Shorten class labels: a comma-separated list of strip, truncate=N and wrap[=WIDTH],
+    /// e.g. strip,wrap=20. Prefix it with d2=, dot= or builtin= to only shorten the labels of
+    /// that renderer; repeatable
+    #[arg(long, value_name = "[ENGINE=]POLICY", value_parser = parse_node_labels)]
+    node_labels: Vec<(Option<Engine>, LabelPolicy)>,
+
+    /// Override the style of a class and its subclasses, e.g.
+    /// 'https://brickschema.org/schema/Brick#AHU=fill:SkyBlue,stroke:Navy,shape:hexagon'
+    #[arg(long, value_name = "IRI=STYLE", value_parser = parse_class_style)]
+    class_style: Vec<(String, NodeStyle)>,
+
+    /// Style the edges of a predicate, or of every predicate in a namespace, e.g.
+    /// 'brick:feeds=stroke:blue' or 'brick:hasPoint=stroke:gray,line:dashed'
+    #[arg(long, value_name = "IRI=STYLE", value_parser = parse_edge_style)]
+    edge_style: Vec<(String, EdgeStyle)>,
+
+    /// Style file with one `class IRI STYLE` or `edge IRI STYLE` rule per line; --class-style
+    /// and --edge-style apply on top of it
+    #[arg(long, value_name = "PATH")]
+    style_file: Option<PathBuf>,
+
+    /// Render an image (svg or png) to stdout instead of printing d2 text
+    #[arg(long, value_name = "FORMAT")]
+    render: Option<ImageFormat>,
+
+    /// Write the d2 text (or the --render image) to this file instead of stdout
+    #[arg(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Keep running and rebuild every output whenever an input file changes
+    #[arg(long)]
+    watch: bool,
+
+    /// Direction of the DOT layout: TB, LR, BT or RL
+    #[arg(long, value_name = "DIR", value_parser = ["TB", "LR", "BT", "RL"])]
+    rankdir: Option<String>,
+
+    /// How DOT draws edges: spline, ortho, polyline, line, curved or none
+    #[arg(long, value_name = "KIND", value_parser = ["spline", "ortho", "polyline", "line", "curved", "none"])]
+    splines: Option<String>,
+
+    /// Minimum space between two DOT nodes of the same rank, in inches
+    #[arg(long, value_name = "INCHES")]
+    nodesep: Option<f64>,
+
+    /// Set any other Graphviz graph attribute of the DOT output; repeatable
+    #[arg(long, value_name = "NAME=VALUE", value_parser = parse_dot_attr)]
+    dot_attr: Vec<(String, String)>,
+
+    /// Font family of the labels in the DOT output and the builtin engine's SVG
+    #[arg(long, value_name = "FAMILY")]
+    font: Option<String>,
+
+    /// Layout engine of the D2 output: elk, dagre or tala
+    #[arg(long, value_name = "ENGINE")]
+    d2_layout: Option<D2Layout>,
+
+    /// Id of the D2 theme the D2 output is rendered with, e.g. 200
+    #[arg(long, value_name = "ID")]
+    d2_theme: Option<u32>,
+
+    /// Render the D2 output in D2's hand-drawn sketch mode
+    #[arg(long)]
+    sketch: bool,
+
+    /// Direction the D2 output is laid out in: up, down, left or right
+    #[arg(long, value_name = "DIR")]
+    direction: Option<Direction>,
+
+    /// Layout engine used by --render: d2, dot or builtin
+    #[arg(long, default_value = "d2")]
+    engine: Engine,
+
+    /// SHACL validation report for the data graph; classes with violations get a red border and
+    /// a violation count
+    #[arg(long, value_name = "PATH")]
+    validation_report: Option<PathBuf>,
+
+    /// Only draw classes in this namespace (an IRI prefix, or brick/rdf/owl); repeatable
+    #[arg(long, value_name = "NAMESPACE")]
+    allow_ns: Vec<String>,
+
+    /// Drop classes in this namespace (an IRI prefix, or brick/rdf/owl); repeatable
+    #[arg(long, value_name = "NAMESPACE")]
+    deny_ns: Vec<String>,
+
+    /// SPARQL ASK query over {from}, {to} and {p}, or with --filter-lang rhai a script defining
+    /// `fn filter(from, to, edge)` or `fn filter(from, to, edge, ctx)`; only edges for which it
+    /// holds are drawn
+    #[arg(long, value_name = "SCRIPT")]
+    filter: Option<PathBuf>,
+
+    /// Language of the --filter script: sparql, or rhai in builds with the rhai feature
+    #[arg(long, value_name = "LANG", default_value = "sparql", requires = "filter")]
+    filter_lang: FilterLang,
+
+    /// Drop edges supported by fewer than this many instance pairs
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    min_edge_count: usize,
+
+    /// What makes a resource a class: owl (owl:Class), rdfs (also rdfs:Class), shapes (also
+    /// the sh:targetClass of a shape) or any (anything used as an rdf:type)
+    #[arg(long, value_name = "KIND", default_value = "owl")]
+    classes: ClassDeclaration,
+
+    /// Which types of an instance with several types to summarize it under: all,
+    /// most-specific or prefer:NS[,NS...]
+    #[arg(long, value_name = "POLICY", default_value = "all")]
+    multi_type: MultiTypePolicy,
+
+    /// Drop owl:Thing, owl:NamedIndividual and the other trivial classes from both ends of
+    /// every edge (the default)
+    #[arg(long, overrides_with = "keep_trivial_classes")]
+    no_trivial_classes: bool,
+
+    /// Draw the built-in trivial classes after all; --trivial-class still applies
+    #[arg(long, overrides_with = "no_trivial_classes")]
+    keep_trivial_classes: bool,
+
+    /// Also treat this class IRI as trivial; repeatable
+    #[arg(long, value_name = "IRI")]
+    trivial_class: Vec<String>,
+
+    /// Drop classes that are left without any edge after filtering
+    #[arg(long)]
+    prune_isolated: bool,
+
+    /// Draw the edges on a cycle of a single predicate (e.g. a brick:feeds loop) in red
+    #[arg(long)]
+    highlight_cycles: bool,
+
+    /// Write the degree and betweenness of every class into its node label
+    #[arg(long)]
+    metrics: bool,
+
+    /// Add a legend of the namespaces and predicates in the diagram to the D2, DOT and SVG
+    /// outputs
+    #[arg(long)]
+    legend: bool,
+
+    /// Only draw edges whose predicate IRI matches this regex; repeatable
+    #[arg(long, value_name = "REGEX")]
+    keep_edge: Vec<Regex>,
+
+    /// Drop edges whose predicate IRI matches this regex, e.g. 'hasTag|hasUnit'; repeatable
+    #[arg(long, value_name = "REGEX")]
+    drop_edge: Vec<Regex>,
+
+    /// Only draw the classes within --hops edges of this class IRI
+    #[arg(long, value_name = "IRI")]
+    focus: Option<String>,
+
+    /// Number of edges to follow from the --focus class
+    #[arg(long, default_value_t = 1, requires = "focus")]
+    hops: usize,
+
+    /// Collapse the subclasses of this class IRI below --rollup-depth into their ancestor at
+    /// that depth
+    #[arg(long, value_name = "IRI")]
+    rollup: Option<String>,
+
+    /// Number of subclass levels below the --rollup class to keep
+    #[arg(long, default_value_t = 1, requires = "rollup")]
+    rollup_depth: usize,
+
+    /// Keep only the N highest ranked classes (see --rank-by) and merge the rest into one
+    /// "other" node
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<usize>,
+
+    /// What --max-nodes ranks classes by: instances or degree
+    #[arg(long, default_value = "instances", requires = "max_nodes")]
+    rank_by: NodeRank,
+
+    /// Draw every edge with the most general rdfs:subPropertyOf ancestor of its predicate
+    #[arg(long)]
+    rollup_properties: bool,
+
+    /// Draw only one direction of owl:inverseOf property pairs, e.g. feeds but not isFedBy
+    #[arg(long)]
+    dedup_inverses: bool,
+
+    /// Draw one edge per pair of classes, with every predicate between them in its label
+    #[arg(long)]
+    aggregate_edges: bool,
+
+    /// Draw literal-valued predicates as edges to a node per datatype, e.g. xsd_float
+    #[arg(long)]
+    datatype_nodes: bool,
+
+    /// Attach RDF-star and rdf:Statement annotations of the data to the edges in the JSON
+    /// and GraphML exports
+    #[arg(long)]
+    annotations: bool,
+
+    /// Skip sorting nodes and edges by label; faster on huge graphs, but the order of every
+    /// output then changes between runs
+    #[arg(long)]
+    unsorted: bool,
+
+    /// Report triples loaded, queries executed, peak memory and time per stage on stderr
+    #[arg(long)]
+    usage: bool,
+
+    /// Show progress bars on stderr while files load (triples, rate), each graph building stage
+    /// runs its queries (rows processed) and images render
+    #[arg(long)]
+    progress: bool,
+
+    /// Give up on any query that runs longer than this many seconds, e.g. on a data graph
+    /// whose extraction query explodes. A query given up on keeps running in the background
+    /// until its next row; at most 8 run at once and a query waits for one to finish
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    query_timeout: Option<Duration>,
+
+    /// Give up on any query that returns more than N rows
+    #[arg(long, value_name = "N")]
+    max_results: Option<usize>,
+
+    /// Log what each stage did on stderr: `-v` the triples loaded and edges found per stage,
+    /// `-vv` also every dropped edge and why, `-vvv` also every query. `RUST_LOG` overrides it
+    #[arg(long, short, action = clap::ArgAction::Count)]
+    verbose: u8,
 
-pub fn main() -> Result<()> {
-    // Get command-line arguments
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        eprintln!(
-            "Usage: {} <ontology_file1> <ontology_file2> ... <graph_filename>",
-            args[0]
-        );
-        std::process::exit(1);
+    /// Only log errors, not warnings or the files written
+    #[arg(long, short, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// How warnings about the class graph and the final error are reported: `text` in the
+    /// log, or `json`, one object per line on stderr. The exit code is 2 for parse errors, 3
+    /// for an empty class graph, 4 for an invalid --filter script, 5 for a query over
+    /// --query-timeout or --max-results, 64 for invalid arguments and 1 for anything else
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    diagnostics: Diagnostics,
+
+    /// Also export the class graph as a named graph of a TriG file
+    #[arg(long, value_name = "PATH")]
+    trig: Option<PathBuf>,
+
+    /// Also export the class graph as GEXF for Gephi
+    #[arg(long, value_name = "PATH")]
+    gexf: Option<PathBuf>,
+
+    /// Also stream the class graph as GraphML
+    #[arg(long, value_name = "PATH")]
+    graphml: Option<PathBuf>,
+
+    /// Also stream the class graph as JSON (`version`, `nodes` and `edges`; the ClassGraphJson
+    /// schema of the library), same as `--out json=PATH`
+    #[arg(long, value_name = "PATH")]
+    json: Option<PathBuf>,
+
+    /// Also export the class graph as VOWL JSON for WebVOWL
+    #[arg(long, value_name = "PATH")]
+    vowl: Option<PathBuf>,
+
+    /// Also write nodes.csv and edges.csv into this directory
+    #[arg(long, value_name = "DIR")]
+    out_csv: Option<PathBuf>,
+
+    /// Also write the class graph as FORMAT to PATH; repeatable, and every output comes from
+    /// the same graph. FORMAT is d2, dot, json, graphml, gexf, vowl, void, cypher, graphson,
+    /// drawio, mermaid, matrix-csv, matrix-html (the class-to-class adjacency matrix), term or
+    /// term-ascii (a text rendering for the terminal), trig, csv (PATH is then a directory), or
+    /// svg or png (laid out by --engine). PATH `-` is stdout, which then carries that output
+    /// instead of the d2 text; `term` and `term-ascii` default to it
+    #[arg(long, value_name = "FORMAT=PATH", value_parser = parse_out)]
+    out: Vec<(OutFormat, PathBuf)>,
+
+    /// Save what was extracted from the data graph (edge rows, instance counts) to this file,
+    /// to rebuild the graph later with --from-cache
+    #[arg(long, value_name = "PATH")]
+    save_cache: Option<PathBuf>,
+
+    /// Build the graph from a file written by --save-cache instead of a data graph; FILES are
+    /// then only the ontologies, so colors, styles and filters can change without reloading
+    /// the data
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["schema", "shapes"])]
+    from_cache: Option<PathBuf>,
+
+    /// Write a JSON manifest of every artifact produced (paths, formats, hashes, counts)
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the whole pipeline described by a viz.toml file: its ontologies, data, prefixes,
+    /// filters, styles, rollup and outputs. Relative paths in the file are relative to it.
+    Run {
+        /// The viz.toml file
+        #[arg(value_name = "CONFIG")]
+        config: PathBuf,
+    },
+    /// Render a bundled miniature building model into every output format, to check the
+    /// installation and see what each output looks like
+    Demo {
+        /// Directory the sample inputs and all outputs are written to
+        #[arg(default_value = "rdfviz-demo")]
+        out: PathBuf,
+    },
+    /// Load every input and build the class graph without writing any output, then print the
+    /// number of classes and edges and the warnings. Fails if an input does not parse or the
+    /// graph is empty, e.g. in CI. Options go before `check`.
+    Check {
+        /// Ontology files followed by the data graph file (only ontologies with --schema)
+        #[arg(num_args = 1.., required = true, value_name = "FILES")]
+        files: Vec<PathBuf>,
+
+        /// Fail on warnings too: deprecated or undeclared classes
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Write a Markdown review report instead of the diagram: the class graph as an embedded
+    /// Mermaid diagram, the warnings and the `stats` tables, to stdout or --output. Options go
+    /// before `report`.
+    Report {
+        /// Ontology files followed by the data graph file (only ontologies with --schema)
+        #[arg(num_args = 1.., required = true, value_name = "FILES")]
+        files: Vec<PathBuf>,
+
+        /// Heading of the report
+        #[arg(long, default_value = "Model review")]
+        title: String,
+    },
+    /// Draw only the shortest relationship paths between two classes, following edges in
+    /// either direction, e.g. to see how an AHU relates to a Zone. Writes the same outputs as
+    /// a normal run. Options go before `path`.
+    Path {
+        /// Ontology files followed by the data graph file (only ontologies with --schema)
+        #[arg(num_args = 1.., required = true, value_name = "FILES")]
+        files: Vec<PathBuf>,
+
+        /// Class the paths start at, as an IRI or a CURIE like brick:AHU
+        #[arg(long, value_name = "CLASS")]
+        from: String,
+
+        /// Class the paths end at, as an IRI or a CURIE like brick:Zone
+        #[arg(long, value_name = "CLASS")]
+        to: String,
+    },
+    /// Explore the class graph in the terminal: browse and search classes, list their
+    /// neighbors, hide predicates and pick a selection, then export it to d2 (to --output, or
+    /// selection.d2). Needs a build with --features tui. Options go before `tui`.
+    Tui {
+        /// Ontology files followed by the data graph file (only ontologies with --schema)
+        #[arg(num_args = 1.., required = true, value_name = "FILES")]
+        files: Vec<PathBuf>,
+    },
+    /// Compare two versions of a data graph: edges only in the new one are drawn green, edges
+    /// only in the old one red and unchanged edges dimmed. Options go before `diff`.
+    Diff {
+        /// Ontology files followed by the old and the new data graph (or ontology version)
+        #[arg(num_args = 3.., required = true, value_name = "FILES")]
+        files: Vec<PathBuf>,
+
+        /// Compare two ontology versions instead: their schema graphs (or shapes with
+        /// --shapes), with added, removed and renamed classes outlined. Every file before the
+        /// two versions is a shared import.
+        #[arg(long)]
+        ontologies: bool,
+    },
+    /// Serve an HTTP API rendering posted data graphs against the ontologies (POST
+    /// /render?format=svg|png|d2|dot|json) and an interactive page at /view; with --data, also
+    /// the diagram of that data graph at /live, a GraphQL API of its summary at /graphql and a
+    /// read-only SPARQL endpoint over its ontologies and data at /sparql. Options go before
+    /// `serve`.
+    Serve {
+        /// Ontology files every posted data graph is summarized against
+        #[arg(value_name = "ONTOLOGIES")]
+        ontologies: Vec<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+
+        /// Also summarize this data graph at startup, for its diagram at /live, GraphQL queries
+        /// of its summary at /graphql and SPARQL queries at /sparql
+        #[arg(long, value_name = "DATA")]
+        data: Option<PathBuf>,
+
+        /// Rebuild the --data summary whenever it or an ontology changes, pushing the new
+        /// diagram to every open /live page over a websocket
+        #[arg(long, requires = "data")]
+        watch: bool,
+    },
+    /// Report instances per class, triples per predicate, classes used but not defined, orphan
+    /// instances, the degree and betweenness of every class and the cycles of the class graph
+    /// instead of drawing the graph.
+    /// Options go before `stats`.
+    Stats {
+        /// Ontology files (unless given by --preset) followed by the data graph
+        #[arg(num_args = 1.., required = true, value_name = "FILES")]
+        files: Vec<PathBuf>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+static DEMO_ONTOLOGY: &str = include_str!("../demo/ontology.ttl");
+static DEMO_BUILDING: &str = include_str!("../demo/building.ttl");
+
+/// Guesses the RDF format from the file extension, defaulting to Turtle
+fn graph_format(path: &Path) -> GraphFormat {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(GraphFormat::from_extension)
+        .unwrap_or(GraphFormat::Turtle)
+}
+
+/// The dataset format of N-Quads and TriG files, which may hold named graphs
+fn dataset_format(path: &Path) -> Option<DatasetFormat> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(DatasetFormat::from_extension)
+}
+
+/// File name of the per-graph summary of `graph`, keeping only the characters legal everywhere
+fn graph_file_name(graph: &NamedNode) -> String {
+    let name: String = graph
+        .as_str()
+        .split_once("://")
+        .map_or(graph.as_str(), |(_, rest)| rest)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    format!("{}.d2", name.trim_matches('_'))
+}
+
+/// Formats `--out` can write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutFormat {
+    D2,
+    Dot,
+    Json,
+    Graphml,
+    Gexf,
+    Vowl,
+    Void,
+    Cypher,
+    Graphson,
+    Drawio,
+    Mermaid,
+    MatrixCsv,
+    MatrixHtml,
+    Term(TermCharset),
+    Trig,
+    Csv,
+    Image(ImageFormat),
+}
+
+fn parse_out(s: &str) -> std::result::Result<(OutFormat, PathBuf), String> {
+    let (format, path) = match s.split_once('=') {
+        Some((format, path)) => (format, path),
+        // the terminal rendering is meant to be read right away
+        None if s.starts_with("term") => (s, "-"),
+        None => return Err(format!("expected FORMAT=PATH, got '{}'", s)),
+    };
+    let format = match format {
+        "d2" => OutFormat::D2,
+        "dot" => OutFormat::Dot,
+        "json" => OutFormat::Json,
+        "graphml" => OutFormat::Graphml,
+        "gexf" => OutFormat::Gexf,
+        "vowl" => OutFormat::Vowl,
+        "void" => OutFormat::Void,
+        "cypher" => OutFormat::Cypher,
+        "graphson" => OutFormat::Graphson,
+        "drawio" => OutFormat::Drawio,
+        "mermaid" => OutFormat::Mermaid,
+        "matrix-csv" => OutFormat::MatrixCsv,
+        "matrix-html" => OutFormat::MatrixHtml,
+        "term" => OutFormat::Term(TermCharset::Unicode),
+        "term-ascii" => OutFormat::Term(TermCharset::Ascii),
+        "trig" => OutFormat::Trig,
+        "csv" => OutFormat::Csv,
+        "svg" | "png" => OutFormat::Image(format.parse()?),
+        _ => {
+            return Err(format!(
+                "unknown output format '{}', expected one of d2, dot, json, graphml, gexf, vowl, void, cypher, graphson, drawio, mermaid, matrix-csv, matrix-html, term, term-ascii, trig, csv, svg, png",
+                format
+            ))
+        }
+    };
+    Ok((format, PathBuf::from(path)))
+}
+
+fn parse_size_range(s: &str) -> std::result::Result<(f64, f64), String> {
+    let (min, max) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected MIN:MAX, got '{}'", s))?;
+    let parse = |size: &str| size.trim().parse::<f64>().map_err(|e| format!("invalid size '{}': {}", size, e));
+    let (min, max) = (parse(min)?, parse(max)?);
+    if min <= 0.0 || max < min {
+        return Err(format!("expected 0 < MIN <= MAX, got '{}'", s));
     }
+    Ok((min, max))
+}
 
-    // Extract the graph filename
-    let graph_filename = args.last().unwrap();
+fn parse_node_labels(s: &str) -> std::result::Result<(Option<Engine>, LabelPolicy), String> {
+    // `truncate=N` also holds a '=', so only take the part before it as an engine if it is one
+    match s.split_once('=') {
+        Some((engine, policy)) if engine.parse::<Engine>().is_ok() => {
+            Ok((Some(engine.parse()?), policy.parse()?))
+        }
+        _ => Ok((None, s.parse()?)),
+    }
+}
 
-    let filter = |from: &str, to: &str, edge: &str| {
-        true
+fn parse_dot_attr(s: &str) -> std::result::Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", s))?;
+    Ok((name.to_owned(), value.to_owned()))
+}
+
+fn parse_class_style(s: &str) -> std::result::Result<(String, NodeStyle), String> {
+    let (class, style) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected IRI=STYLE, got '{}'", s))?;
+    Ok((class.to_owned(), style.parse()?))
+}
+
+fn parse_seconds(s: &str) -> std::result::Result<Duration, String> {
+    s.parse()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("expected a number of seconds, got '{}'", s))
+}
+
+fn parse_edge_style(s: &str) -> std::result::Result<(String, EdgeStyle), String> {
+    let (predicate, style) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected IRI=STYLE, got '{}'", s))?;
+    Ok((predicate.to_owned(), style.parse()?))
+}
+
+pub fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::from(diagnostics::usage_failure(&e));
+        }
+    };
+    init_logging(&args);
+    match dispatch(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        // the reader of a pipeline like `| head` has all it wanted
+        Err(e) if is_broken_pipe(&e) => ExitCode::SUCCESS,
+        Err(e) => args.diagnostics.fail(&e),
+    }
+}
+
+/// Runs the command picked by `args`
+fn dispatch(args: &Args) -> Result<()> {
+    if args.capabilities {
+        print_stdout(format!("{}\n", Capabilities::to_json()).as_bytes())?;
+        return Ok(());
+    }
+    if let Some(Command::Demo { out }) = &args.command {
+        return demo(out);
+    }
+    if let Some(Command::Run { config }) = &args.command {
+        return run_config(config);
+    }
+    if let Some(Command::Check { deny_warnings, .. }) = &args.command {
+        return check(args, *deny_warnings);
+    }
+    if let Some(Command::Report { title, .. }) = &args.command {
+        return report(args, title);
+    }
+    if let Some(Command::Tui { .. }) = &args.command {
+        return explore(args);
+    }
+    if let Some(Command::Stats { files, json }) = &args.command {
+        return stats(args, files, *json);
+    }
+    if let Some(Command::Serve { ontologies, addr, data, watch }) = &args.command {
+        return serve::serve(args, addr, &with_presets(args, ontologies)?, data.as_deref(), *watch);
+    }
+
+    run_and_watch(args)
+}
+
+/// Builds the class graph and writes its outputs, then with `--watch` keeps doing so whenever
+/// an input changes
+fn run_and_watch(args: &Args) -> Result<()> {
+    match run(args) {
+        // the next save may well fill an empty graph, so keep watching
+        Err(e) if args.watch && e.is::<EmptyGraph>() => args.diagnostics.warn("empty-graph", None, &e.to_string()),
+        ran => ran?,
+    }
+    if args.watch {
+        watch(args)?;
+    }
+    Ok(())
+}
+
+/// Logs to stderr at the level picked by `--verbose` and `--quiet`; the files written are
+/// logged by default, the library's own progress only with `--verbose`
+fn init_logging(args: &Args) {
+    let filter = match (args.quiet, args.verbose) {
+        (true, _) => "error".to_owned(),
+        (false, 0) => "warn,rdfviz=info".to_owned(),
+        (false, verbose) => {
+            let level = ["info", "debug", "trace"][usize::from(verbose.min(3)) - 1];
+            format!("warn,rdfviz={0},rdf_class_viz={0}", level)
+        }
     };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(filter));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(false)
+        .without_time()
+        .init();
+}
 
-    let color_map_defn = vec![
-        ("https://brickschema.org/schema/Brick#Location", "LightCoral"),
-        ("https://brickschema.org/schema/Brick#Point", "Gold"),
-        ("https://brickschema.org/schema/Brick#Equipment", "#32BF84"),
+/// Logs a warning for every deprecated class in the graph and every undeclared class, and
+/// returns how many there were
+fn warn_about(args: &Args, v: &Visualizer) -> usize {
+    let diagnostics = args.diagnostics;
+    let mut deprecated: Vec<_> = v.deprecations().iter().collect();
+    deprecated.sort_by_key(|(class, _)| *class);
+    for (class, deprecation) in deprecated.iter() {
+        diagnostics.warn("deprecated-class", Some(class), &deprecation.suggestion(class));
+    }
+    for (class, count) in v.undefined_classes() {
+        let noun = if *count == 1 { "instance" } else { "instances" };
+        let message = format!("{} is the type of {} {} but no ontology declares it as a class", class, count, noun);
+        diagnostics.warn("undeclared-class", Some(class), &message);
+    }
+    // classes without a single edge usually mean the data graph uses none of the ontology's
+    // predicates
+    let graph = v.graph();
+    let degenerate = graph.node_count() > 0 && graph.edge_count() == 0;
+    if degenerate {
+        let message = format!("the class graph has {} classes but no edges", graph.node_count());
+        diagnostics.warn("no-edges", None, &message);
+    }
+    deprecated.len() + v.undefined_classes().len() + usize::from(degenerate)
+}
+
+/// Builds the class graph of the `check` files and prints its size, failing on an empty graph
+/// and, with `deny_warnings`, on warnings
+fn check(args: &Args, deny_warnings: bool) -> Result<()> {
+    let v = build(args)?;
+    let warnings = warn_about(args, &v);
+    let graph = v.graph();
+    let summary = format!(
+        "{} classes, {} edges from {} triples, {} warning{}\n",
+        graph.node_count(),
+        graph.edge_count(),
+        v.usage().triples,
+        warnings,
+        if warnings == 1 { "" } else { "s" }
+    );
+    print_stdout(summary.as_bytes())?;
+    if graph.node_count() == 0 {
+        return Err(EmptyGraph.into());
+    }
+    if deny_warnings && warnings > 0 {
+        bail!("{} warning(s) with --deny-warnings", warnings);
+    }
+    Ok(())
+}
+
+/// Builds the class graph of the `report` files and writes its Markdown report
+fn report(args: &Args, title: &str) -> Result<()> {
+    let v = build(args)?;
+    let report = v.markdown_report(title)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, report)?,
+        None => print_stdout(report.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Builds the class graph of the `tui` files and opens the explorer on it
+#[cfg(feature = "tui")]
+fn explore(args: &Args) -> Result<()> {
+    let v = build(args)?;
+    let export = args.output.clone().unwrap_or_else(|| PathBuf::from("selection.d2"));
+    tui::explore(v, &export)
+}
+
+#[cfg(not(feature = "tui"))]
+fn explore(_: &Args) -> Result<()> {
+    bail!("the tui explorer is not supported by this build; rebuild with --features tui")
+}
+
+/// Builds the class graph and writes every output requested by `args`
+fn run(args: &Args) -> Result<()> {
+    let to_stdout = outputs(args).iter().filter(|(_, path)| is_stdout(path)).count();
+    if to_stdout > 1 {
+        bail!("{} outputs are sent to stdout (`-`), but it can only carry one", to_stdout);
+    }
+
+    let (mut v, d2) = load(args)?;
+    warn_about(args, &v);
+    let empty = v.graph().node_count() == 0;
+
+    let (stdout, stdout_format) = match args.render {
+        Some(format) => (v.render_with(args.engine, format)?, format.extension()),
+        None => (d2.into_bytes(), "d2"),
+    };
+    let stdout_taken = outputs(args).iter().any(|(_, path)| is_stdout(path));
+    match &args.output {
+        Some(path) => std::fs::write(path, &stdout)?,
+        None if stdout_taken => {}
+        None => print_stdout(&stdout)?,
+    }
+
+    if let Some(path) = &args.save_cache {
+        std::fs::write(path, v.graph_cache()?.to_json())?;
+    }
+
+    let exports = exports(args, &v)?;
+    for (path, _, contents) in exports.iter() {
+        if is_stdout(path) {
+            print_stdout(contents)?;
+        } else {
+            std::fs::write(path, contents)?;
+        }
+    }
+    let streamed = stream_exports(args, &v)?;
+
+    if let Some(path) = &args.manifest {
+        let mut artifacts = vec![(
+            args.output.clone().unwrap_or_else(|| PathBuf::from("-")),
+            stdout_format,
+            stdout,
+        )];
+        artifacts.extend(exports);
+        for (path, format) in streamed {
+            let contents = std::fs::read(&path)?;
+            artifacts.push((path, format, contents));
+        }
+        write_manifest(path, &v, &artifacts)?;
+    }
+
+    if args.usage {
+        eprintln!("{}", v.usage());
+    }
+
+    if let Some(dir) = &args.per_component {
+        std::fs::create_dir_all(dir)?;
+        // rebuild once per component, like --per-graph, so each file gets the whole pipeline
+        let count = v.components().len();
+        for index in 0..count {
+            let path = dir.join(format!("component-{}.d2", index + 1));
+            v = v.with_component(Some(index));
+            v.build_graph()?;
+            std::fs::write(&path, v.graph_to_d2lang()? + "\n")?;
+            info!("wrote {}", path.display());
+        }
+        v = v.with_component(None);
+    }
+
+    if let Some(dir) = &args.per_graph {
+        std::fs::create_dir_all(dir)?;
+        for graph in v.named_graphs()? {
+            let path = dir.join(graph_file_name(&graph));
+            v = v.with_graph_selection(GraphSelection::Named(vec![graph]));
+            v.build_graph()?;
+            std::fs::write(&path, v.graph_to_d2lang()? + "\n")?;
+            info!("wrote {}", path.display());
+        }
+    }
+
+    // the outputs are still written, so a script can look at them, but the run fails
+    if empty {
+        return Err(EmptyGraph.into());
+    }
+    Ok(())
+}
+
+/// Runs the pipeline of a viz.toml like the command line it stands for, from the directory of
+/// the file so that its relative paths resolve against it
+fn run_config(path: &Path) -> Result<()> {
+    let config = Config::read(path)?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::env::set_current_dir(dir).with_context(|| format!("cannot enter {}", dir.display()))?;
+    }
+    let args = Args::try_parse_from(config.to_args()?).with_context(|| format!("invalid options in {}", path.display()))?;
+    run_and_watch(&args)
+}
+
+/// Every input file of `args`: ontologies, data graphs, the validation report, the filter and
+/// the style file
+fn inputs(args: &Args) -> Vec<&Path> {
+    let files = match &args.command {
+        Some(
+            Command::Diff { files, .. }
+            | Command::Check { files, .. }
+            | Command::Path { files, .. }
+            | Command::Report { files, .. }
+            | Command::Tui { files },
+        ) => files,
+        _ => &args.files,
+    };
+    files
+        .iter()
+        .chain(args.validation_report.iter())
+        .chain(args.filter.iter())
+        .chain(args.style_file.iter())
+        .map(PathBuf::as_path)
+        .collect()
+}
+
+/// Reruns [`run`] whenever one of the input files changes
+fn watch(args: &Args) -> Result<()> {
+    watch_files(&inputs(args), || run(args))
+}
+
+/// Calls `rebuild` whenever one of `files` changes, reporting failures without exiting so a
+/// half-saved file does not end the session
+fn watch_files(files: &[&Path], mut rebuild: impl FnMut() -> Result<()>) -> Result<()> {
+    let files = files
+        .iter()
+        .map(std::fs::canonicalize)
+        .collect::<std::io::Result<HashSet<_>>>()?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    // watch the directories rather than the files, which editors often replace on save
+    let dirs: HashSet<_> = files.iter().filter_map(|file| file.parent()).collect();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    info!("watching {} input files for changes", files.len());
+
+    while let Ok(event) = rx.recv() {
+        let event = event?;
+        let changed = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event.paths.iter().any(|path| files.contains(path));
+        if !changed {
+            continue;
+        }
+        // saves arrive as bursts of events; rebuild once the burst is over
+        while rx.recv_timeout(Duration::from_millis(200)).is_ok() {}
+        match rebuild() {
+            Ok(()) => info!("rebuilt after a change to {}", event.paths[0].display()),
+            Err(e) => error!("{:#}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Writes the bundled sample inputs into `out`, then every output rendered from them
+fn demo(out: &Path) -> Result<()> {
+    std::fs::create_dir_all(out)?;
+    let ontology = out.join("ontology.ttl");
+    let building = out.join("building.ttl");
+    std::fs::write(&ontology, DEMO_ONTOLOGY)?;
+    std::fs::write(&building, DEMO_BUILDING)?;
+
+    let args = Args {
+        files: vec![ontology, building],
+        trig: Some(out.join("demo.trig")),
+        gexf: Some(out.join("demo.gexf")),
+        graphml: Some(out.join("demo.graphml")),
+        json: Some(out.join("demo.json")),
+        vowl: Some(out.join("demo.vowl.json")),
+        out_csv: Some(out.to_owned()),
+        out: vec![
+            (OutFormat::Void, out.join("demo.void.ttl")),
+            (OutFormat::Cypher, out.join("demo.cypher")),
+            (OutFormat::Graphson, out.join("demo.graphson.json")),
+            (OutFormat::Drawio, out.join("demo.drawio")),
+        ],
+        ..Default::default()
+    };
+    let (v, d2) = load(&args)?;
+
+    let mut artifacts = vec![
+        (out.join("demo.d2"), "d2", d2.into_bytes()),
+        (out.join("demo.dot"), "dot", v.graph_to_dot()?.into_bytes()),
+        (
+            out.join("demo.svg"),
+            "svg",
+            v.render_with(Engine::Builtin, ImageFormat::Svg)?,
+        ),
     ];
-    let color_map: HashMap<&str, &str> = color_map_defn.into_iter().collect();
+    // the external layout engines are optional, so only report when they are missing
+    for (name, engine) in [("d2", Engine::D2), ("dot", Engine::Dot)] {
+        match v.render_with(engine, ImageFormat::Svg) {
+            Ok(image) => artifacts.push((out.join(format!("demo-{}.svg", name)), "svg", image)),
+            Err(e) => warn!("skipping the {} engine: {}", name, e),
+        }
+    }
+    artifacts.extend(exports(&args, &v)?);
+
+    for (path, _, contents) in artifacts.iter() {
+        std::fs::write(path, contents)?;
+        info!("wrote {}", path.display());
+    }
+    for (path, format) in stream_exports(&args, &v)? {
+        info!("wrote {}", path.display());
+        let contents = std::fs::read(&path)?;
+        artifacts.push((path, format, contents));
+    }
+    let manifest = out.join("manifest.json");
+    write_manifest(&manifest, &v, &artifacts)?;
+    info!("wrote {}", manifest.display());
+    Ok(())
+}
+
+/// Loads the ontologies and the data graph in `files` and prints their usage statistics
+fn stats(args: &Args, files: &[PathBuf], json: bool) -> Result<()> {
+    let (data, ontology_files) = files.split_last().expect("clap requires the files");
+    let mut v = Visualizer::new(|_: &str, _: &str, _: &str| true, HashMap::new())?
+        .with_class_declaration(args.classes)
+        .with_trivial_classes(trivial_classes(args));
+    for ontology in parse_all(&with_presets(args, ontology_files)?)?.iter() {
+        v.add_parsed_ontology(ontology)?;
+    }
+    v.add_data(open(data)?, graph_format(data))?;
+    // the class metrics come from the class graph
+    v.build_graph()?;
+
+    let stats = v.stats()?;
+    let stats = if json { stats.to_json() } else { stats.to_string() };
+    print_stdout(format!("{}\n", stats).as_bytes())?;
+    Ok(())
+}
+
+/// The classes never drawn: the builtin trivial classes unless kept, plus --trivial-class
+fn trivial_classes(args: &Args) -> Vec<String> {
+    let defaults = TRIVIAL_CLASSES.iter().map(|class| class.to_string());
+    let extra = args.trivial_class.iter().cloned();
+    if args.keep_trivial_classes {
+        extra.collect()
+    } else {
+        defaults.chain(extra).collect()
+    }
+}
+
+/// Whether `args` draw the instance data, the schema or the shapes
+fn graph_source(args: &Args) -> GraphSource {
+    if args.schema {
+        GraphSource::Schema
+    } else if args.shapes {
+        GraphSource::Shapes
+    } else {
+        GraphSource::Instances
+    }
+}
+
+/// Creates a visualizer with every graph building option of `args`, before anything is loaded
+fn configure(args: &Args) -> Result<Visualizer<'static>> {
+    let filter = |_from: &str, _to: &str, _edge: &str| {
+        true
+    };
+
+    // presets bring the color maps of their own ontologies, --colors overrides both
+    let color_maps = match (args.colors.is_empty(), args.preset.is_empty()) {
+        (false, _) => args.colors.clone(),
+        (true, false) => args.preset.iter().map(|preset| preset.colors).collect(),
+        (true, true) => vec![ColorMap::Brick],
+    };
+    let color_map: HashMap<&str, &str> = match args.theme {
+        Some(theme) => color_maps.iter().flat_map(|colors| colors.themed(theme)).collect(),
+        None => color_maps.iter().flat_map(ColorMap::to_map).collect(),
+    };
+    let shape_map: HashMap<&str, NodeShape> = match args.category_shapes {
+        true => color_maps.iter().flat_map(ColorMap::shapes).collect(),
+        false => HashMap::new(),
+    };
 
     // Create a Visualizer
-    let mut v = Visualizer::new(filter, color_map)?;
+    let mut v = Visualizer::new(filter, color_map)?
+        .with_shape_map(shape_map)
+        .with_literals(args.literals)
+        .with_attributes(args.attributes)
+        .with_property_chains(args.property_chains)
+        .with_source(graph_source(args))
+        .with_restrictions(args.restrictions)
+        .with_category_chips(args.category_chips)
+        .with_depth_colors(args.depth_colors)
+        .with_tooltips(args.tooltips)
+        .with_d2_sketch(args.sketch)
+        .with_legend(args.legend)
+        .with_metric_annotations(args.metrics)
+        .with_cycle_highlight(args.highlight_cycles)
+        .with_provenance(args.provenance)
+        .with_unknown_class_nodes(args.unknown_classes)
+        .with_class_declaration(args.classes)
+        .with_multi_type_policy(args.multi_type.clone())
+        .with_property_rollup(args.rollup_properties)
+        .with_inverse_dedup(args.dedup_inverses)
+        .with_min_edge_count(args.min_edge_count)
+        .with_edge_widths(args.edge_widths)
+        .with_aggregate_edges(args.aggregate_edges)
+        .with_datatype_nodes(args.datatype_nodes)
+        .with_statement_annotations(args.annotations)
+        .with_prune_isolated(args.prune_isolated)
+        .with_label_style(args.labels)
+        .with_sorted_output(!args.unsorted)
+        .with_trivial_classes(trivial_classes(args));
+    if let Some(path) = &args.filter {
+        let script = std::fs::read_to_string(path)?;
+        let filter = script_filter(args.filter_lang, &script, v.store())?;
+        v = v.with_context_filter(filter);
+    }
+    for namespace in args.allow_ns.iter() {
+        v = v.allow_namespace(namespace);
+    }
+    for namespace in args.deny_ns.iter() {
+        v = v.deny_namespace(namespace);
+    }
+    for pattern in args.keep_edge.iter() {
+        v = v.keep_edges_matching(pattern.clone());
+    }
+    for pattern in args.drop_edge.iter() {
+        v = v.drop_edges_matching(pattern.clone());
+    }
+    if let Some(rankdir) = &args.rankdir {
+        v = v.with_dot_attribute("rankdir", rankdir.clone());
+    }
+    if let Some(splines) = &args.splines {
+        v = v.with_dot_attribute("splines", splines.clone());
+    }
+    if let Some(nodesep) = args.nodesep {
+        v = v.with_dot_attribute("nodesep", nodesep.to_string());
+    }
+    for (name, value) in args.dot_attr.iter() {
+        v = v.with_dot_attribute(name.clone(), value.clone());
+    }
+    if let Some(layout) = args.d2_layout {
+        v = v.with_d2_layout(layout);
+    }
+    if let Some(theme_id) = args.d2_theme {
+        v = v.with_d2_theme(theme_id);
+    }
+    if let Some(direction) = args.direction {
+        v = v.with_d2_direction(direction);
+    }
+    for (engine, policy) in args.node_labels.iter() {
+        v = match engine {
+            Some(engine) => v.with_engine_label_policy(*engine, *policy),
+            None => v.with_label_policy(*policy),
+        };
+    }
+    if let Some(font) = &args.font {
+        v = v.with_font(font.clone());
+    }
+    if let Some(theme) = args.theme {
+        v = v.with_theme(theme);
+    }
+    if let Some(template) = &args.links {
+        v = v.with_node_links(template.clone());
+    }
+    if let Some((min, max)) = args.node_sizes {
+        v = v.with_node_sizes(min, max);
+    }
+    if let Some(root) = &args.rollup {
+        v = v.with_rollup(root.clone(), args.rollup_depth);
+    }
+    if let Some(max) = args.max_nodes {
+        v = v.with_max_nodes(max, args.rank_by);
+    }
+    if let Some(Command::Path { from, to, .. }) = &args.command {
+        v = v.with_path(from.clone(), to.clone());
+    }
+    if let Some(focus) = &args.focus {
+        v = v.with_focus(focus.clone(), args.hops);
+    }
+    if let Some(path) = &args.style_file {
+        let sheet: StyleSheet = std::fs::read_to_string(path)?
+            .parse()
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("cannot load {}", path.display()))?;
+        v = v.with_style_sheet(sheet);
+    }
+    for (class, style) in args.class_style.iter() {
+        v = v.with_class_style(class.clone(), style.clone());
+    }
+    for (predicate, style) in args.edge_style.iter() {
+        v = v.with_edge_style(predicate.clone(), style.clone());
+    }
+    if args.progress {
+        v = v.with_progress_sink(ProgressBars::new());
+    }
+    if let Some(timeout) = args.query_timeout {
+        v = v.with_query_timeout(timeout);
+    }
+    if let Some(max) = args.max_results {
+        v = v.with_max_results(max);
+    }
+    Ok(v)
+}
+
+/// Number of data graphs at the end of the input files: none in the schema and shapes modes
+/// or with --from-cache
+fn data_graphs(args: &Args) -> usize {
+    if graph_source(args) == GraphSource::Instances && args.from_cache.is_none() {
+        1
+    } else {
+        0
+    }
+}
+
+/// Builds the class graph configured by `args` and returns the visualizer along with the d2
+/// text of the class graph
+fn load(args: &Args) -> Result<(Visualizer<'static>, String)> {
+    let v = build(args)?;
+    let d2 = v.graph_to_d2lang()? + "\n";
+    Ok((v, d2))
+}
+
+/// Builds the visualizer configured by `args`, loads the ontologies and the data graph and
+/// builds the class graph, without writing anything
+fn build(args: &Args) -> Result<Visualizer<'static>> {
+    // Split the data graphs off the ontology files: none in the schema and shapes modes or
+    // with --from-cache, the old and the new one for a diff
+    let (files, data_count) = match &args.command {
+        Some(Command::Diff { .. }) if args.from_cache.is_some() => bail!("diff cannot use --from-cache"),
+        Some(Command::Diff { files, .. }) => (files, 2),
+        Some(
+            Command::Check { files, .. }
+            | Command::Path { files, .. }
+            | Command::Report { files, .. }
+            | Command::Tui { files },
+        ) => {
+            (files, data_graphs(args))
+        }
+        _ => (&args.files, data_graphs(args)),
+    };
+    if files.len() < data_count || (files.len() == data_count && args.preset.is_empty()) {
+        bail!("expected ontology files followed by {} data graph(s)", data_count);
+    }
+    let (ontology_files, data_files) = files.split_at(files.len() - data_count);
+    let ontology_files = with_presets(args, ontology_files)?;
+
+    let mut v = configure(args)?;
+
+    // Parse the ontology files in parallel, then load them in order
+    let ontologies = parse_all(&ontology_files)?;
+    for (ontology_file, ontology) in ontology_files.iter().zip(ontologies.iter()) {
+        if args.provenance {
+            let source = ontology_file.file_name().unwrap_or_default().to_string_lossy();
+            v.add_parsed_ontology_from(source, ontology)
+        } else {
+            v.add_parsed_ontology(ontology)
+        }
+        .with_context(|| format!("cannot load {}", ontology_file.display()))?;
+    }
+
+    if let Some(path) = &args.validation_report {
+        v.add_validation_report(open(path)?, graph_format(path))?;
+    }
 
-    // Process ontology files
-    for ontology_file in &args[1..args.len() - 1] {
-        let f = File::open(ontology_file)?;
-        let f = BufReader::new(f);
-        v.add_ontology(f, GraphFormat::Turtle)?;
+    let graphs = args
+        .graph
+        .iter()
+        .map(|graph| NamedNode::new(graph.as_str()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    if let Some(path) = &args.from_cache {
+        let cache = GraphCache::from_json(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("cannot load {}", path.display()))?;
+        v.build_graph_from_cache(cache)?;
+        return Ok(v);
+    }
+
+    // Process the data graph(s)
+    match data_files {
+        [data] => match dataset_format(data) {
+            Some(format) => {
+                v.add_dataset(open(data)?, format)
+                    .with_context(|| format!("cannot load {}", data.display()))?;
+                v = v.with_graph_selection(if graphs.is_empty() {
+                    GraphSelection::All
+                } else {
+                    GraphSelection::Named(graphs)
+                });
+                v.build_graph()?;
+            }
+            None => {
+                v.add_data(open(data)?, graph_format(data))
+                    .with_context(|| format!("cannot load {}", data.display()))?;
+                v.build_graph()?;
+            }
+        },
+        rest => {
+            if let [old, new] = rest {
+                let (old, old_format) = (open(old)?, graph_format(old));
+                let (new, new_format) = (open(new)?, graph_format(new));
+                if matches!(args.command, Some(Command::Diff { ontologies: true, .. })) {
+                    v.diff_ontologies(old, old_format, new, new_format)?;
+                } else {
+                    v.diff(old, old_format, new, new_format)?;
+                }
+            } else {
+                v.build_graph()?;
+            }
+        }
+    }
+    Ok(v)
+}
+
+/// The ontology files of `args.preset`, downloaded if needed, followed by `ontology_files`
+fn with_presets(args: &Args, ontology_files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for preset in args.preset.iter() {
+        files.push(preset.path()?);
+    }
+    files.extend(ontology_files.iter().cloned());
+    Ok(files)
+}
+
+fn open(path: &Path) -> Result<BufReader<File>> {
+    Ok(BufReader::new(File::open(path)?))
+}
+
+/// Parses every file on its own thread, so Brick, QUDT and 223P are not parsed one after the
+/// other; the graphs come back in the order of `files`
+fn parse_all(files: &[PathBuf]) -> Result<Vec<ParsedGraph>> {
+    std::thread::scope(|scope| {
+        let parsers: Vec<_> = files
+            .iter()
+            .map(|file| {
+                scope.spawn(move || {
+                    ParsedGraph::parse(open(file)?, graph_format(file))
+                        .with_context(|| format!("cannot load {}", file.display()))
+                })
+            })
+            .collect();
+        files
+            .iter()
+            .zip(parsers)
+            .map(|(file, parser)| {
+                parser
+                    .join()
+                    .map_err(|_| anyhow!("the parser of {} panicked", file.display()))?
+            })
+            .collect()
+    })
+}
+
+/// Every extra output requested by `args`: the `--out` ones and those of the single-format
+/// flags (`--json`, `--trig`, ...)
+fn outputs(args: &Args) -> Vec<(OutFormat, PathBuf)> {
+    [
+        (OutFormat::Trig, &args.trig),
+        (OutFormat::Gexf, &args.gexf),
+        (OutFormat::Graphml, &args.graphml),
+        (OutFormat::Json, &args.json),
+        (OutFormat::Vowl, &args.vowl),
+        (OutFormat::Csv, &args.out_csv),
+    ]
+    .into_iter()
+    .filter_map(|(format, path)| Some((format, path.clone()?)))
+    .chain(args.out.iter().cloned())
+    .collect()
+}
+
+/// Whether an export `path` is `-`, i.e. stdout
+fn is_stdout(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Writes the artifact of a command to stdout. Stdout carries nothing else, every message goes
+/// to stderr, so `rdfviz ... | d2 - out.svg` gets exactly the artifact
+fn print_stdout(contents: &[u8]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(contents)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Whether `e` is the reader of stdout having gone away, e.g. `| head` after its lines
+fn is_broken_pipe(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        let io = match cause.downcast_ref::<rdf_class_viz::Error>() {
+            Some(rdf_class_viz::Error::Io(io)) => Some(io),
+            _ => cause.downcast_ref::<std::io::Error>(),
+        };
+        io.is_some_and(|io| io.kind() == std::io::ErrorKind::BrokenPipe)
+    })
+}
+
+/// The extra exports requested by `args` as (path, format, contents)
+fn exports(args: &Args, v: &Visualizer) -> Result<Vec<(PathBuf, &'static str, Vec<u8>)>> {
+    let mut exports = Vec::new();
+    for (format, path) in outputs(args) {
+        match format {
+            OutFormat::D2 => exports.push((path, "d2", (v.graph_to_d2lang()? + "\n").into_bytes())),
+            OutFormat::Dot => exports.push((path, "dot", v.graph_to_dot()?.into_bytes())),
+            OutFormat::Trig => {
+                let mut contents = Vec::new();
+                write_trig([("urn:rdf_class_viz:view:full", v)], &mut contents)?;
+                exports.push((path, "trig", contents));
+            }
+            OutFormat::Gexf => exports.push((path, "gexf", v.graph_to_gexf()?.into_bytes())),
+            OutFormat::Vowl => exports.push((path, "vowl", v.graph_to_vowl()?.into_bytes())),
+            OutFormat::Void => exports.push((path, "void", v.graph_to_void()?.into_bytes())),
+            OutFormat::Cypher => exports.push((path, "cypher", v.graph_to_cypher()?.into_bytes())),
+            OutFormat::Graphson => exports.push((path, "graphson", v.graph_to_graphson()?.into_bytes())),
+            OutFormat::Drawio => exports.push((path, "drawio", v.graph_to_drawio()?.into_bytes())),
+            OutFormat::Mermaid => exports.push((path, "mermaid", v.graph_to_mermaid()?.into_bytes())),
+            OutFormat::MatrixCsv => exports.push((path, "csv", v.matrix_csv()?.into_bytes())),
+            OutFormat::MatrixHtml => exports.push((path, "html", v.matrix_html()?.into_bytes())),
+            OutFormat::Term(charset) => exports.push((path, "term", v.graph_to_term(charset)?.into_bytes())),
+            OutFormat::Csv => {
+                std::fs::create_dir_all(&path)?;
+                exports.push((path.join("nodes.csv"), "csv", v.nodes_csv()?.into_bytes()));
+                exports.push((path.join("edges.csv"), "csv", v.edges_csv()?.into_bytes()));
+            }
+            OutFormat::Image(image) => {
+                exports.push((path, image.extension(), v.render_with(args.engine, image)?))
+            }
+            // streamed by stream_exports
+            OutFormat::Json | OutFormat::Graphml => {}
+        }
     }
+    Ok(exports)
+}
 
-    // Process the graph file
-    let f = File::open(graph_filename)?;
-    let f = BufReader::new(f);
-    println!("{}", v.create_graph(f, GraphFormat::Turtle)?);
+/// Streams the large-format exports requested by `args` straight to their files and returns
+/// the files as (path, format)
+fn stream_exports(args: &Args, v: &Visualizer) -> Result<Vec<(PathBuf, &'static str)>> {
+    let mut streamed = Vec::new();
+    for (format, path) in outputs(args) {
+        // stdout is not a file the manifest can read back, so it is not returned
+        match format {
+            OutFormat::Graphml if is_stdout(&path) => v.write_graphml(std::io::stdout().lock())?,
+            OutFormat::Json if is_stdout(&path) => v.write_json(std::io::stdout().lock())?,
+            OutFormat::Graphml => {
+                v.write_graphml(BufWriter::new(File::create(&path)?))?;
+                streamed.push((path, "graphml"));
+            }
+            OutFormat::Json => {
+                v.write_json(BufWriter::new(File::create(&path)?))?;
+                streamed.push((path, "json"));
+            }
+            _ => {}
+        }
+    }
+    Ok(streamed)
+}
 
+fn write_manifest(path: &Path, v: &Visualizer, artifacts: &[(PathBuf, &str, Vec<u8>)]) -> Result<()> {
+    let mut manifest = Manifest::new();
+    for (path, format, contents) in artifacts {
+        manifest.record(path.to_string_lossy(), *format, contents, v);
+    }
+    std::fs::write(path, manifest.to_json()?)?;
     Ok(())
 }
+
+type EdgeFilter = Box<dyn Fn(&str, &str, &str, &FilterContext) -> bool + Send + Sync>;
+
+/// Compiles a --filter script; SPARQL filters ask their query of `store`
+fn script_filter(lang: FilterLang, script: &str, store: &Store) -> Result<EdgeFilter> {
+    match lang {
+        FilterLang::Sparql => {
+            let filter = SparqlFilter::new(store.clone(), script)?;
+            Ok(Box::new(move |from, to, edge, _: &FilterContext| filter.keep(from, to, edge)))
+        }
+        #[cfg(feature = "rhai")]
+        FilterLang::Rhai => Ok(Box::new(rdf_class_viz::RhaiFilter::new(script)?.into_fn())),
+        #[cfg(not(feature = "rhai"))]
+        FilterLang::Rhai => {
+            let _ = script;
+            bail!("rhai filters are not supported by this build; rebuild with --features rhai")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_out_reads_format_and_path() {
+        assert_eq!(parse_out("json=out/graph.json").unwrap(), (OutFormat::Json, PathBuf::from("out/graph.json")));
+        assert_eq!(parse_out("matrix-csv=m.csv").unwrap(), (OutFormat::MatrixCsv, PathBuf::from("m.csv")));
+        assert_eq!(parse_out("png=g.png").unwrap(), (OutFormat::Image(ImageFormat::Png), PathBuf::from("g.png")));
+        // only the first '=' separates the path
+        assert_eq!(parse_out("d2=a=b.d2").unwrap(), (OutFormat::D2, PathBuf::from("a=b.d2")));
+    }
+
+    #[test]
+    fn parse_out_prints_the_terminal_rendering_by_default() {
+        assert_eq!(parse_out("term").unwrap(), (OutFormat::Term(TermCharset::Unicode), PathBuf::from("-")));
+        assert_eq!(parse_out("term-ascii").unwrap(), (OutFormat::Term(TermCharset::Ascii), PathBuf::from("-")));
+        assert_eq!(parse_out("term=tree.txt").unwrap(), (OutFormat::Term(TermCharset::Unicode), PathBuf::from("tree.txt")));
+    }
+
+    #[test]
+    fn parse_out_rejects_unknown_formats_and_missing_paths() {
+        assert!(parse_out("json").unwrap_err().contains("expected FORMAT=PATH"));
+        assert!(parse_out("pdf=g.pdf").unwrap_err().contains("unknown output format 'pdf'"));
+        assert!(parse_out("terminal").unwrap_err().contains("unknown output format"));
+    }
+}
@@ -0,0 +1,103 @@
+//! `--preset`: well-known ontologies downloaded on first use and cached under the XDG cache
+//! directory, each with the built-in color map of its ontology
+
+use anyhow::{bail, Context, Result};
+use rdf_class_viz::ColorMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+use tracing::info;
+
+/// A release of a well-known ontology
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub version: &'static str,
+    url: &'static str,
+    /// Color map of the categories its classes are colored by
+    pub colors: ColorMap,
+}
+
+/// Every known preset; the first release of each name is its default
+static PRESETS: &[Preset] = &[
+    Preset {
+        name: "brick",
+        version: "1.4",
+        url: "https://github.com/BrickSchema/Brick/releases/download/v1.4.0/Brick.ttl",
+        colors: ColorMap::Brick,
+    },
+    Preset {
+        name: "brick",
+        version: "1.3",
+        url: "https://github.com/BrickSchema/Brick/releases/download/v1.3.0/Brick.ttl",
+        colors: ColorMap::Brick,
+    },
+    Preset {
+        name: "s223",
+        version: "latest",
+        url: "https://open223.info/223p.ttl",
+        colors: ColorMap::S223,
+    },
+];
+
+/// Parses `NAME` or `NAME@VERSION`, e.g. `brick@1.4` or `s223`
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, version) = match s.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (s, None),
+        };
+        PRESETS
+            .iter()
+            .find(|preset| preset.name == name && version.is_none_or(|version| preset.version == version))
+            .copied()
+            .ok_or_else(|| {
+                let known: Vec<String> = PRESETS
+                    .iter()
+                    .map(|preset| format!("{}@{}", preset.name, preset.version))
+                    .collect();
+                format!("unknown preset '{}', expected one of {}", s, known.join(", "))
+            })
+    }
+}
+
+impl Preset {
+    /// Path of the cached ontology file, downloaded with `curl` if it is not cached yet
+    pub fn path(&self) -> Result<PathBuf> {
+        let dir = cache_dir()?.join(format!("{}-{}", self.name, self.version));
+        let path = dir.join(self.url.rsplit('/').next().unwrap_or("ontology.ttl"));
+        if path.exists() {
+            return Ok(path);
+        }
+        std::fs::create_dir_all(&dir)?;
+        info!("downloading {}@{} from {}", self.name, self.version, self.url);
+        // write next to the final file so an interrupted download is never mistaken for a
+        // cached one
+        let partial = path.with_extension("part");
+        let status = Command::new("curl")
+            .args(["--fail", "--silent", "--show-error", "--location", "--output"])
+            .arg(&partial)
+            .arg(self.url)
+            .status()
+            .context("failed to run curl to download the preset")?;
+        if !status.success() {
+            bail!("downloading {} failed: curl exited with {}", self.url, status);
+        }
+        std::fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+}
+
+/// `$XDG_CACHE_HOME/rdfviz/presets`, or `~/.cache/rdfviz/presets`
+fn cache_dir() -> Result<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".cache"),
+            None => bail!("cannot find a cache directory for presets: neither XDG_CACHE_HOME nor HOME is set"),
+        },
+    };
+    Ok(base.join("rdfviz").join("presets"))
+}
@@ -0,0 +1,62 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use rdf_class_viz::{LoadProgress, ProgressSink, RowProgress};
+use std::io::IsTerminal;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Shows a spinner on stderr for the stage that is running, with the triples or rows it got
+/// through so far. When stderr is not a terminal (CI logs, redirects) the progress reports are
+/// printed as plain lines instead.
+pub struct ProgressBars {
+    bar: Mutex<Option<ProgressBar>>,
+    terminal: bool,
+}
+
+impl ProgressBars {
+    pub fn new() -> Self {
+        ProgressBars {
+            bar: Mutex::new(None),
+            terminal: std::io::stderr().is_terminal(),
+        }
+    }
+
+    fn report(&self, stage: &str, message: String) {
+        if !self.terminal {
+            eprintln!("{}: {}", stage, message);
+        } else if let Some(bar) = self.bar.lock().unwrap().as_ref() {
+            bar.set_message(message);
+        }
+    }
+}
+
+impl ProgressSink for ProgressBars {
+    fn stage_started(&self, stage: &'static str) {
+        if !self.terminal {
+            return;
+        }
+        let style = ProgressStyle::with_template("{spinner} {prefix} {msg} [{elapsed}]")
+            .expect("valid progress template");
+        let bar = ProgressBar::new_spinner().with_style(style).with_prefix(stage);
+        bar.enable_steady_tick(Duration::from_millis(100));
+        if let Some(previous) = self.bar.lock().unwrap().replace(bar) {
+            previous.finish_and_clear();
+        }
+    }
+
+    fn loaded(&self, progress: LoadProgress) {
+        self.report(
+            progress.stage,
+            format!("{} triples ({:.0} triples/s)", progress.triples, progress.rate()),
+        );
+    }
+
+    fn rows(&self, progress: RowProgress) {
+        self.report(progress.stage, format!("{} rows", progress.rows));
+    }
+
+    fn stage_finished(&self, _stage: &'static str, _elapsed: Duration) {
+        if let Some(bar) = self.bar.lock().unwrap().take() {
+            bar.finish_and_clear();
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! `rdfviz serve`: an HTTP front end that renders the class summary of posted data graphs,
+//! and with `--data` a live diagram, a GraphQL API and a SPARQL endpoint of a data graph on disk
+
+use crate::graphql::{self, Schema, Summary};
+use crate::{configure, graph_format, parse_all, watch_files, Args};
+use anyhow::{anyhow, bail, Context, Result};
+use juniper::http::graphiql::graphiql_source;
+use juniper::http::GraphQLRequest;
+use oxigraph::io::{GraphFormat, GraphSerializer};
+use oxigraph::sparql::{Query, QueryResultsFormat};
+use oxigraph::store::Store;
+use rdf_class_viz::{Error, ImageFormat, LimitedResults, ParsedGraph, QueryLimits, Visualizer};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tiny_http::{Header, Method, ReadWrite, Request, Response, Server};
+use tracing::{error, info};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+static VIEW_PAGE: &str = include_str!("view.html");
+static LIVE_PAGE: &str = include_str!("live.html");
+
+/// The ontologies every posted data graph is summarized against, parsed once at startup
+struct Ontologies {
+    files: Vec<(String, ParsedGraph)>,
+}
+
+impl Ontologies {
+    fn read(paths: &[PathBuf]) -> Result<Self> {
+        let names = paths
+            .iter()
+            .map(|path| path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+        Ok(Ontologies {
+            files: names.zip(parse_all(paths)?).collect(),
+        })
+    }
+}
+
+/// The data graph of `serve --data`, summarized at startup and with `--watch` again on every
+/// change: its diagram, pushed as a text message to every page connected to `/live/socket`,
+/// the summary `/graphql` answers from and the store `/sparql` queries
+struct Dataset {
+    data: PathBuf,
+    /// The last rebuild: the SVG, or the error it failed with prefixed by `error: `
+    latest: Mutex<String>,
+    /// The summary of the last successful rebuild
+    summary: Mutex<Summary>,
+    /// The ontologies and the data graph of the last successful rebuild
+    store: Mutex<Option<Store>>,
+    sockets: Mutex<Vec<WebSocket<Box<dyn ReadWrite + Send>>>>,
+}
+
+impl Dataset {
+    /// Summarizes the data graph against freshly read ontologies and pushes the result,
+    /// dropping the sockets of pages that went away
+    fn rebuild(&self, args: &Args, ontology_files: &[PathBuf]) {
+        let rebuilt = Ontologies::read(ontology_files).and_then(|ontologies| {
+            let data = std::fs::read(&self.data)?;
+            let v = summarize(args, &ontologies, &data, graph_format(&self.data))?;
+            Ok((v.render_with(args.engine, ImageFormat::Svg)?, Summary::new(&v), v.store().clone()))
+        });
+        let message = match rebuilt {
+            Ok((svg, summary, store)) => {
+                *self.summary.lock().expect("no panics while rebuilding") = summary;
+                *self.store.lock().expect("no panics while rebuilding") = Some(store);
+                String::from_utf8_lossy(&svg).into_owned()
+            }
+            Err(e) => {
+                error!("{:#}", e);
+                format!("error: {:#}", e)
+            }
+        };
+        *self.latest.lock().expect("no panics while pushing") = message.clone();
+        let mut sockets = self.sockets.lock().expect("no panics while pushing");
+        sockets.retain_mut(|socket| socket.send(Message::text(message.clone())).is_ok());
+    }
+
+    /// Completes the websocket handshake of `request` and sends it the last rebuild
+    fn connect(&self, request: Request) -> Result<()> {
+        let key = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+            .context("not a websocket request")?
+            .value
+            .to_string();
+        // upgrade adds the Upgrade and Connection headers
+        let accept = Header::from_bytes("Sec-WebSocket-Accept", derive_accept_key(key.as_bytes()))
+            .expect("accept keys are valid headers");
+        let response = Response::empty(101).with_header(accept);
+        let mut socket = WebSocket::from_raw_socket(request.upgrade("websocket", response), Role::Server, None);
+        let latest = self.latest.lock().expect("no panics while pushing").clone();
+        socket.send(Message::text(latest))?;
+        self.sockets.lock().expect("no panics while pushing").push(socket);
+        Ok(())
+    }
+}
+
+/// Serves `POST /render?format=svg|png|d2|dot|json` (the data graph as the body; Turtle unless
+/// the Content-Type says otherwise) and the `GET /view` page, one request at a time. With a
+/// `data` graph, also serves its diagram at `GET /live`, GraphQL queries of its summary at
+/// `POST /graphql` and read-only SPARQL queries of its ontologies and data at `/sparql`,
+/// rebuilt whenever an input changes if `watch` is set.
+pub fn serve(args: &Args, addr: &str, ontology_files: &[PathBuf], data: Option<&Path>, watch: bool) -> Result<()> {
+    let ontologies = Ontologies::read(ontology_files)?;
+    let dataset = data.map(|data| Dataset {
+        data: data.to_owned(),
+        latest: Mutex::new(String::new()),
+        summary: Mutex::new(Summary::default()),
+        store: Mutex::new(None),
+        sockets: Mutex::new(Vec::new()),
+    });
+    let schema = graphql::schema();
+
+    let server = Server::http(addr).map_err(|e| anyhow!("cannot listen on {}: {}", addr, e))?;
+    std::thread::scope(|scope| {
+        if let Some(dataset) = dataset.as_ref() {
+            dataset.rebuild(args, ontology_files);
+            info!("serving the diagram of {} on http://{}/live", dataset.data.display(), addr);
+            info!("serving GraphQL queries of its summary on http://{}/graphql", addr);
+            info!("serving SPARQL queries of its triples on http://{}/sparql", addr);
+        }
+        if let Some(dataset) = dataset.as_ref().filter(|_| watch) {
+            scope.spawn(move || {
+                let files: Vec<&Path> =
+                    ontology_files.iter().map(PathBuf::as_path).chain([dataset.data.as_path()]).collect();
+                let rebuilt = watch_files(&files, || {
+                    dataset.rebuild(args, ontology_files);
+                    Ok(())
+                });
+                if let Err(e) = rebuilt {
+                    error!("stopped watching: {:#}", e);
+                }
+            });
+        }
+        info!("serving on http://{}/view", addr);
+        for mut request in server.incoming_requests() {
+            if let Some(dataset) = dataset.as_ref().filter(|_| request.url() == "/live/socket") {
+                if let Err(e) = dataset.connect(request) {
+                    error!("{:#}", e);
+                }
+                continue;
+            }
+            let response = match handle(args, &ontologies, dataset.as_ref(), &schema, &mut request) {
+                Ok((content_type, body)) => Response::from_data(body).with_header(header(content_type)),
+                Err(e) => Response::from_string(format!("{:#}", e))
+                    .with_status_code(status(&e))
+                    .with_header(header("text/plain; charset=utf-8")),
+            };
+            if let Err(e) = request.respond(response) {
+                error!("{}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The status of a request that failed with `e`: 503 for a query over --query-timeout, 413
+/// for one over --max-results and 400 for anything else
+fn status(e: &anyhow::Error) -> u16 {
+    match e.chain().find_map(|cause| cause.downcast_ref::<Error>()) {
+        Some(Error::Timeout(_)) => 503,
+        Some(Error::TooManyResults(_)) => 413,
+        _ => 400,
+    }
+}
+
+fn header(content_type: &str) -> Header {
+    Header::from_bytes("Content-Type", content_type).expect("content types are valid headers")
+}
+
+/// The content type and body answering `request`
+fn handle(
+    args: &Args,
+    ontologies: &Ontologies,
+    dataset: Option<&Dataset>,
+    schema: &Schema,
+    request: &mut Request,
+) -> Result<(&'static str, Vec<u8>)> {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    match (request.method(), path) {
+        (Method::Get, "/" | "/view") => Ok(("text/html; charset=utf-8", VIEW_PAGE.as_bytes().to_vec())),
+        (Method::Get, "/live") => Ok(("text/html; charset=utf-8", LIVE_PAGE.as_bytes().to_vec())),
+        (Method::Get, "/graphql") => Ok(("text/html; charset=utf-8", graphiql_source("/graphql", None).into_bytes())),
+        (Method::Post, "/graphql") => {
+            let dataset = dataset.context("no data graph to query; start serve with --data")?;
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body)?;
+            let query: GraphQLRequest = serde_json::from_str(&body).context("invalid GraphQL request")?;
+            let summary = dataset.summary.lock().expect("no panics while rebuilding");
+            let response = query.execute_sync(schema, &summary);
+            Ok(("application/json", serde_json::to_vec(&response)?))
+        }
+        (Method::Get | Method::Post, "/sparql") => {
+            let dataset = dataset.context("no data graph to query; start serve with --data")?;
+            let sparql_query = match request.method() {
+                Method::Get => form_param(query, "query").map(form_decode).transpose()?,
+                _ => {
+                    let form = content_type(request) == Some("application/x-www-form-urlencoded");
+                    let mut body = String::new();
+                    request.as_reader().read_to_string(&mut body)?;
+                    if form {
+                        form_param(&body, "query").map(form_decode).transpose()?
+                    } else {
+                        Some(body)
+                    }
+                }
+            };
+            let sparql_query = sparql_query.context("expected a SPARQL query in the query parameter or the body")?;
+            let accept = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("Accept"))
+                .map_or("", |header| header.value.as_str())
+                .to_owned();
+            let store = dataset.store.lock().expect("no panics while rebuilding").clone();
+            let store = store.context("the data graph failed to load")?;
+            let limits = QueryLimits {
+                timeout: args.query_timeout,
+                max_results: args.max_results,
+            };
+            sparql(&store, limits, &sparql_query, &accept)
+        }
+        (Method::Post, "/render") => {
+            let format = query
+                .split('&')
+                .find_map(|param| param.strip_prefix("format="))
+                .unwrap_or("svg")
+                .to_owned();
+            let data_format = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("Content-Type"))
+                .and_then(|header| GraphFormat::from_media_type(header.value.as_str()))
+                .unwrap_or(GraphFormat::Turtle);
+            let mut data = Vec::new();
+            request.as_reader().read_to_end(&mut data)?;
+            render(args, ontologies, &data, data_format, &format)
+        }
+        (method, path) => bail!("no route for {} {}", method, path),
+    }
+}
+
+/// The media type of the body of `request`, without its parameters
+fn content_type(request: &Request) -> Option<&str> {
+    let header = request.headers().iter().find(|header| header.field.equiv("Content-Type"))?;
+    header.value.as_str().split(';').next().map(str::trim)
+}
+
+/// The raw value of the parameter `name` in the `a=1&b=2` form `form`
+fn form_param<'f>(form: &'f str, name: &str) -> Option<&'f str> {
+    form.split('&').find_map(|param| param.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// Decodes a form value: `+` is a space and `%XX` a percent-encoded byte
+fn form_decode(value: &str) -> Result<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let byte = value
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .with_context(|| format!("invalid percent-encoding in '{}'", value))?;
+                decoded.push(byte);
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    Ok(String::from_utf8(decoded)?)
+}
+
+/// Evaluates the SPARQL query `query` over `store`, with the named graphs in the default graph
+/// unless the query picks its own, and serializes the results in the first format `accept`
+/// lists that fits them: SPARQL JSON results and Turtle graphs by default. Updates are not
+/// queries, so the store is never modified. The query is held to `limits` like the ones
+/// building the class graph, so one expensive query cannot block the server for long.
+fn sparql(store: &Store, limits: QueryLimits, query: &str, accept: &str) -> Result<(&'static str, Vec<u8>)> {
+    let mut query = Query::parse(query, None)?;
+    if query.dataset().default_graph_graphs().is_none() {
+        query.dataset_mut().set_default_graph_as_union();
+    }
+    let mut accepted = accept.split(',').filter_map(|media_type| media_type.split(';').next()).map(str::trim);
+    let mut body = Vec::new();
+    match limits.evaluate(store, query)? {
+        LimitedResults::Triples(triples) => {
+            let format = accepted.find_map(GraphFormat::from_media_type).unwrap_or(GraphFormat::Turtle);
+            let mut writer = GraphSerializer::from_format(format).triple_writer(&mut body)?;
+            for triple in triples {
+                writer.write(&triple.map_err(Error::from)?)?;
+            }
+            writer.finish()?;
+            Ok((format.media_type(), body))
+        }
+        LimitedResults::Results(results) => {
+            let format = accepted.find_map(QueryResultsFormat::from_media_type).unwrap_or(QueryResultsFormat::Json);
+            results.write(&mut body, format).map_err(Error::from)?;
+            Ok((format.media_type(), body))
+        }
+    }
+}
+
+/// Builds the class graph of `data` against the ontologies
+fn summarize(
+    args: &Args,
+    ontologies: &Ontologies,
+    data: &[u8],
+    data_format: GraphFormat,
+) -> Result<Visualizer<'static>> {
+    let mut v = configure(args)?;
+    for (name, ontology) in ontologies.files.iter() {
+        if args.provenance {
+            v.add_parsed_ontology_from(name.as_str(), ontology)?;
+        } else {
+            v.add_parsed_ontology(ontology)?;
+        }
+    }
+    v.add_data(data, data_format)?;
+    v.build_graph()?;
+    Ok(v)
+}
+
+/// Summarizes `data` against the ontologies and serializes the class graph in `format`
+fn render(
+    args: &Args,
+    ontologies: &Ontologies,
+    data: &[u8],
+    data_format: GraphFormat,
+    format: &str,
+) -> Result<(&'static str, Vec<u8>)> {
+    let v = summarize(args, ontologies, data, data_format)?;
+    Ok(match format {
+        "svg" => ("image/svg+xml", v.render_with(args.engine, ImageFormat::Svg)?),
+        "png" => ("image/png", v.render_with(args.engine, ImageFormat::Png)?),
+        "d2" => ("text/plain; charset=utf-8", v.graph_to_d2lang()?.into_bytes()),
+        "dot" => ("text/vnd.graphviz; charset=utf-8", v.graph_to_dot()?.into_bytes()),
+        "json" => {
+            let mut json = Vec::new();
+            v.write_json(&mut json)?;
+            ("application/json", json)
+        }
+        _ => bail!("unknown format '{}', expected one of svg, png, d2, dot, json", format),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_decode_turns_plus_into_space_and_decodes_percent_escapes() {
+        assert_eq!(form_decode("SELECT+*+WHERE+%7B%7D").unwrap(), "SELECT * WHERE {}");
+        assert_eq!(form_decode("a%2Bb%3Dc").unwrap(), "a+b=c");
+        assert_eq!(form_decode("caf%C3%A9").unwrap(), "café");
+        assert_eq!(form_decode("").unwrap(), "");
+    }
+
+    #[test]
+    fn form_decode_rejects_bad_escapes() {
+        assert!(form_decode("100%").is_err());
+        assert!(form_decode("%2").is_err());
+        assert!(form_decode("%zz").is_err());
+        assert!(form_decode("%é").is_err());
+        // not UTF-8
+        assert!(form_decode("%FF").is_err());
+    }
+
+    /// A store of `count` triples, whose three-way join below has `count`³ rows
+    fn store(count: usize) -> Store {
+        let store = Store::new().unwrap();
+        let data: String = (0..count).map(|i| format!("<urn:s{0}> <urn:p> <urn:o{0}> .\n", i)).collect();
+        store.load_graph(data.as_bytes(), GraphFormat::NTriples, &oxigraph::model::GraphName::DefaultGraph, None).unwrap();
+        store
+    }
+
+    const JOIN: &str = "SELECT * WHERE { ?a ?p ?b . ?c ?q ?d . ?e ?r ?f }";
+
+    fn limits(timeout: Option<u64>, max_results: Option<usize>) -> QueryLimits {
+        QueryLimits {
+            timeout: timeout.map(std::time::Duration::from_millis),
+            max_results,
+        }
+    }
+
+    #[test]
+    fn sparql_answers_within_the_limits() {
+        let store = store(3);
+        let (content_type, body) = sparql(&store, limits(Some(60_000), Some(27)), JOIN, "").unwrap();
+        assert_eq!(content_type, "application/sparql-results+json");
+        let results: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(results["results"]["bindings"].as_array().unwrap().len(), 27);
+        let construct = "CONSTRUCT { ?s <urn:q> ?o } WHERE { ?s <urn:p> ?o }";
+        let (content_type, body) = sparql(&store, limits(Some(60_000), Some(3)), construct, "").unwrap();
+        assert_eq!(content_type, "text/turtle");
+        assert_eq!(String::from_utf8(body).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn sparql_rejects_queries_over_max_results_with_413() {
+        let store = store(3);
+        let e = sparql(&store, limits(None, Some(26)), JOIN, "").unwrap_err();
+        assert_eq!(status(&e), 413);
+        let e = sparql(&store, limits(Some(60_000), Some(26)), JOIN, "").unwrap_err();
+        assert_eq!(status(&e), 413);
+        let construct = "CONSTRUCT { ?s <urn:q> ?o } WHERE { ?s <urn:p> ?o }";
+        let e = sparql(&store, limits(Some(60_000), Some(2)), construct, "").unwrap_err();
+        assert_eq!(status(&e), 413);
+    }
+
+    #[test]
+    fn sparql_rejects_slow_queries_with_503() {
+        let store = store(300);
+        let e = sparql(&store, limits(Some(50), None), JOIN, "").unwrap_err();
+        assert_eq!(status(&e), 503);
+    }
+
+    #[test]
+    fn invalid_queries_are_bad_requests() {
+        let e = sparql(&store(1), QueryLimits::default(), "SELECT", "").unwrap_err();
+        assert_eq!(status(&e), 400);
+    }
+
+    #[test]
+    fn form_param_finds_the_raw_value() {
+        assert_eq!(form_param("format=svg&query=a+b", "query"), Some("a+b"));
+        assert_eq!(form_param("queryx=1&query=2", "query"), Some("2"));
+        assert_eq!(form_param("format=svg", "query"), None);
+    }
+}
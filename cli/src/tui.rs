@@ -0,0 +1,279 @@
+//! `rdfviz tui`: a terminal explorer of the class graph, to browse classes and their
+//! neighbors, narrow down a selection and export just that selection to d2
+
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use rdf_class_viz::Visualizer;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+/// The pane keys act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Classes,
+    Predicates,
+}
+
+/// State of the explorer, over a copy of the class graph taken once at startup
+struct Explorer {
+    /// Every class label, sorted
+    classes: Vec<String>,
+    /// Every edge as (from, predicate, to) class labels
+    edges: Vec<(String, String, String)>,
+    /// Every predicate label, sorted
+    predicates: Vec<String>,
+    hidden: HashSet<String>,
+    selection: BTreeSet<String>,
+    search: String,
+    searching: bool,
+    pane: Pane,
+    class_cursor: ListState,
+    predicate_cursor: ListState,
+    status: String,
+}
+
+impl Explorer {
+    fn new(v: &Visualizer) -> Self {
+        let g = v.graph();
+        let mut classes: Vec<String> = g.node_weights().cloned().collect();
+        classes.sort();
+        let edges: Vec<(String, String, String)> = g
+            .edge_indices()
+            .filter_map(|idx| {
+                let (from, to) = g.edge_endpoints(idx)?;
+                Some((g[from].clone(), g[idx].clone(), g[to].clone()))
+            })
+            .collect();
+        let predicates: BTreeSet<String> = edges.iter().map(|(_, predicate, _)| predicate.clone()).collect();
+        Explorer {
+            classes,
+            edges,
+            predicates: predicates.into_iter().collect(),
+            hidden: HashSet::new(),
+            selection: BTreeSet::new(),
+            search: String::new(),
+            searching: false,
+            pane: Pane::Classes,
+            class_cursor: ListState::default().with_selected(Some(0)),
+            predicate_cursor: ListState::default().with_selected(Some(0)),
+            status: String::from("/ search, space select, e expand, p predicates, x export, q quit"),
+        }
+    }
+
+    /// The classes matching the search, case-insensitively
+    fn visible_classes(&self) -> Vec<&String> {
+        let search = self.search.to_lowercase();
+        self.classes
+            .iter()
+            .filter(|class| class.to_lowercase().contains(&search))
+            .collect()
+    }
+
+    fn current_class(&self) -> Option<&String> {
+        self.visible_classes().get(self.class_cursor.selected()?).copied()
+    }
+
+    /// The edges of `class` whose predicate is not hidden, as (outgoing, predicate, other class)
+    fn neighbors<'e>(&'e self, class: &str) -> Vec<(bool, &'e String, &'e String)> {
+        let mut neighbors: Vec<_> = self
+            .edges
+            .iter()
+            .filter(|(_, predicate, _)| !self.hidden.contains(predicate))
+            .flat_map(|(from, predicate, to)| {
+                let outgoing = (from == class).then_some((true, predicate, to));
+                let incoming = (to == class).then_some((false, predicate, from));
+                outgoing.into_iter().chain(incoming)
+            })
+            .collect();
+        neighbors.sort();
+        neighbors
+    }
+
+    /// Moves the cursor of the active pane by `by` rows, staying within its list
+    fn move_cursor(&mut self, by: isize) {
+        let (cursor, len) = match self.pane {
+            Pane::Classes => {
+                let len = self.visible_classes().len();
+                (&mut self.class_cursor, len)
+            }
+            Pane::Predicates => (&mut self.predicate_cursor, self.predicates.len()),
+        };
+        let row = cursor.selected().unwrap_or(0).saturating_add_signed(by);
+        cursor.select(Some(row.min(len.saturating_sub(1))));
+    }
+
+    /// Handles a key press and returns whether to quit
+    fn key(&mut self, code: KeyCode, v: &mut Visualizer, export: &Path) -> Result<bool> {
+        if self.searching {
+            match code {
+                KeyCode::Char(c) => self.search.push(c),
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Enter | KeyCode::Esc => self.searching = false,
+                _ => {}
+            }
+            self.class_cursor.select(Some(0));
+            return Ok(false);
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Char('/') => {
+                self.pane = Pane::Classes;
+                self.searching = true;
+            }
+            KeyCode::Char('p') | KeyCode::Tab => {
+                self.pane = match self.pane {
+                    Pane::Classes => Pane::Predicates,
+                    Pane::Predicates => Pane::Classes,
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => match self.pane {
+                Pane::Classes => {
+                    if let Some(class) = self.current_class().cloned() {
+                        if !self.selection.remove(&class) {
+                            self.selection.insert(class);
+                        }
+                    }
+                }
+                Pane::Predicates => {
+                    if let Some(predicate) = self.predicate_cursor.selected().and_then(|i| self.predicates.get(i)) {
+                        if !self.hidden.remove(predicate) {
+                            self.hidden.insert(predicate.clone());
+                        }
+                    }
+                }
+            },
+            KeyCode::Char('e') | KeyCode::Right => {
+                if let Some(class) = self.current_class().cloned() {
+                    let neighbors: Vec<String> =
+                        self.neighbors(&class).into_iter().map(|(_, _, other)| other.clone()).collect();
+                    self.status = format!("added {} and its {} neighbor(s)", class, neighbors.len());
+                    self.selection.insert(class);
+                    self.selection.extend(neighbors);
+                }
+            }
+            KeyCode::Char('c') => self.selection.clear(),
+            KeyCode::Char('x') => self.status = self.export(v, export)?,
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Writes the selected classes and the edges among them with a shown predicate to
+    /// `path` as d2, then rebuilds the full graph
+    fn export(&self, v: &mut Visualizer, path: &Path) -> Result<String> {
+        if self.selection.is_empty() {
+            return Ok(String::from("nothing selected to export"));
+        }
+        let classes: HashSet<String> = self.selection.iter().cloned().collect();
+        let predicates: HashSet<String> =
+            self.predicates.iter().filter(|predicate| !self.hidden.contains(*predicate)).cloned().collect();
+        v.keep_selection(&classes, &predicates);
+        let d2 = v.graph_to_d2lang()? + "\n";
+        v.build_graph()?;
+        std::fs::write(path, d2)?;
+        Ok(format!("wrote {} class(es) to {}", classes.len(), path.display()))
+    }
+
+    fn draw(&mut self, frame: &mut Frame, v: &Visualizer) {
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [classes_area, neighbors_area, predicates_area] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(40), Constraint::Percentage(25)])
+                .areas(main);
+        let active = |pane: Pane| {
+            if self.pane == pane {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            }
+        };
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+        let classes: Vec<ListItem> = self
+            .visible_classes()
+            .into_iter()
+            .map(|class| {
+                let color = v
+                    .colors()
+                    .get(class)
+                    .and_then(|color| color.parse::<Color>().ok())
+                    .unwrap_or(Color::Reset);
+                let mark = if self.selection.contains(class) { "[x] " } else { "[ ] " };
+                ListItem::new(Line::from(vec![
+                    Span::raw(mark),
+                    Span::styled("■ ", Style::default().fg(color)),
+                    Span::raw(class.clone()),
+                ]))
+            })
+            .collect();
+        let title = if self.searching || !self.search.is_empty() {
+            format!("Classes /{}", self.search)
+        } else {
+            format!("Classes ({} selected)", self.selection.len())
+        };
+        let classes = List::new(classes)
+            .block(Block::bordered().title(title).border_style(active(Pane::Classes)))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(classes, classes_area, &mut self.class_cursor);
+
+        let (title, neighbors) = match self.current_class() {
+            Some(class) => {
+                let neighbors: Vec<ListItem> = self
+                    .neighbors(class)
+                    .into_iter()
+                    .map(|(outgoing, predicate, other)| {
+                        let arrow = if outgoing { "→" } else { "←" };
+                        ListItem::new(format!("{} {} {}", arrow, predicate, other))
+                    })
+                    .collect();
+                (format!("Neighbors of {}", class), neighbors)
+            }
+            None => (String::from("Neighbors"), Vec::new()),
+        };
+        frame.render_widget(List::new(neighbors).block(Block::bordered().title(title)), neighbors_area);
+
+        let predicates: Vec<ListItem> = self
+            .predicates
+            .iter()
+            .map(|predicate| {
+                let mark = if self.hidden.contains(predicate) { "[ ] " } else { "[x] " };
+                ListItem::new(format!("{}{}", mark, predicate))
+            })
+            .collect();
+        let predicates = List::new(predicates)
+            .block(Block::bordered().title("Predicates").border_style(active(Pane::Predicates)))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(predicates, predicates_area, &mut self.predicate_cursor);
+
+        frame.render_widget(Paragraph::new(self.status.as_str()), status);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, v: &mut Visualizer, export: &Path) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame, v))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && self.key(key.code, v, export)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Runs the explorer over the class graph of `v` until the user quits; `x` writes the
+/// selection to `export` as d2
+pub fn explore(mut v: Visualizer, export: &Path) -> Result<()> {
+    let mut explorer = Explorer::new(&v);
+    let mut terminal = ratatui::init();
+    let result = explorer.run(&mut terminal, &mut v, export);
+    ratatui::restore();
+    result
+}
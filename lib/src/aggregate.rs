@@ -0,0 +1,88 @@
+use crate::annotations::merge_annotations;
+use crate::{Annotations, Visualizer};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+impl Visualizer<'_> {
+    /// Draw one edge per pair of classes, labeled with every predicate between them stacked on
+    /// separate lines, instead of one parallel edge per predicate
+    pub fn with_aggregate_edges(mut self, aggregate_edges: bool) -> Self {
+        self.aggregate_edges = aggregate_edges;
+        self
+    }
+
+    /// Merges the parallel edges between every pair of classes. The merged edge keeps the
+    /// first predicate IRI in [`Visualizer::edge_predicates`] and lists all of them in
+    /// [`Visualizer::aggregated_predicates`].
+    pub(crate) fn aggregate_parallel_edges(&mut self) {
+        let mut groups: Vec<((NodeIndex, NodeIndex), Vec<EdgeIndex>)> = Vec::new();
+        let mut group_of: HashMap<(NodeIndex, NodeIndex), usize> = HashMap::new();
+        for edge in self.g.edge_references() {
+            let pair = (edge.source(), edge.target());
+            let group = *group_of.entry(pair).or_insert_with(|| {
+                groups.push((pair, Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].1.push(edge.id());
+        }
+
+        // same nodes at the same indices, without the edges
+        let nodes_only = self.g.filter_map(|_, label| Some(label.clone()), |_, _| None::<Arc<str>>);
+        let old = std::mem::replace(&mut self.g, nodes_only);
+        let mut edge_predicates = HashMap::new();
+        let mut edge_counts = HashMap::new();
+        let mut edge_changes = HashMap::new();
+        let mut restrictions = HashMap::new();
+        let mut edge_annotations: HashMap<EdgeIndex, Annotations> = HashMap::new();
+        let mut derived = Vec::new();
+        for ((from, to), mut members) in groups {
+            members.sort_by(|a, b| old[*a].cmp(&old[*b]));
+            let label = members.iter().map(|idx| &*old[*idx]).collect::<Vec<_>>().join("\n");
+            let idx = self.g.add_edge(from, to, label.into());
+            let count = members.iter().filter_map(|member| self.edge_counts.get(member)).sum::<usize>();
+            if members.iter().any(|member| self.edge_counts.contains_key(member)) {
+                edge_counts.insert(idx, count);
+            }
+            if let Some(predicate) = self.edge_predicates.get(&members[0]) {
+                edge_predicates.insert(idx, predicate.clone());
+            }
+            // changes and derivations only carry over when every merged edge agrees
+            let changes: Vec<_> = members.iter().map(|member| self.edge_changes.get(member)).collect();
+            if let Some(Some(change)) = changes.first() {
+                if changes.iter().all(|other| *other == Some(change)) {
+                    edge_changes.insert(idx, **change);
+                }
+            }
+            for member in members.iter() {
+                if let Some(annotations) = self.edge_annotations.get(member) {
+                    merge_annotations(edge_annotations.entry(idx).or_default(), annotations);
+                }
+            }
+            if members.iter().all(|member| self.derived.contains(member)) {
+                derived.push(idx);
+            }
+            if let [member] = members[..] {
+                if let Some(kind) = self.restrictions.get(&member) {
+                    restrictions.insert(idx, kind.clone());
+                }
+            } else {
+                let predicates = members.iter().filter_map(|member| self.edge_predicates.get(member).map(|p| p.to_string()));
+                self.aggregated.insert(idx, predicates.collect());
+            }
+        }
+        self.edge_predicates = edge_predicates;
+        self.edge_counts = edge_counts;
+        self.edge_changes = edge_changes;
+        self.restrictions = restrictions;
+        self.edge_annotations = edge_annotations;
+        self.derived = derived.into_iter().collect();
+    }
+
+    /// Predicate IRIs of the edges merged into each edge of [`Visualizer::graph`] by
+    /// [`Visualizer::with_aggregate_edges`]; edges standing for a single predicate are absent
+    pub fn aggregated_predicates(&self) -> &HashMap<EdgeIndex, Vec<String>> {
+        &self.aggregated
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{iri, Result, Visualizer};
+use oxigraph::model::Term;
+use oxigraph::sparql::QueryResults;
+use petgraph::graph::EdgeIndex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Annotation property IRI to the distinct values it was given
+pub type Annotations = BTreeMap<String, BTreeSet<String>>;
+
+impl Visualizer<'_> {
+    /// Collect the annotations of the statements behind each edge, given as RDF-star quoted
+    /// triples (`<< :ahu brick:feeds :vav >> ex:confidence 0.9`) or `rdf:Statement`
+    /// reifications, into [`Visualizer::edge_annotations`]
+    pub fn with_statement_annotations(mut self, statement_annotations: bool) -> Self {
+        self.statement_annotations = statement_annotations;
+        self
+    }
+
+    /// Annotations of the statements behind each edge of [`Visualizer::graph`], when enabled
+    /// with [`Visualizer::with_statement_annotations`]
+    pub fn edge_annotations(&self) -> &HashMap<EdgeIndex, Annotations> {
+        &self.edge_annotations
+    }
+
+    /// Attaches statement annotations to the edges the annotated statements contributed to
+    pub(crate) fn collect_annotations(&mut self) -> Result<()> {
+        let q = format!("PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 SELECT DISTINCT ?from ?p ?to ?annotation ?value WHERE {{
+                     {{
+                         ?statement ?annotation ?value .
+                         FILTER(isTRIPLE(?statement))
+                         BIND(SUBJECT(?statement) AS ?x)
+                         BIND(PREDICATE(?statement) AS ?p)
+                         BIND(OBJECT(?statement) AS ?y)
+                     }} UNION {{
+                         ?statement rdf:type rdf:Statement ;
+                             rdf:subject ?x ;
+                             rdf:predicate ?p ;
+                             rdf:object ?y ;
+                             ?annotation ?value .
+                         FILTER(?annotation NOT IN (rdf:type, rdf:subject, rdf:predicate, rdf:object))
+                     }}
+                     ?x rdf:type ?from .
+                     ?y rdf:type ?to .
+                     {}
+                     {}
+                     {}
+                     {}
+                 }}",
+            self.class_pattern("from"),
+            self.class_pattern("to"),
+            self.type_policy("x", "from"),
+            self.type_policy("y", "to"));
+
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                let (Some(from), Some(to), Some(p)) = (row.get("from"), row.get("to"), row.get("p")) else {
+                    continue;
+                };
+                let p = self.interner.iri(p);
+                let p = self.rolled_up_property(&p);
+                let (from, to, p) = match self.inverses.get(&*p) {
+                    Some(canonical) => (to, from, self.interner.intern(canonical)),
+                    None => (from, to, p),
+                };
+                let (from, to) = (self.interner.label(from), self.interner.label(to));
+                let (Some(from_idx), Some(to_idx)) = (self.nodes.get(&*from), self.nodes.get(&*to)) else {
+                    continue;
+                };
+                let Some(idx) = self.edge_lookup.get(&(*from_idx, *to_idx, p)).copied() else {
+                    continue;
+                };
+                let value = match row.get("value") {
+                    Some(Term::Literal(literal)) => literal.value().to_owned(),
+                    Some(value) => iri(value),
+                    None => continue,
+                };
+                self.edge_annotations
+                    .entry(idx)
+                    .or_default()
+                    .entry(iri(row.get("annotation").unwrap()))
+                    .or_default()
+                    .insert(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds the annotations of `other` to `annotations`
+pub(crate) fn merge_annotations(annotations: &mut Annotations, other: &Annotations) {
+    for (property, values) in other {
+        annotations.entry(property.clone()).or_default().extend(values.iter().cloned());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{iri, Error, GraphSource, Result, Visualizer};
+use oxigraph::model::Term;
+use oxigraph::sparql::QueryResults;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Version of the [`GraphCache`] format; caches of another version are refused
+pub const GRAPH_CACHE_VERSION: u32 = 1;
+
+/// What building the class graph extracts from a data graph: the (from, predicate, to) rows of
+/// the edge query and the instance counts of every class. Saved to a file, it lets the graph
+/// be rebuilt with other colors, styles, filters or exports by loading only the ontologies
+/// again. It reflects the class declaration, multi-type policy and unknown class settings it
+/// was saved with; literal summaries, annotations, property chains and datatype edges read
+/// the data graph and are not cached.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphCache {
+    pub version: u32,
+    pub edges: Vec<CachedEdge>,
+    /// Instances of each class, by IRI
+    pub instance_counts: BTreeMap<String, usize>,
+    /// Instances of each type no ontology declares as a class, by IRI
+    pub undefined_classes: BTreeMap<String, usize>,
+}
+
+/// One row of the edge query, before any filter ran
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedEdge {
+    pub from: String,
+    pub predicate: String,
+    pub to: String,
+    /// Instance pairs supporting the edge
+    pub count: usize,
+}
+
+impl CachedEdge {
+    /// The row of an edge query solution
+    pub(crate) fn new(from: &Term, p: &Term, to: &Term, count: usize) -> Self {
+        CachedEdge {
+            from: iri(from),
+            predicate: iri(p),
+            to: iri(to),
+            count,
+        }
+    }
+}
+
+impl GraphCache {
+    /// A cache with no edges or classes yet
+    pub(crate) fn new() -> Self {
+        GraphCache {
+            version: GRAPH_CACHE_VERSION,
+            edges: Vec::new(),
+            instance_counts: BTreeMap::new(),
+            undefined_classes: BTreeMap::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Reads a cache written by [`GraphCache::to_json`]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let cache: GraphCache = serde_json::from_str(json).map_err(|e| Error::Cache(e.to_string()))?;
+        if cache.version != GRAPH_CACHE_VERSION {
+            return Err(Error::Cache(format!(
+                "version {} is not supported, expected {}",
+                cache.version, GRAPH_CACHE_VERSION
+            )));
+        }
+        Ok(cache)
+    }
+}
+
+impl Visualizer<'_> {
+    /// Runs the extraction queries against the data loaded so far and captures their results,
+    /// to [`Visualizer::build_graph_from_cache`] later without the data graph
+    pub fn graph_cache(&self) -> Result<GraphCache> {
+        let mut cache = GraphCache {
+            edges: self.cached_edges("")?,
+            ..GraphCache::new()
+        };
+        if self.source == GraphSource::Instances {
+            cache.instance_counts = self.query_instance_counts("")?;
+            cache.undefined_classes = self.query_undefined_classes("")?;
+        }
+        Ok(cache)
+    }
+
+    /// The rows of the edge query, restricted by a `scope` pattern binding `?from` or `?to`
+    pub(crate) fn cached_edges(&self, scope: &str) -> Result<Vec<CachedEdge>> {
+        let mut edges = Vec::new();
+        if let QueryResults::Solutions(solutions) = self.query(&self.edge_query(scope))? {
+            for row in solutions {
+                let row = row?;
+                let count = row.get("count").map_or(1, |count| crate::count_of(Some(count)));
+                let (from, p, to) = (row.get("from").unwrap(), row.get("p").unwrap(), row.get("to").unwrap());
+                edges.push(CachedEdge::new(from, p, to, count));
+            }
+        }
+        Ok(edges)
+    }
+
+    /// Builds the class graph from a [`GraphCache`] instead of the data graph, styling and
+    /// filtering it against the ontologies loaded so far
+    pub fn build_graph_from_cache(&mut self, cache: GraphCache) -> Result<()> {
+        self.cache = Some(Arc::new(cache));
+        let built = self.build_graph();
+        if let Some(cache) = self.cache.take().filter(|_| self.is_incremental()) {
+            self.summary = Some(Arc::try_unwrap(cache).unwrap_or_else(|cache| (*cache).clone()));
+        }
+        built
+    }
+
+    /// Instance counts of the cached classes, keyed by class label
+    pub(crate) fn cached_instance_counts(&mut self) -> Option<Vec<(String, usize)>> {
+        let cache = self.cache.clone()?;
+        let counts = cache
+            .instance_counts
+            .iter()
+            .map(|(class, count)| (self.interner.iri_label(class).to_string(), *count));
+        Some(counts.collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> GraphCache {
+        let mut cache = GraphCache::new();
+        cache.edges.push(CachedEdge {
+            from: "https://brickschema.org/schema/Brick#AHU".into(),
+            predicate: "https://brickschema.org/schema/Brick#feeds".into(),
+            to: "https://brickschema.org/schema/Brick#VAV".into(),
+            count: 12,
+        });
+        cache.instance_counts.insert("https://brickschema.org/schema/Brick#AHU".into(), 3);
+        cache.undefined_classes.insert("urn:ex#Widget".into(), 1);
+        cache
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let cache = cache();
+        assert_eq!(GraphCache::from_json(&cache.to_json()).unwrap(), cache);
+        assert_eq!(GraphCache::from_json(&GraphCache::new().to_json()).unwrap(), GraphCache::new());
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let cache = GraphCache {
+            version: GRAPH_CACHE_VERSION + 1,
+            ..cache()
+        };
+        assert!(matches!(GraphCache::from_json(&cache.to_json()), Err(Error::Cache(e)) if e.contains("not supported")));
+    }
+
+    #[test]
+    fn malformed_json_is_a_cache_error() {
+        assert!(matches!(GraphCache::from_json("{\"version\": 1}"), Err(Error::Cache(_))));
+        assert!(matches!(GraphCache::from_json("not json"), Err(Error::Cache(_))));
+    }
+}
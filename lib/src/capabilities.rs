@@ -0,0 +1,48 @@
+use oxigraph::io::{DatasetFormat, GraphFormat};
+use serde::Serialize;
+
+/// Input and output formats supported by this build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Formats {
+    /// RDF serializations accepted for ontologies and data graphs (datasets only for data), by
+    /// file extension
+    pub input: Vec<&'static str>,
+    /// Serializers of the class graph
+    pub output: Vec<&'static str>,
+}
+
+/// What the compiled library supports, so wrappers (bindings, services) can advertise exactly
+/// that instead of hardcoding it
+pub struct Capabilities;
+
+impl Capabilities {
+    pub fn formats() -> Formats {
+        Formats {
+            input: [GraphFormat::Turtle, GraphFormat::NTriples, GraphFormat::RdfXml]
+                .into_iter()
+                .map(GraphFormat::file_extension)
+                .chain([DatasetFormat::NQuads, DatasetFormat::TriG].map(DatasetFormat::file_extension))
+                .collect(),
+            output: vec!["d2", "dot", "svg", "png", "trig", "gexf", "graphml", "json", "csv", "vowl", "void", "cypher", "graphson", "drawio", "mermaid", "markdown", "matrix-csv", "matrix-html", "term", "manifest"],
+        }
+    }
+
+    /// Cargo features the library was compiled with
+    pub fn features() -> Vec<&'static str> {
+        let mut features = Vec::new();
+        if cfg!(feature = "rhai") {
+            features.push("rhai");
+        }
+        features
+    }
+
+    /// Both of the above as JSON
+    pub fn to_json() -> String {
+        serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "formats": Self::formats(),
+            "features": Self::features(),
+        })
+        .to_string()
+    }
+}
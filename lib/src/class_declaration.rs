@@ -0,0 +1,94 @@
+use crate::Visualizer;
+use std::str::FromStr;
+
+const OWL_CLASS: &str = "<http://www.w3.org/2002/07/owl#Class>";
+
+/// What makes a resource a class that can appear in the class graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClassDeclaration {
+    /// Declared `a owl:Class`
+    #[default]
+    Owl,
+    /// Declared `a owl:Class` or `a rdfs:Class`, for RDFS-only and schema.org-style vocabularies
+    Rdfs,
+    /// Declared `a owl:Class` or the `sh:targetClass` of a SHACL shape
+    ShapeTarget,
+    /// Used as the object of any `rdf:type` statement, other than `owl:Class` and `rdfs:Class`
+    AnyType,
+}
+
+impl FromStr for ClassDeclaration {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "owl" => Ok(ClassDeclaration::Owl),
+            "rdfs" => Ok(ClassDeclaration::Rdfs),
+            "shapes" => Ok(ClassDeclaration::ShapeTarget),
+            "any" => Ok(ClassDeclaration::AnyType),
+            _ => Err(format!(
+                "unknown class declaration '{}', expected one of owl, rdfs, shapes, any",
+                s
+            )),
+        }
+    }
+}
+
+impl Visualizer<'_> {
+    /// What makes a resource a class; only `owl:Class` declarations count by default
+    pub fn with_class_declaration(mut self, declaration: ClassDeclaration) -> Self {
+        self.class_declaration = declaration;
+        self
+    }
+
+    /// SPARQL pattern matching when `?{var}`, bound elsewhere in the same group, is a class
+    /// according to the class declaration or an undefined class drawn as an unknown node. Uses
+    /// full IRIs so it does not depend on the prefixes of the query it is spliced into.
+    pub(crate) fn class_pattern(&self, var: &str) -> String {
+        if !self.unknown_class_nodes || self.undefined_classes.is_empty() {
+            return self.declared_class_pattern(var);
+        }
+        let unknown: Vec<String> = self.undefined_classes.keys().map(|iri| format!("<{}>", iri)).collect();
+        format!(
+            "FILTER(?{} IN ({}) || EXISTS {{ {} }})",
+            var,
+            unknown.join(", "),
+            self.declared_class_pattern(var)
+        )
+    }
+
+    /// Like [`Visualizer::class_pattern`], but only matching the classes some ontology declares
+    pub(crate) fn declared_class_pattern(&self, var: &str) -> String {
+        match self.class_declaration {
+            // a filter rather than a triple pattern, which the optimizer joins first and then
+            // crosses with every other class, slowing edge queries down quadratically
+            ClassDeclaration::Owl => format!("FILTER EXISTS {{ ?{} a {} }}", var, OWL_CLASS),
+            // FILTER EXISTS rather than a UNION so classes declared both ways match once
+            ClassDeclaration::Rdfs => format!(
+                "FILTER EXISTS {{ ?{0} a ?{0}_declared . FILTER(?{0}_declared IN ({1}, <http://www.w3.org/2000/01/rdf-schema#Class>)) }}",
+                var, OWL_CLASS
+            ),
+            ClassDeclaration::ShapeTarget => format!(
+                "FILTER EXISTS {{ {{ ?{0} a {1} }} UNION {{ ?{0}_shape <http://www.w3.org/ns/shacl#targetClass> ?{0} }} }}",
+                var, OWL_CLASS
+            ),
+            // the classes of classes are types too, but drawing them only adds noise
+            ClassDeclaration::AnyType => format!(
+                "FILTER EXISTS {{ ?{0}_instance a ?{0} }} FILTER(?{0} NOT IN ({1}, <http://www.w3.org/2000/01/rdf-schema#Class>))",
+                var, OWL_CLASS
+            ),
+        }
+    }
+
+    /// SPARQL pattern binding `?{var}` to every class, once each
+    pub(crate) fn declared_classes(&self, var: &str) -> String {
+        match self.class_declaration {
+            ClassDeclaration::Owl => format!("?{} a {} .", var, OWL_CLASS),
+            _ => format!(
+                "{{ SELECT DISTINCT ?{0} WHERE {{ {{ ?{0} ?{0}_p ?{0}_o }} UNION {{ ?{0}_s ?{0}_p ?{0} }} {1} }} }}",
+                var,
+                self.declared_class_pattern(var)
+            ),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Curated class color maps for well-known ontologies
+
+use crate::NodeShape;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// A built-in class color map: the fill of the top-level classes of a well-known ontology,
+/// inherited by their subclasses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMap {
+    /// Brick locations, points and equipment
+    Brick,
+    /// ASHRAE 223P equipment, spaces, properties and connections
+    S223,
+    /// SOSA/SSN sensors, actuators, observations and the features they are about
+    Sosa,
+    /// PROV-O entities, activities and agents
+    Prov,
+}
+
+static BRICK: &[(&str, &str)] = &[
+    ("https://brickschema.org/schema/Brick#Location", "LightCoral"),
+    ("https://brickschema.org/schema/Brick#Point", "Gold"),
+    ("https://brickschema.org/schema/Brick#Equipment", "#32BF84"),
+];
+
+static S223: &[(&str, &str)] = &[
+    ("http://data.ashrae.org/standard223#Equipment", "#32BF84"),
+    ("http://data.ashrae.org/standard223#PhysicalSpace", "LightCoral"),
+    ("http://data.ashrae.org/standard223#DomainSpace", "LightSalmon"),
+    ("http://data.ashrae.org/standard223#Property", "Gold"),
+    ("http://data.ashrae.org/standard223#Connection", "LightSteelBlue"),
+    ("http://data.ashrae.org/standard223#ConnectionPoint", "LightBlue"),
+];
+
+static SOSA: &[(&str, &str)] = &[
+    ("http://www.w3.org/ns/sosa/Sensor", "Gold"),
+    ("http://www.w3.org/ns/sosa/Actuator", "Orange"),
+    ("http://www.w3.org/ns/sosa/Sampler", "Khaki"),
+    ("http://www.w3.org/ns/sosa/Platform", "#32BF84"),
+    ("http://www.w3.org/ns/sosa/Observation", "LightSkyBlue"),
+    ("http://www.w3.org/ns/sosa/Actuation", "LightSteelBlue"),
+    ("http://www.w3.org/ns/sosa/Sample", "Thistle"),
+    ("http://www.w3.org/ns/sosa/FeatureOfInterest", "LightCoral"),
+    ("http://www.w3.org/ns/sosa/ObservableProperty", "PaleGreen"),
+    ("http://www.w3.org/ns/sosa/ActuatableProperty", "PaleGreen"),
+    ("http://www.w3.org/ns/ssn/System", "#32BF84"),
+];
+
+/// The colors of the PROV-O spec's own diagrams
+static PROV: &[(&str, &str)] = &[
+    ("http://www.w3.org/ns/prov#Entity", "#FFFC87"),
+    ("http://www.w3.org/ns/prov#Activity", "#9FB1FC"),
+    ("http://www.w3.org/ns/prov#Agent", "#FED37F"),
+];
+
+static BRICK_SHAPES: &[(&str, NodeShape)] = &[
+    ("https://brickschema.org/schema/Brick#Location", NodeShape::House),
+    ("https://brickschema.org/schema/Brick#Point", NodeShape::Circle),
+    ("https://brickschema.org/schema/Brick#Equipment", NodeShape::Rectangle),
+];
+
+static S223_SHAPES: &[(&str, NodeShape)] = &[
+    ("http://data.ashrae.org/standard223#Equipment", NodeShape::Rectangle),
+    ("http://data.ashrae.org/standard223#PhysicalSpace", NodeShape::House),
+    ("http://data.ashrae.org/standard223#DomainSpace", NodeShape::Hexagon),
+    ("http://data.ashrae.org/standard223#Property", NodeShape::Circle),
+    ("http://data.ashrae.org/standard223#ConnectionPoint", NodeShape::Diamond),
+];
+
+static SOSA_SHAPES: &[(&str, NodeShape)] = &[
+    ("http://www.w3.org/ns/sosa/Sensor", NodeShape::Circle),
+    ("http://www.w3.org/ns/sosa/Actuator", NodeShape::Circle),
+    ("http://www.w3.org/ns/sosa/Platform", NodeShape::Rectangle),
+    ("http://www.w3.org/ns/sosa/Observation", NodeShape::Oval),
+    ("http://www.w3.org/ns/sosa/FeatureOfInterest", NodeShape::House),
+];
+
+/// The shapes of the PROV-O spec's own diagrams
+static PROV_SHAPES: &[(&str, NodeShape)] = &[
+    ("http://www.w3.org/ns/prov#Entity", NodeShape::Oval),
+    ("http://www.w3.org/ns/prov#Activity", NodeShape::Rectangle),
+    ("http://www.w3.org/ns/prov#Agent", NodeShape::House),
+];
+
+impl ColorMap {
+    /// (class IRI, color) of every category
+    pub fn entries(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            ColorMap::Brick => BRICK,
+            ColorMap::S223 => S223,
+            ColorMap::Sosa => SOSA,
+            ColorMap::Prov => PROV,
+        }
+    }
+
+    /// The map to pass to [`crate::Visualizer::new`]
+    pub fn to_map(&self) -> HashMap<&'static str, &'static str> {
+        self.entries().iter().copied().collect()
+    }
+
+    /// The shapes of the same categories, for [`crate::Visualizer::with_shape_map`]: e.g.
+    /// Brick points are circles, equipment rectangles and locations houses
+    pub fn shapes(&self) -> HashMap<&'static str, NodeShape> {
+        let shapes = match self {
+            ColorMap::Brick => BRICK_SHAPES,
+            ColorMap::S223 => S223_SHAPES,
+            ColorMap::Sosa => SOSA_SHAPES,
+            ColorMap::Prov => PROV_SHAPES,
+        };
+        shapes.iter().copied().collect()
+    }
+}
+
+impl FromStr for ColorMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "brick" => Ok(ColorMap::Brick),
+            "s223" | "223p" => Ok(ColorMap::S223),
+            "sosa" | "ssn" => Ok(ColorMap::Sosa),
+            "prov" => Ok(ColorMap::Prov),
+            _ => Err(format!(
+                "unknown color map '{}', expected one of brick, s223, sosa, prov",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Splitting the class graph into its connected components, for models made of several
+//! disconnected subsystems
+
+use crate::Visualizer;
+use petgraph::graph::NodeIndex;
+use std::collections::{HashSet, VecDeque};
+
+impl Visualizer<'_> {
+    /// The class labels of every connected component of the class graph, ignoring edge
+    /// directions. Components are sorted largest first, ties by their smallest label, and
+    /// the labels within one sorted, so their indices are stable across runs.
+    pub fn components(&self) -> Vec<Vec<String>> {
+        let mut seen: HashSet<NodeIndex> = HashSet::new();
+        let mut components = Vec::new();
+        for start in self.g.node_indices() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut component = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            while let Some(idx) = queue.pop_front() {
+                component.push(self.g[idx].to_string());
+                for next in self.g.neighbors_undirected(idx) {
+                    if seen.insert(next) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        components
+    }
+
+    /// Keep only the classes of the connected component at `index` of
+    /// [`Visualizer::components`] when building the graph, after every other filter; an index
+    /// past the last component leaves the graph empty
+    pub fn with_component(mut self, index: Option<usize>) -> Self {
+        self.component = index;
+        self
+    }
+
+    pub(crate) fn keep_component(&mut self, index: usize) {
+        let component: HashSet<String> = self.components().into_iter().nth(index).unwrap_or_default().into_iter().collect();
+        let keep = self.g.node_weights().filter(|label| component.contains(&***label)).cloned().collect();
+        let edges = self.g.edge_indices().collect();
+        self.retain(&keep, &edges);
+    }
+}
@@ -0,0 +1,145 @@
+//! Cycles of a single predicate in the class graph, like `brick:feeds` loops, which often point
+//! at modeling mistakes in HVAC topologies
+
+use crate::{Result, Visualizer};
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::Graph;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Color the edges of cycles are drawn in by [`Visualizer::with_cycle_highlight`]
+pub const CYCLE_COLOR: &str = "#d62728";
+
+/// Classes that reach each other through edges of one predicate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Cycle {
+    /// Full IRI of the predicate
+    pub predicate: String,
+    /// The classes on the cycle, sorted; a single class has an edge to itself
+    pub classes: Vec<String>,
+}
+
+impl Visualizer<'_> {
+    /// Draw the edges that lie on a cycle of their predicate in [`CYCLE_COLOR`]
+    pub fn with_cycle_highlight(mut self, highlight_cycles: bool) -> Self {
+        self.highlight_cycles = highlight_cycles;
+        self
+    }
+
+    /// Every cycle of the class graph made of edges of a single predicate, sorted by predicate
+    /// and then classes, with classes by label
+    pub fn cycles(&self) -> Vec<Cycle> {
+        self.predicate_cycles().into_iter().map(|(cycle, _)| cycle).collect()
+    }
+
+    /// Edges on one of the [`Visualizer::cycles`]
+    pub fn cycle_edges(&self) -> HashSet<EdgeIndex> {
+        self.predicate_cycles().into_iter().flat_map(|(_, edges)| edges).collect()
+    }
+
+    /// Remembers the cycle edges for the renderers, when [`Visualizer::with_cycle_highlight`]
+    /// is set
+    pub(crate) fn find_cycles(&mut self) -> Result<()> {
+        self.highlighted_cycle_edges = self.cycle_edges();
+        tracing::debug!(edges = self.highlighted_cycle_edges.len(), "found cycle edges");
+        Ok(())
+    }
+
+    /// The strongly connected components of the subgraph of every predicate that hold a cycle,
+    /// with the edges of that predicate inside them
+    fn predicate_cycles(&self) -> Vec<(Cycle, Vec<EdgeIndex>)> {
+        let mut by_predicate: BTreeMap<&str, Vec<EdgeIndex>> = BTreeMap::new();
+        for idx in self.g.edge_indices() {
+            if let Some(predicate) = self.edge_predicates.get(&idx) {
+                by_predicate.entry(predicate.as_ref()).or_default().push(idx);
+            }
+        }
+        let mut cycles = Vec::new();
+        for (predicate, edges) in by_predicate {
+            // the subgraph of one predicate, with its nodes pointing back to the class graph
+            let mut sub: Graph<NodeIndex, EdgeIndex> = Graph::new();
+            let mut nodes: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+            for &idx in edges.iter() {
+                let (from, to) = self.g.edge_endpoints(idx).expect("edges of the graph");
+                let from = *nodes.entry(from).or_insert_with(|| sub.add_node(from));
+                let to = *nodes.entry(to).or_insert_with(|| sub.add_node(to));
+                sub.add_edge(from, to, idx);
+            }
+            for component in tarjan_scc(&sub) {
+                let members: HashSet<NodeIndex> = component.iter().copied().collect();
+                let inner: Vec<EdgeIndex> = sub
+                    .edge_indices()
+                    .filter(|edge| {
+                        let (from, to) = sub.edge_endpoints(*edge).expect("edges of the subgraph");
+                        members.contains(&from) && members.contains(&to)
+                    })
+                    .map(|edge| sub[edge])
+                    .collect();
+                // a single class is only a cycle with an edge to itself
+                if inner.is_empty() {
+                    continue;
+                }
+                let mut classes: Vec<String> = component.iter().map(|node| self.g[sub[*node]].to_string()).collect();
+                classes.sort();
+                let cycle = Cycle {
+                    predicate: predicate.to_owned(),
+                    classes,
+                };
+                cycles.push((cycle, inner));
+            }
+        }
+        cycles.sort_by(|(a, _), (b, _)| (&a.predicate, &a.classes).cmp(&(&b.predicate, &b.classes)));
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::visualizer;
+
+    const FEEDS: &str = "https://brickschema.org/schema/Brick#feeds";
+
+    #[test]
+    fn cycles_follow_a_single_predicate() {
+        let ontology = "
+            brick:AHU a owl:Class .
+            brick:VAV a owl:Class .
+            brick:Chiller a owl:Class .
+            brick:Sensor a owl:Class .
+        ";
+        // AHU and VAV feed each other, a chiller feeds another one, and the VAV and sensor
+        // only loop through two different predicates
+        let data = "
+            ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 .
+            ex:vav1 a brick:VAV ; brick:feeds ex:ahu1 ; brick:hasPoint ex:sensor1 .
+            ex:sensor1 a brick:Sensor ; brick:isPointOf ex:vav1 .
+            ex:chiller1 a brick:Chiller ; brick:feeds ex:chiller2 .
+            ex:chiller2 a brick:Chiller .
+        ";
+        let mut v = visualizer(ontology, data).with_cycle_highlight(true);
+        v.build_graph().unwrap();
+        let cycle = |classes: &[&str]| Cycle {
+            predicate: FEEDS.to_owned(),
+            classes: classes.iter().map(|class| class.to_string()).collect(),
+        };
+        assert_eq!(v.cycles(), vec![cycle(&["brick_AHU", "brick_VAV"]), cycle(&["brick_Chiller"])]);
+
+        let edges = v.cycle_edges();
+        assert_eq!(edges.len(), 3);
+        assert!(edges.iter().all(|idx| &*v.edge_predicates()[idx] == FEEDS));
+        assert_eq!(v.highlighted_cycle_edges, edges);
+    }
+
+    #[test]
+    fn acyclic_graphs_have_no_cycles() {
+        let mut v = visualizer("brick:AHU a owl:Class . brick:VAV a owl:Class .", "
+            ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 .
+            ex:vav1 a brick:VAV .
+        ");
+        v.build_graph().unwrap();
+        assert!(v.cycles().is_empty());
+        assert!(v.cycle_edges().is_empty());
+    }
+}
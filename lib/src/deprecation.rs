@@ -0,0 +1,72 @@
+use crate::{iri, Result, Visualizer};
+use oxigraph::model::Term;
+use oxigraph::sparql::QueryResults;
+
+/// A class in the graph that the ontology marks `owl:deprecated`, with whatever migration
+/// hints it gives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// IRI of the deprecated class
+    pub iri: String,
+    /// Label of the class that replaces it (`brick:isReplacedBy` or `dcterms:isReplacedBy`)
+    pub replaced_by: Option<String>,
+    /// Ontology version in which the class was deprecated
+    pub version: Option<String>,
+    /// Free text explanation of the change
+    pub message: Option<String>,
+}
+
+impl Deprecation {
+    /// A one line "rename to X" suggestion for the class labeled `class`
+    pub fn suggestion(&self, class: &str) -> String {
+        let mut s = format!("{} is deprecated", class);
+        if let Some(version) = &self.version {
+            s.push_str(&format!(" since {}", version));
+        }
+        if let Some(replacement) = &self.replaced_by {
+            s.push_str(&format!("; rename to {}", replacement));
+        }
+        if let Some(message) = &self.message {
+            s.push_str(&format!(" ({})", message));
+        }
+        s
+    }
+}
+
+impl Visualizer<'_> {
+    /// Records the deprecated classes among those in the graph
+    pub(crate) fn find_deprecations(&mut self) -> Result<()> {
+        let q = "PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 PREFIX brick: <https://brickschema.org/schema/Brick#>
+                 PREFIX dcterms: <http://purl.org/dc/terms/>
+                 SELECT ?class (SAMPLE(?replacement) AS ?replaced_by) (SAMPLE(?version) AS ?since)
+                        (SAMPLE(?message) AS ?why) WHERE {
+                     ?class owl:deprecated true .
+                     OPTIONAL { ?class brick:isReplacedBy|dcterms:isReplacedBy ?replacement }
+                     OPTIONAL { ?class brick:deprecatedInVersion ?version }
+                     OPTIONAL { ?class brick:deprecationMitigationMessage ?message }
+                 } GROUP BY ?class";
+
+        if let QueryResults::Solutions(solutions) = self.query(q)? {
+            for row in solutions {
+                let row = row?;
+                let class = self.interner.label(row.get("class").unwrap());
+                if !self.node_iris.contains_key(&class) {
+                    continue;
+                }
+                let literal = |name| match row.get(name) {
+                    Some(Term::Literal(literal)) => Some(literal.value().to_owned()),
+                    _ => None,
+                };
+                let deprecation = Deprecation {
+                    iri: iri(row.get("class").unwrap()),
+                    replaced_by: row.get("replaced_by").map(|term| self.interner.label(term).to_string()),
+                    version: literal("since"),
+                    message: literal("why"),
+                };
+                self.deprecations.insert(class.to_string(), deprecation);
+            }
+        }
+        Ok(())
+    }
+}
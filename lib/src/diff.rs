@@ -0,0 +1,365 @@
+use crate::{GraphSource, NodeStyle, Result, Visualizer};
+use oxigraph::io::GraphFormat;
+use oxigraph::model::{GraphName, NamedNodeRef, Term};
+use oxigraph::sparql::QueryResults;
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+use std::sync::Arc;
+
+static OLD_GRAPH: NamedNodeRef<'static> = NamedNodeRef::new_unchecked("urn:rdf_class_viz:diff:old");
+static NEW_GRAPH: NamedNodeRef<'static> = NamedNodeRef::new_unchecked("urn:rdf_class_viz:diff:new");
+
+/// How an edge of a diff changed between the old and the new data graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeChange {
+    Added,
+    Removed,
+    Unchanged,
+}
+
+impl EdgeChange {
+    /// Stroke color of the edge: green when added, red when removed and dimmed when unchanged
+    pub fn color(&self) -> &'static str {
+        match self {
+            EdgeChange::Added => "green",
+            EdgeChange::Removed => "red",
+            EdgeChange::Unchanged => "#bbbbbb",
+        }
+    }
+}
+
+/// How a class changed between two versions of an ontology
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeChange {
+    Added,
+    Removed,
+    /// Replaced by the class with this label (`brick:isReplacedBy` or `dcterms:isReplacedBy`
+    /// in the new version)
+    Renamed(String),
+}
+
+impl NodeChange {
+    /// Border color of the class: green when added, red when removed and orange when renamed
+    pub fn color(&self) -> &'static str {
+        match self {
+            NodeChange::Added => "green",
+            NodeChange::Removed => "red",
+            NodeChange::Renamed(_) => "orange",
+        }
+    }
+
+    /// Label line describing the change
+    pub fn describe(&self) -> String {
+        match self {
+            NodeChange::Added => "added".to_owned(),
+            NodeChange::Removed => "removed".to_owned(),
+            NodeChange::Renamed(to) => format!("renamed to {}", to),
+        }
+    }
+}
+
+/// An edge of the old class graph, kept while the new one is built
+struct OldEdge {
+    from: Arc<str>,
+    to: Arc<str>,
+    predicate: Arc<str>,
+    label: Arc<str>,
+    count: usize,
+}
+
+/// A class of the old class graph, kept while the new one is built
+struct OldNode {
+    label: Arc<str>,
+    iri: Arc<str>,
+    color: String,
+    style: NodeStyle,
+    instances: Option<usize>,
+}
+
+impl Visualizer<'_> {
+    /// Builds the class graph of `new_graph` and adds the edges only found in `old_graph`,
+    /// marking every edge as added, removed or unchanged (see [`Visualizer::edge_changes`]).
+    /// Ontologies loaded with [`Visualizer::add_ontology`] are shared by both sides.
+    pub fn diff(
+        &mut self,
+        old_graph: impl BufRead,
+        old_format: GraphFormat,
+        new_graph: impl BufRead,
+        new_format: GraphFormat,
+    ) -> Result<()> {
+        self.load_into("load old data", old_graph, old_format, OLD_GRAPH.into())?;
+        self.load_into("load new data", new_graph, new_format, NEW_GRAPH.into())?;
+        self.diff_graphs()?;
+        self.sort_graph();
+        Ok(())
+    }
+
+    /// Compares two versions of an ontology: the schema graphs of both (see
+    /// [`GraphSource::Schema`]; shapes are compared instead in [`GraphSource::Shapes`] mode) are
+    /// diffed like [`Visualizer::diff`], and classes added, removed or renamed between the
+    /// versions are outlined and annotated (see [`Visualizer::node_changes`]).
+    pub fn diff_ontologies(
+        &mut self,
+        old_ontology: impl BufRead,
+        old_format: GraphFormat,
+        new_ontology: impl BufRead,
+        new_format: GraphFormat,
+    ) -> Result<()> {
+        self.load_into("load old ontology", old_ontology, old_format, OLD_GRAPH.into())?;
+        self.load_into("load new ontology", new_ontology, new_format, NEW_GRAPH.into())?;
+        let source = self.source;
+        if source == GraphSource::Instances {
+            self.source = GraphSource::Schema;
+        }
+        let diffed = self.diff_graphs().and_then(|_| self.diff_classes());
+        self.source = source;
+        diffed?;
+        self.sort_graph();
+        Ok(())
+    }
+
+    /// Builds the class graph of the new side and merges in the edges only found on the old one
+    fn diff_graphs(&mut self) -> Result<()> {
+        self.build_over(OLD_GRAPH)?;
+        let graph = self.graph();
+        let old_edges: Vec<OldEdge> = graph
+            .edge_references()
+            .map(|edge| OldEdge {
+                from: graph[edge.source()].clone(),
+                to: graph[edge.target()].clone(),
+                predicate: self.edge_predicates[&edge.id()].clone(),
+                label: edge.weight().clone(),
+                count: self.edge_counts.get(&edge.id()).copied().unwrap_or(0),
+            })
+            .collect();
+        let old_nodes: Vec<OldNode> = self
+            .node_iris
+            .iter()
+            .map(|(label, iri)| OldNode {
+                label: label.clone(),
+                iri: iri.clone(),
+                color: self.colors[label].clone(),
+                style: self.styles[label].clone(),
+                instances: self.instance_counts.get(&**label).copied(),
+            })
+            .collect();
+
+        self.build_over(NEW_GRAPH)?;
+        let old_keys: HashSet<(&str, &str, &str)> = old_edges
+            .iter()
+            .map(|edge| (&*edge.from, &*edge.to, &*edge.predicate))
+            .collect();
+        let mut new_keys = HashSet::new();
+        for edge in self.g.edge_references() {
+            let key = (
+                self.g[edge.source()].clone(),
+                self.g[edge.target()].clone(),
+                self.edge_predicates[&edge.id()].clone(),
+            );
+            let change = if old_keys.contains(&(&*key.0, &*key.1, &*key.2)) {
+                EdgeChange::Unchanged
+            } else {
+                EdgeChange::Added
+            };
+            self.edge_changes.insert(edge.id(), change);
+            new_keys.insert(key);
+        }
+
+        // classes that only appear in removed edges keep their old styling
+        for node in old_nodes {
+            if self.node_iris.contains_key(&node.label) {
+                continue;
+            }
+            self.node_iris.insert(node.label.clone(), node.iri);
+            self.colors.insert(node.label.clone(), node.color);
+            self.styles.insert(node.label.clone(), node.style);
+            if let Some(count) = node.instances {
+                self.instance_counts.insert(node.label.to_string(), count);
+            }
+        }
+        for edge in old_edges {
+            if new_keys.contains(&(edge.from.clone(), edge.to.clone(), edge.predicate.clone())) {
+                continue;
+            }
+            let from = self.node_index(&edge.from);
+            let to = self.node_index(&edge.to);
+            let idx = self.g.add_edge(from, to, edge.label);
+            self.edge_predicates.insert(idx, edge.predicate);
+            self.edge_counts.insert(idx, edge.count);
+            self.edge_changes.insert(idx, EdgeChange::Removed);
+        }
+        Ok(())
+    }
+
+    /// Records the classes added, removed and renamed between the old and new ontology, adding
+    /// them to the graph if no edge brought them in
+    fn diff_classes(&mut self) -> Result<()> {
+        let old = self.classes_in(OLD_GRAPH)?;
+        let new = self.classes_in(NEW_GRAPH)?;
+        let renames = self.renames_in(NEW_GRAPH)?;
+
+        let mut changes = Vec::new();
+        for class in old.iter() {
+            match renames.get(class) {
+                Some(to) => changes.push((class, OLD_GRAPH, NodeChange::Renamed(self.interner.label(to).to_string()))),
+                None if !new.contains(class) => changes.push((class, OLD_GRAPH, NodeChange::Removed)),
+                None => {}
+            }
+        }
+        for class in new.difference(&old) {
+            changes.push((class, NEW_GRAPH, NodeChange::Added));
+        }
+
+        for (class, side, change) in changes {
+            let label = self.interner.label(class);
+            // style the class with the version of the ontology it exists in
+            self.default_graph = Some(vec![GraphName::DefaultGraph, side.into_owned().into()]);
+            let styled = self.style_node(&label, class);
+            self.default_graph = None;
+            styled?;
+            self.node_index(&label);
+            if let Some(style) = self.styles.get_mut(&label) {
+                style.stroke = Some(change.color().to_owned());
+            }
+            self.node_changes.insert(label.to_string(), change);
+        }
+        Ok(())
+    }
+
+    /// Every class declared in `graph`
+    fn classes_in(&self, graph: NamedNodeRef) -> Result<HashSet<Term>> {
+        let q = format!("SELECT DISTINCT ?class WHERE {{ GRAPH {} {{ {} }} FILTER(isIRI(?class)) }}",
+                        graph, self.declared_classes("class"));
+        let mut classes = HashSet::new();
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                classes.insert(row?.get("class").unwrap().clone());
+            }
+        }
+        Ok(classes)
+    }
+
+    /// The replacement of every class `graph` says is replaced by another
+    fn renames_in(&self, graph: NamedNodeRef) -> Result<HashMap<Term, Term>> {
+        let q = format!("PREFIX brick: <https://brickschema.org/schema/Brick#>
+                 PREFIX dcterms: <http://purl.org/dc/terms/>
+                 SELECT ?class ?replacement WHERE {{
+                     GRAPH {} {{ ?class brick:isReplacedBy|dcterms:isReplacedBy ?replacement }}
+                 }}", graph);
+        let mut renames = HashMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                renames.insert(
+                    row.get("class").unwrap().clone(),
+                    row.get("replacement").unwrap().clone(),
+                );
+            }
+        }
+        Ok(renames)
+    }
+
+    /// Builds the class graph over the ontologies plus the data loaded into `graph`
+    fn build_over(&mut self, graph: NamedNodeRef) -> Result<()> {
+        self.default_graph = Some(vec![GraphName::DefaultGraph, graph.into_owned().into()]);
+        let built = self.build_graph();
+        self.default_graph = None;
+        built
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{turtle, visualizer};
+
+    const ONTOLOGY: &str = "
+        brick:AHU a owl:Class .
+        brick:VAV a owl:Class .
+        brick:Sensor a owl:Class .
+        brick:Damper a owl:Class .
+    ";
+
+    /// (from, predicate, to) labels of every edge with how it changed, sorted
+    fn edge_changes(v: &Visualizer) -> Vec<(String, String, String, EdgeChange)> {
+        let g = v.graph();
+        let mut changes: Vec<_> = g
+            .edge_references()
+            .map(|edge| {
+                let (from, p, to) = (&g[edge.source()], edge.weight(), &g[edge.target()]);
+                (from.to_string(), p.to_string(), to.to_string(), v.edge_changes()[&edge.id()])
+            })
+            .collect();
+        changes.sort_by(|a, b| (&a.0, &a.1, &a.2).cmp(&(&b.0, &b.1, &b.2)));
+        changes
+    }
+
+    fn change(from: &str, p: &str, to: &str, change: EdgeChange) -> (String, String, String, EdgeChange) {
+        (from.to_owned(), p.to_owned(), to.to_owned(), change)
+    }
+
+    #[test]
+    fn diff_marks_added_removed_and_unchanged_edges() {
+        let old = turtle("
+            ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 .
+            ex:vav1 a brick:VAV ; brick:hasPart ex:damper1 .
+            ex:damper1 a brick:Damper .
+        ");
+        let new = turtle("
+            ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 .
+            ex:vav1 a brick:VAV ; brick:hasPoint ex:sensor1 .
+            ex:sensor1 a brick:Sensor .
+        ");
+        let mut v = visualizer(ONTOLOGY, "");
+        v.diff(old.as_bytes(), GraphFormat::Turtle, new.as_bytes(), GraphFormat::Turtle).unwrap();
+        assert_eq!(edge_changes(&v), vec![
+            change("brick_AHU", "brick_feeds", "brick_VAV", EdgeChange::Unchanged),
+            change("brick_VAV", "brick_hasPart", "brick_Damper", EdgeChange::Removed),
+            change("brick_VAV", "brick_hasPoint", "brick_Sensor", EdgeChange::Added),
+        ]);
+        // the damper is only in the old data, and still drawn for its removed edge
+        assert!(v.node_iris().contains_key("brick_Damper"));
+        assert!(v.node_changes().is_empty());
+    }
+
+    #[test]
+    fn diff_ontologies_marks_added_removed_and_renamed_classes() {
+        let old = turtle("
+            brick:AHU a owl:Class .
+            brick:VAV a owl:Class .
+            brick:Damper a owl:Class .
+            brick:Temp_Sensor a owl:Class .
+            brick:feeds rdfs:domain brick:AHU ; rdfs:range brick:VAV .
+            brick:hasPart rdfs:domain brick:VAV ; rdfs:range brick:Damper .
+        ");
+        let new = turtle("
+            brick:AHU a owl:Class .
+            brick:VAV a owl:Class .
+            brick:Temperature_Sensor a owl:Class .
+            brick:Temp_Sensor brick:isReplacedBy brick:Temperature_Sensor .
+            brick:feeds rdfs:domain brick:AHU ; rdfs:range brick:VAV .
+            brick:hasPoint rdfs:domain brick:VAV ; rdfs:range brick:Temperature_Sensor .
+        ");
+        let mut v = visualizer("", "");
+        v.diff_ontologies(old.as_bytes(), GraphFormat::Turtle, new.as_bytes(), GraphFormat::Turtle)
+            .unwrap();
+        assert_eq!(edge_changes(&v), vec![
+            change("brick_AHU", "brick_feeds", "brick_VAV", EdgeChange::Unchanged),
+            change("brick_VAV", "brick_hasPart", "brick_Damper", EdgeChange::Removed),
+            change("brick_VAV", "brick_hasPoint", "brick_Temperature_Sensor", EdgeChange::Added),
+        ]);
+        let mut changes: Vec<_> = v.node_changes().iter().map(|(class, change)| (class.as_str(), change.clone())).collect();
+        changes.sort_by_key(|(class, _)| *class);
+        assert_eq!(changes, vec![
+            ("brick_Damper", NodeChange::Removed),
+            ("brick_Temp_Sensor", NodeChange::Renamed("brick_Temperature_Sensor".to_owned())),
+            ("brick_Temperature_Sensor", NodeChange::Added),
+        ]);
+        // a class no edge brought in is still drawn, outlined with its change
+        assert!(v.node_iris().contains_key("brick_Temp_Sensor"));
+        assert_eq!(v.styles()["brick_Temp_Sensor"].stroke.as_deref(), Some("orange"));
+        // the source the graph is built from is left as it was
+        assert_eq!(v.source, GraphSource::Instances);
+    }
+}
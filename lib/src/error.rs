@@ -0,0 +1,77 @@
+use oxigraph::io::read::ParseError;
+use oxigraph::model::IriParseError;
+use oxigraph::sparql::EvaluationError;
+use oxigraph::store::{LoaderError, StorageError};
+use crate::limits::limit_exceeded;
+use std::string::FromUtf8Error;
+use std::time::Duration;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong while loading, summarizing or rendering a graph
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An ontology or data graph is not valid RDF in the given format
+    #[error("failed to parse RDF")]
+    Parse(#[from] ParseError),
+    /// The underlying triple store failed
+    #[error("triple store failure")]
+    Storage(#[from] StorageError),
+    /// One of the extraction queries failed to evaluate
+    #[error("extraction query failed")]
+    Query(#[source] EvaluationError),
+    /// A query ran longer than [`crate::Visualizer::with_query_timeout`] allows
+    #[error("query timed out after {0:?}")]
+    Timeout(Duration),
+    /// A query returned more rows than [`crate::Visualizer::with_max_results`] allows
+    #[error("query returned more than {0} results")]
+    TooManyResults(usize),
+    /// An output could not be produced
+    #[error("failed to render output: {0}")]
+    Render(String),
+    /// Reading an input or writing an output file failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A string given as an IRI (e.g. a view name) is not a valid IRI
+    #[error("invalid IRI")]
+    InvalidIri(#[from] IriParseError),
+    /// A filter script is invalid
+    #[error("invalid filter: {0}")]
+    Filter(String),
+    /// A class the graph was asked to focus on does not appear in it
+    #[error("class {0} is not in the class graph")]
+    NotInGraph(String),
+    /// No chain of edges in the class graph links the two classes of a path query
+    #[error("no path between {0} and {1} in the class graph")]
+    NoPath(String, String),
+    /// A [`crate::GraphCache`] file is malformed or of another version
+    #[error("invalid graph cache: {0}")]
+    Cache(String),
+    /// Something tried to load triples into a visualizer created from a [`crate::Snapshot`]
+    #[error("cannot load triples into a visualizer created from a snapshot")]
+    ReadOnly,
+}
+
+impl From<LoaderError> for Error {
+    fn from(e: LoaderError) -> Self {
+        match e {
+            LoaderError::Parsing(e) => Error::Parse(e),
+            LoaderError::Storage(e) => Error::Storage(e),
+        }
+    }
+}
+
+impl From<EvaluationError> for Error {
+    fn from(e: EvaluationError) -> Self {
+        match limit_exceeded(&e) {
+            Some(exceeded) => exceeded.into(),
+            None => Error::Query(e),
+        }
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(e: FromUtf8Error) -> Self {
+        Error::Render(e.to_string())
+    }
+}
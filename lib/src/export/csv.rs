@@ -0,0 +1,55 @@
+use crate::{Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::io::Write;
+
+pub(super) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl Visualizer<'_> {
+    /// One row per class: `id,label,color,namespace,instance_count`, where the id is the class
+    /// IRI
+    pub fn nodes_csv(&self) -> Result<String> {
+        let mut w = Vec::new();
+        writeln!(w, "id,label,color,namespace,instance_count")?;
+        let graph = self.graph();
+        for idx in graph.node_indices() {
+            let label = &graph[idx];
+            let iri = self.node_iris().get(label).map(AsRef::as_ref).unwrap_or("");
+            writeln!(
+                w,
+                "{},{},{},{},{}",
+                csv_field(iri),
+                csv_field(label),
+                csv_field(self.colors().get(label).map(String::as_str).unwrap_or("")),
+                csv_field(crate::split_iri(iri).0),
+                self.instance_counts().get(&**label).unwrap_or(&0)
+            )?;
+        }
+        Ok(String::from_utf8(w)?)
+    }
+
+    /// One row per edge: `from,to,predicate,count` with class and predicate IRIs and the
+    /// number of instance pairs supporting the edge
+    pub fn edges_csv(&self) -> Result<String> {
+        let mut w = Vec::new();
+        writeln!(w, "from,to,predicate,count")?;
+        let graph = self.graph();
+        let iri = |idx| self.node_iris().get(&graph[idx]).map(AsRef::as_ref).unwrap_or("");
+        for edge in graph.edge_references() {
+            writeln!(
+                w,
+                "{},{},{},{}",
+                csv_field(iri(edge.source())),
+                csv_field(iri(edge.target())),
+                csv_field(&self.edge_predicates()[&edge.id()]),
+                self.edge_counts().get(&edge.id()).unwrap_or(&0)
+            )?;
+        }
+        Ok(String::from_utf8(w)?)
+    }
+}
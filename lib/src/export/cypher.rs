@@ -0,0 +1,65 @@
+use crate::{split_iri, Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::io::Write;
+use std::sync::Arc;
+
+/// A single-quoted Cypher string literal
+fn cypher_string(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// A backquoted Cypher name, e.g. of a relationship type
+fn cypher_name(s: &str) -> String {
+    format!("`{}`", s.replace('`', "``"))
+}
+
+impl Visualizer<'_> {
+    /// Cypher script creating the class graph in Neo4j: one `(:Class {iri})` node per class with
+    /// its `label`, `color` and `instances`, and one relationship per edge, typed with the local
+    /// name of its predicate and carrying the predicate IRI and `count` of instance pairs. It
+    /// only `MERGE`s, so running it again updates the same nodes and relationships.
+    pub fn graph_to_cypher(&self) -> Result<String> {
+        let mut w = Vec::new();
+        writeln!(w, "CREATE CONSTRAINT class_iri IF NOT EXISTS FOR (c:Class) REQUIRE c.iri IS UNIQUE;")?;
+        // classes without an IRI, like the "other" node of --max-nodes, are keyed by label
+        let iri = |label: &Arc<str>| self.node_iris().get(label).unwrap_or(label).clone();
+        let graph = self.graph();
+        let mut nodes: Vec<&Arc<str>> = graph.node_weights().collect();
+        nodes.sort();
+        for label in nodes {
+            let mut set = vec![format!("c.label = {}", cypher_string(label))];
+            if let Some(color) = self.colors().get(label) {
+                set.push(format!("c.color = {}", cypher_string(color)));
+            }
+            if let Some(count) = self.instance_counts().get(&**label) {
+                set.push(format!("c.instances = {}", count));
+            }
+            writeln!(
+                w,
+                "MERGE (c:Class {{iri: {}}}) SET {};",
+                cypher_string(&iri(label)),
+                set.join(", ")
+            )?;
+        }
+
+        let mut edges: Vec<_> = graph.edge_references().collect();
+        edges.sort_by_key(|edge| (&graph[edge.source()], &graph[edge.target()], edge.weight()));
+        for edge in edges {
+            let predicate = &self.edge_predicates()[&edge.id()];
+            let count = match self.edge_counts().get(&edge.id()) {
+                Some(count) => format!(" SET r.count = {}", count),
+                None => String::new(),
+            };
+            writeln!(
+                w,
+                "MATCH (a:Class {{iri: {}}}), (b:Class {{iri: {}}}) MERGE (a)-[r:{} {{predicate: {}}}]->(b){};",
+                cypher_string(&iri(&graph[edge.source()])),
+                cypher_string(&iri(&graph[edge.target()])),
+                cypher_name(split_iri(predicate).1),
+                cypher_string(predicate),
+                count
+            )?;
+        }
+        Ok(String::from_utf8(w)?)
+    }
+}
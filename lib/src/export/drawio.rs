@@ -0,0 +1,111 @@
+use crate::export::xml_escape;
+use crate::render::CircleLayout;
+use crate::{LineStyle, Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::collections::HashMap;
+use std::io::Write;
+
+impl Visualizer<'_> {
+    /// diagrams.net (draw.io) file of the class graph: an mxGraph model with every class as a
+    /// box in its fill, stroke, font color and shape, laid out on a circle like the builtin
+    /// engine does, and every edge as a connector styled like in the other renderers. The
+    /// boxes can then be moved and edited by hand in draw.io.
+    pub fn graph_to_drawio(&self) -> Result<String> {
+        let CircleLayout { size, centers: layout } = self.circle_layout();
+        let font_sizes = self.node_font_sizes();
+        let (line_color, outline, font) = match self.theme {
+            Some(theme) => (theme.line_color(), theme.line_color(), theme.font_color()),
+            None => ("#555555", "#333333", "#000000"),
+        };
+
+        let mut w = Vec::new();
+        writeln!(w, "<mxfile host=\"rdf_class_viz\">")?;
+        writeln!(w, "  <diagram id=\"class-graph\" name=\"Class graph\">")?;
+        writeln!(
+            w,
+            "    <mxGraphModel grid=\"1\" gridSize=\"10\" guides=\"1\" connect=\"1\" arrows=\"1\" page=\"0\" pageWidth=\"{0:.0}\" pageHeight=\"{0:.0}\"{1}>",
+            size,
+            self.theme.map_or_else(String::new, |theme| format!(" background=\"{}\"", theme.background()))
+        )?;
+        writeln!(w, "      <root>")?;
+        writeln!(w, "        <mxCell id=\"0\"/>")?;
+        writeln!(w, "        <mxCell id=\"1\" parent=\"0\"/>")?;
+
+        let mut ids: HashMap<&str, String> = HashMap::new();
+        for (i, (node, (x, y))) in layout.iter().enumerate() {
+            let id = format!("n{}", i);
+            let font_size = font_sizes.get(*node).copied().unwrap_or(12.0);
+            let scale = font_size / 12.0;
+            let width = node.len() as f64 * 7.5 * scale + 20.0;
+            let height = 28.0 * scale;
+            let style = self.styles().get(*node).cloned().unwrap_or_default();
+            let mut keys = String::from("whiteSpace=wrap;html=1;");
+            keys.push_str(style.shape.map_or("rounded=1;", |shape| shape.drawio_style()));
+            keys.push_str(&format!(
+                "fillColor={};strokeColor={};fontColor={};fontSize={:.0};",
+                self.colors().get(*node).map_or(self.default_fill(), String::as_str),
+                style.stroke.as_deref().unwrap_or(outline),
+                style.font_color.as_deref().unwrap_or(font),
+                font_size
+            ));
+            if self.is_unknown_class(node) {
+                keys.push_str("dashed=1;");
+            }
+            writeln!(
+                w,
+                "        <mxCell id=\"{}\" value=\"{}\" style=\"{}\" vertex=\"1\" parent=\"1\">",
+                id,
+                xml_escape(node),
+                xml_escape(&keys)
+            )?;
+            writeln!(
+                w,
+                "          <mxGeometry x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" as=\"geometry\"/>",
+                x - width / 2.0,
+                y - height / 2.0,
+                width,
+                height
+            )?;
+            writeln!(w, "        </mxCell>")?;
+            ids.insert(*node, id);
+        }
+
+        let graph = self.graph();
+        let widths = self.edge_widths();
+        for edge in graph.edge_references() {
+            let (Some(source), Some(target)) = (ids.get(&*graph[edge.source()]), ids.get(&*graph[edge.target()])) else {
+                continue;
+            };
+            let style = self.resolved_edge_style(edge.id());
+            let color = style.stroke.as_deref().unwrap_or(line_color);
+            let mut keys = format!(
+                "endArrow=classic;html=1;strokeColor={};fontColor={};strokeWidth={:.1};",
+                color,
+                style.font_color.as_deref().unwrap_or(color),
+                widths.get(&edge.id()).copied().unwrap_or(1.0)
+            );
+            match style.line {
+                Some(LineStyle::Dashed) => keys.push_str("dashed=1;"),
+                Some(LineStyle::Dotted) => keys.push_str("dashed=1;dashPattern=1 3;"),
+                _ => {}
+            }
+            writeln!(
+                w,
+                "        <mxCell id=\"e{}\" value=\"{}\" style=\"{}\" edge=\"1\" parent=\"1\" source=\"{}\" target=\"{}\">",
+                edge.id().index(),
+                xml_escape(edge.weight()),
+                xml_escape(&keys),
+                source,
+                target
+            )?;
+            writeln!(w, "          <mxGeometry relative=\"1\" as=\"geometry\"/>")?;
+            writeln!(w, "        </mxCell>")?;
+        }
+
+        writeln!(w, "      </root>")?;
+        writeln!(w, "    </mxGraphModel>")?;
+        writeln!(w, "  </diagram>")?;
+        writeln!(w, "</mxfile>")?;
+        Ok(String::from_utf8(w)?)
+    }
+}
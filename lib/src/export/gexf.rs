@@ -0,0 +1,80 @@
+use crate::export::xml_escape;
+use crate::{Result, Rgb, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::io::Write;
+
+impl Visualizer<'_> {
+    /// GEXF 1.3 document of the class graph for Gephi. Nodes carry `namespace`,
+    /// `instance_count` and `color` attributes (plus a `viz:color`) so they can drive sizing and
+    /// partitioning there.
+    pub fn graph_to_gexf(&self) -> Result<String> {
+        let mut w = Vec::new();
+        writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            w,
+            "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">"
+        )?;
+        writeln!(w, "  <graph defaultedgetype=\"directed\">")?;
+        writeln!(w, "    <attributes class=\"node\">")?;
+        writeln!(w, "      <attribute id=\"namespace\" title=\"namespace\" type=\"string\"/>")?;
+        writeln!(w, "      <attribute id=\"instance_count\" title=\"instance_count\" type=\"integer\"/>")?;
+        writeln!(w, "      <attribute id=\"color\" title=\"color\" type=\"string\"/>")?;
+        writeln!(w, "    </attributes>")?;
+
+        let graph = self.graph();
+        writeln!(w, "    <nodes>")?;
+        for idx in graph.node_indices() {
+            let label = &graph[idx];
+            let iri = self.node_iris().get(label).map(AsRef::as_ref).unwrap_or("");
+            let color = self.colors().get(label).map(String::as_str).unwrap_or("");
+            writeln!(
+                w,
+                "      <node id=\"{}\" label=\"{}\">",
+                idx.index(),
+                xml_escape(label)
+            )?;
+            writeln!(w, "        <attvalues>")?;
+            writeln!(
+                w,
+                "          <attvalue for=\"namespace\" value=\"{}\"/>",
+                xml_escape(crate::split_iri(iri).0)
+            )?;
+            writeln!(
+                w,
+                "          <attvalue for=\"instance_count\" value=\"{}\"/>",
+                self.instance_counts().get(&**label).unwrap_or(&0)
+            )?;
+            writeln!(
+                w,
+                "          <attvalue for=\"color\" value=\"{}\"/>",
+                xml_escape(color)
+            )?;
+            writeln!(w, "        </attvalues>")?;
+            if let Some(rgb) = Rgb::parse(color) {
+                writeln!(
+                    w,
+                    "        <viz:color r=\"{}\" g=\"{}\" b=\"{}\"/>",
+                    rgb.r, rgb.g, rgb.b
+                )?;
+            }
+            writeln!(w, "      </node>")?;
+        }
+        writeln!(w, "    </nodes>")?;
+
+        writeln!(w, "    <edges>")?;
+        for edge in graph.edge_references() {
+            writeln!(
+                w,
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\" label=\"{}\"/>",
+                edge.id().index(),
+                edge.source().index(),
+                edge.target().index(),
+                xml_escape(edge.weight())
+            )?;
+        }
+        writeln!(w, "    </edges>")?;
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</gexf>")?;
+        Ok(String::from_utf8(w)?)
+    }
+}
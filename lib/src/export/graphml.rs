@@ -0,0 +1,96 @@
+use crate::export::xml_escape;
+use crate::{Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::io::Write;
+
+impl Visualizer<'_> {
+    /// Streams the class graph as GraphML, one node or edge element at a time, so the document
+    /// is never held in memory. Nodes carry `label`, `iri`, `color` and `instance_count` data,
+    /// edges `label`, `predicate`, `count`, `derived` and, for annotated edges, `annotations`
+    /// as `property=value|value; ...`.
+    pub fn write_graphml(&self, mut w: impl Write) -> Result<()> {
+        writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(w, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">")?;
+        for (id, domain, kind) in [
+            ("label", "node", "string"),
+            ("iri", "node", "string"),
+            ("color", "node", "string"),
+            ("instance_count", "node", "int"),
+            ("edge_label", "edge", "string"),
+            ("predicate", "edge", "string"),
+            ("count", "edge", "int"),
+            ("derived", "edge", "boolean"),
+            ("annotations", "edge", "string"),
+        ] {
+            let name = id.trim_start_matches("edge_");
+            writeln!(
+                w,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, domain, name, kind
+            )?;
+        }
+        writeln!(w, "  <graph edgedefault=\"directed\">")?;
+
+        let graph = self.graph();
+        for idx in graph.node_indices() {
+            let label = &graph[idx];
+            writeln!(w, "    <node id=\"n{}\">", idx.index())?;
+            writeln!(w, "      <data key=\"label\">{}</data>", xml_escape(label))?;
+            if let Some(iri) = self.node_iris().get(label) {
+                writeln!(w, "      <data key=\"iri\">{}</data>", xml_escape(iri))?;
+            }
+            if let Some(color) = self.colors().get(label) {
+                writeln!(w, "      <data key=\"color\">{}</data>", xml_escape(color))?;
+            }
+            writeln!(
+                w,
+                "      <data key=\"instance_count\">{}</data>",
+                self.instance_counts().get(&**label).unwrap_or(&0)
+            )?;
+            writeln!(w, "    </node>")?;
+        }
+
+        for edge in graph.edge_references() {
+            writeln!(
+                w,
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">",
+                edge.id().index(),
+                edge.source().index(),
+                edge.target().index()
+            )?;
+            writeln!(w, "      <data key=\"edge_label\">{}</data>", xml_escape(edge.weight()))?;
+            if let Some(predicate) = self.edge_predicates().get(&edge.id()) {
+                writeln!(w, "      <data key=\"predicate\">{}</data>", xml_escape(predicate))?;
+            }
+            writeln!(
+                w,
+                "      <data key=\"count\">{}</data>",
+                self.edge_counts().get(&edge.id()).unwrap_or(&0)
+            )?;
+            writeln!(
+                w,
+                "      <data key=\"derived\">{}</data>",
+                self.derived_edges().contains(&edge.id())
+            )?;
+            if let Some(annotations) = self.edge_annotations().get(&edge.id()) {
+                let annotations: Vec<String> = annotations
+                    .iter()
+                    .map(|(property, values)| {
+                        format!("{}={}", property, values.iter().cloned().collect::<Vec<_>>().join("|"))
+                    })
+                    .collect();
+                writeln!(
+                    w,
+                    "      <data key=\"annotations\">{}</data>",
+                    xml_escape(&annotations.join("; "))
+                )?;
+            }
+            writeln!(w, "    </edge>")?;
+        }
+
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")?;
+        w.flush()?;
+        Ok(())
+    }
+}
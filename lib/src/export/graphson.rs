@@ -0,0 +1,81 @@
+use crate::{split_iri, Error, Result, Visualizer};
+use petgraph::graph::EdgeReference;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use std::io::Write;
+
+/// A GraphSON 3.0 typed 64-bit integer
+fn int64(n: usize) -> Value {
+    json!({"@type": "g:Int64", "@value": n})
+}
+
+impl Visualizer<'_> {
+    /// GraphSON 3.0 of the class graph in TinkerPop's adjacency list layout, one vertex per
+    /// line, as read by `g.io(path).read()` in JanusGraph, Neptune and other TinkerPop
+    /// databases. Vertices are labeled `Class` and carry `iri`, `name`, `color` and
+    /// `instance_count`; edges are labeled with the local name of their predicate and carry
+    /// the `predicate` IRI and their `count`.
+    pub fn graph_to_graphson(&self) -> Result<String> {
+        let graph = self.graph();
+        let mut property_id = 0;
+        let mut vertex_property = |value: Value| {
+            property_id += 1;
+            json!([{"id": int64(property_id - 1), "value": value}])
+        };
+        // an edge of the outE (pointing at its inV) or inE (coming from its outV) of a vertex
+        let edge_json = |edge: EdgeReference<Arc<str>>, outgoing: bool| {
+            let mut value = json!({
+                "id": int64(edge.id().index()),
+                "properties": {
+                    "predicate": &*self.edge_predicates()[&edge.id()],
+                    "count": int64(self.edge_counts().get(&edge.id()).copied().unwrap_or(0)),
+                },
+            });
+            if outgoing {
+                value["inV"] = int64(edge.target().index());
+            } else {
+                value["outV"] = int64(edge.source().index());
+            }
+            value
+        };
+        // edges grouped by label, for the outE and inE of a vertex
+        let adjacent = |idx, direction| {
+            let mut by_label: Map<String, Value> = Map::new();
+            for edge in graph.edges_directed(idx, direction) {
+                let label = split_iri(&self.edge_predicates()[&edge.id()]).1.to_owned();
+                let edges = by_label.entry(label).or_insert_with(|| Value::Array(Vec::new()));
+                if let Value::Array(edges) = edges {
+                    edges.push(edge_json(edge, direction == Direction::Outgoing));
+                }
+            }
+            Value::Object(by_label)
+        };
+
+        let mut w = Vec::new();
+        for idx in graph.node_indices() {
+            let label = &graph[idx];
+            let mut properties = Map::new();
+            if let Some(iri) = self.node_iris().get(label) {
+                properties.insert("iri".to_owned(), vertex_property(json!(&**iri)));
+            }
+            properties.insert("name".to_owned(), vertex_property(json!(&**label)));
+            if let Some(color) = self.colors().get(label) {
+                properties.insert("color".to_owned(), vertex_property(json!(color)));
+            }
+            let count = self.instance_counts().get(&**label).copied().unwrap_or(0);
+            properties.insert("instance_count".to_owned(), vertex_property(int64(count)));
+            let vertex = json!({
+                "id": int64(idx.index()),
+                "label": "Class",
+                "outE": adjacent(idx, Direction::Outgoing),
+                "inE": adjacent(idx, Direction::Incoming),
+                "properties": properties,
+            });
+            serde_json::to_writer(&mut w, &vertex).map_err(|e| Error::Render(e.to_string()))?;
+            writeln!(w)?;
+        }
+        Ok(String::from_utf8(w)?)
+    }
+}
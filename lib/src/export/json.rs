@@ -0,0 +1,114 @@
+use crate::{Annotations, Error, Result, Visualizer};
+use petgraph::graph::{EdgeReference, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::Arc;
+
+/// Version of the [`ClassGraphJson`] schema, bumped on any incompatible change
+pub const CLASS_GRAPH_JSON_VERSION: u32 = 1;
+
+/// The JSON export of the class graph, as written by [`Visualizer::write_json`]. Tools reading
+/// the export can deserialize it into this structure; fields are only ever added within a
+/// [`CLASS_GRAPH_JSON_VERSION`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassGraphJson {
+    pub version: u32,
+    pub nodes: Vec<NodeJson>,
+    pub edges: Vec<EdgeJson>,
+}
+
+/// A class of the [`ClassGraphJson`] export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeJson {
+    /// Referred to by the `from` and `to` of edges
+    pub id: usize,
+    pub label: String,
+    pub iri: Option<String>,
+    pub color: Option<String>,
+    /// Instances of the class, named as in the GraphML, GEXF and CSV exports
+    pub instance_count: usize,
+    /// Ontology declaring the class, when provenance is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// An edge of the [`ClassGraphJson`] export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EdgeJson {
+    pub from: usize,
+    pub to: usize,
+    pub label: String,
+    pub predicate: Option<String>,
+    /// Instance pairs supporting the edge
+    pub count: usize,
+    pub derived: bool,
+    /// Annotation property IRI to its values, for edges backed by annotated statements
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
+impl ClassGraphJson {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+fn write_value(w: &mut impl Write, value: &impl Serialize) -> Result<()> {
+    serde_json::to_writer(w, value).map_err(|e| Error::Render(e.to_string()))
+}
+
+impl Visualizer<'_> {
+    /// The class graph as a [`ClassGraphJson`] document held in memory
+    pub fn class_graph_json(&self) -> ClassGraphJson {
+        let graph = self.graph();
+        ClassGraphJson {
+            version: CLASS_GRAPH_JSON_VERSION,
+            nodes: graph.node_indices().map(|idx| self.node_json(idx)).collect(),
+            edges: graph.edge_references().map(|edge| self.edge_json(edge)).collect(),
+        }
+    }
+
+    /// Streams the class graph as a [`ClassGraphJson`] document, serializing one element at a
+    /// time so the document is never held in memory
+    pub fn write_json(&self, mut w: impl Write) -> Result<()> {
+        let graph = self.graph();
+        write!(w, "{{\"version\":{},\"nodes\":[", CLASS_GRAPH_JSON_VERSION)?;
+        for (i, idx) in graph.node_indices().enumerate() {
+            writeln!(w, "{}", if i > 0 { "," } else { "" })?;
+            write_value(&mut w, &self.node_json(idx))?;
+        }
+        write!(w, "\n],\"edges\":[")?;
+        for (i, edge) in graph.edge_references().enumerate() {
+            writeln!(w, "{}", if i > 0 { "," } else { "" })?;
+            write_value(&mut w, &self.edge_json(edge))?;
+        }
+        writeln!(w, "\n]}}")?;
+        w.flush()?;
+        Ok(())
+    }
+
+    fn node_json(&self, idx: NodeIndex) -> NodeJson {
+        let label = &self.graph()[idx];
+        NodeJson {
+            id: idx.index(),
+            label: label.to_string(),
+            iri: self.node_iris().get(label).map(|iri| iri.to_string()),
+            color: self.colors().get(label).cloned(),
+            instance_count: self.instance_counts().get(&**label).copied().unwrap_or(0),
+            source: self.provenance().get(&**label).cloned(),
+        }
+    }
+
+    fn edge_json(&self, edge: EdgeReference<Arc<str>>) -> EdgeJson {
+        EdgeJson {
+            from: edge.source().index(),
+            to: edge.target().index(),
+            label: edge.weight().to_string(),
+            predicate: self.edge_predicates().get(&edge.id()).map(|predicate| predicate.to_string()),
+            count: self.edge_counts().get(&edge.id()).copied().unwrap_or(0),
+            derived: self.derived_edges().contains(&edge.id()),
+            annotations: self.edge_annotations().get(&edge.id()).cloned(),
+        }
+    }
+}
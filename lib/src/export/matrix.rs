@@ -0,0 +1,112 @@
+use super::csv::csv_field;
+use crate::export::xml_escape;
+use crate::{Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::Arc;
+
+/// The `(predicate label, instance pairs)` of the edges between two classes, by source and
+/// target class label
+type Cells<'g> = BTreeMap<(&'g Arc<str>, &'g Arc<str>), Vec<(&'g Arc<str>, Option<usize>)>>;
+
+impl Visualizer<'_> {
+    /// Class labels in order, and the cells of the matrix
+    fn adjacency(&self) -> (Vec<&Arc<str>>, Cells<'_>) {
+        let mut classes: Vec<&Arc<str>> = self.g.node_weights().collect();
+        classes.sort();
+        let mut cells = Cells::new();
+        for edge in self.g.edge_references() {
+            cells
+                .entry((&self.g[edge.source()], &self.g[edge.target()]))
+                .or_default()
+                .push((edge.weight(), self.edge_counts.get(&edge.id()).copied()));
+        }
+        for predicates in cells.values_mut() {
+            predicates.sort();
+        }
+        (classes, cells)
+    }
+
+    /// The class-to-class adjacency matrix as CSV: one row per source class, one column per
+    /// target class, and cells listing the predicates between them with their counts, e.g.
+    /// `brick_feeds (12); brick_hasPart (3)`
+    pub fn matrix_csv(&self) -> Result<String> {
+        let (classes, cells) = self.adjacency();
+        let mut w = Vec::new();
+        let header: Vec<String> = classes.iter().map(|class| csv_field(class)).collect();
+        writeln!(w, ",{}", header.join(","))?;
+        for from in classes.iter() {
+            let row: Vec<String> = classes
+                .iter()
+                .map(|to| csv_field(&describe(cells.get(&(*from, *to)).map(Vec::as_slice).unwrap_or_default())))
+                .collect();
+            writeln!(w, "{},{}", csv_field(from), row.join(","))?;
+        }
+        Ok(String::from_utf8(w)?)
+    }
+
+    /// The adjacency matrix of [`Visualizer::matrix_csv`] as a standalone HTML page, with the
+    /// class headers in their class colors and cells shaded by their number of instance pairs
+    pub fn matrix_html(&self) -> Result<String> {
+        let (classes, cells) = self.adjacency();
+        let max = cells.values().map(Vec::as_slice).map(pairs).max().unwrap_or(0).max(1);
+        let header = |class: &Arc<str>| {
+            let color = self.colors.get(class).map_or(self.default_fill(), String::as_str);
+            format!("style=\"background:{}\"", xml_escape(color))
+        };
+
+        let mut w = Vec::new();
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html><head><meta charset=\"utf-8\"><title>Class adjacency matrix</title>")?;
+        writeln!(
+            w,
+            "<style>table {{ border-collapse: collapse; font: 12px sans-serif; }} th, td {{ border: 1px solid #ccc; padding: 4px; }} thead th {{ writing-mode: vertical-rl; }} td {{ white-space: pre; }}</style>"
+        )?;
+        writeln!(w, "</head><body><table>")?;
+        write!(w, "<thead><tr><th>from \\ to</th>")?;
+        for class in classes.iter() {
+            write!(w, "<th {}>{}</th>", header(class), xml_escape(class))?;
+        }
+        writeln!(w, "</tr></thead><tbody>")?;
+        for from in classes.iter() {
+            write!(w, "<tr><th {}>{}</th>", header(from), xml_escape(from))?;
+            for to in classes.iter() {
+                match cells.get(&(*from, *to)) {
+                    Some(predicates) => {
+                        // shade by share of the busiest cell, but keep every edge visible
+                        let alpha = 0.1 + 0.6 * pairs(predicates) as f64 / max as f64;
+                        let lines: Vec<String> = predicates.iter().map(|predicate| xml_escape(&describe(&[*predicate]))).collect();
+                        write!(
+                            w,
+                            "<td style=\"background:rgba(31,119,180,{:.2})\">{}</td>",
+                            alpha,
+                            lines.join("\n")
+                        )?;
+                    }
+                    None => write!(w, "<td></td>")?,
+                }
+            }
+            writeln!(w, "</tr>")?;
+        }
+        writeln!(w, "</tbody></table></body></html>")?;
+        Ok(String::from_utf8(w)?)
+    }
+}
+
+/// Instance pairs behind all the predicates of a cell
+fn pairs(predicates: &[(&Arc<str>, Option<usize>)]) -> usize {
+    predicates.iter().filter_map(|(_, count)| *count).sum()
+}
+
+/// `predicate (count)` of every predicate of a cell, separated by semicolons
+fn describe(predicates: &[(&Arc<str>, Option<usize>)]) -> String {
+    let parts: Vec<String> = predicates
+        .iter()
+        .map(|(predicate, count)| match count {
+            Some(count) => format!("{} ({})", predicate, count),
+            None => predicate.to_string(),
+        })
+        .collect();
+    parts.join("; ")
+}
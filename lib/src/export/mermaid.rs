@@ -0,0 +1,48 @@
+use crate::{LineStyle, Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Text of a quoted Mermaid label, which takes HTML entities instead of backslash escapes
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;").replace('\n', "<br>")
+}
+
+impl Visualizer<'_> {
+    /// Mermaid flowchart of the class graph, with nodes filled in their class colors and
+    /// derived edges dotted, for Markdown documents rendered by GitHub, GitLab and the like
+    pub fn graph_to_mermaid(&self) -> Result<String> {
+        let mut w = Vec::new();
+        writeln!(w, "flowchart LR")?;
+        // class labels are not valid Mermaid ids in general, so number the nodes
+        let mut ids: HashMap<&str, String> = HashMap::new();
+        for (i, (node, _)) in self.in_order(&self.colors).into_iter().enumerate() {
+            let id = format!("n{}", i);
+            writeln!(w, "    {}[\"{}\"]", id, mermaid_escape(node))?;
+            ids.insert(node.as_ref(), id);
+        }
+        for edge in self.g.edge_references() {
+            let (Some(from), Some(to)) = (ids.get(&*self.g[edge.source()]), ids.get(&*self.g[edge.target()])) else {
+                continue;
+            };
+            let arrow = match self.resolved_edge_style(edge.id()).line {
+                Some(LineStyle::Dashed | LineStyle::Dotted) => "-.->",
+                _ => "-->",
+            };
+            writeln!(w, "    {} {}|\"{}\"| {}", from, arrow, mermaid_escape(edge.weight()), to)?;
+        }
+        for (node, color) in self.in_order(&self.colors) {
+            let mut style = vec![format!("fill:{}", color)];
+            if let Some(node_style) = self.styles.get(node) {
+                if let Some(stroke) = &node_style.stroke {
+                    style.push(format!("stroke:{}", stroke));
+                }
+                if let Some(font_color) = &node_style.font_color {
+                    style.push(format!("color:{}", font_color));
+                }
+            }
+            writeln!(w, "    style {} {}", ids[node.as_ref()], style.join(","))?;
+        }
+        Ok(String::from_utf8(w)?)
+    }
+}
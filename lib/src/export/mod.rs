@@ -0,0 +1,27 @@
+//! Serializers of the class graph into formats other tools can consume
+
+mod csv;
+mod cypher;
+mod drawio;
+mod gexf;
+mod graphml;
+mod graphson;
+mod json;
+mod matrix;
+mod mermaid;
+mod term;
+mod trig;
+mod void;
+mod vowl;
+
+pub use json::{ClassGraphJson, EdgeJson, NodeJson, CLASS_GRAPH_JSON_VERSION};
+pub use term::TermCharset;
+pub use trig::write_trig;
+pub use void::VOID_DATASET;
+
+pub(crate) fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,84 @@
+use crate::{LineStyle, Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Characters the terminal rendering is drawn with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TermCharset {
+    /// Box-drawing characters and arrows
+    #[default]
+    Unicode,
+    /// Plain ASCII, for terminals and logs that mangle anything else
+    Ascii,
+}
+
+impl TermCharset {
+    /// (branch, last branch, solid arrow, dashed arrow)
+    fn glyphs(self) -> (&'static str, &'static str, &'static str, &'static str) {
+        match self {
+            TermCharset::Unicode => ("├──", "└──", "───▶", "╌╌╌▶"),
+            TermCharset::Ascii => ("|--", "`--", "--->", "...>"),
+        }
+    }
+}
+
+impl FromStr for TermCharset {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "unicode" => Ok(TermCharset::Unicode),
+            "ascii" => Ok(TermCharset::Ascii),
+            _ => Err(format!("unknown charset '{}', expected unicode or ascii", s)),
+        }
+    }
+}
+
+impl Visualizer<'_> {
+    /// Compact text rendering of the class graph for a terminal: every class with its instance
+    /// count, followed by an indented tree of its outgoing relationships. Derived and
+    /// aggregated edges are drawn with a dashed arrow
+    pub fn graph_to_term(&self, charset: TermCharset) -> Result<String> {
+        let (branch, last_branch, solid, dashed) = charset.glyphs();
+        let label = |node: &str| -> String {
+            self.label_policy
+                .apply(node, self.node_iris.get(node).map(AsRef::as_ref))
+                .join(" ")
+        };
+
+        let mut nodes: Vec<_> = self.g.node_indices().collect();
+        nodes.sort_by(|a, b| self.g[*a].cmp(&self.g[*b]));
+        let mut w = Vec::new();
+        for node in nodes {
+            write!(w, "{}", label(&self.g[node]))?;
+            if let Some(count) = self.instance_counts.get(&*self.g[node]) {
+                write!(w, " ({} instance{})", count, if *count == 1 { "" } else { "s" })?;
+            }
+            writeln!(w)?;
+
+            let mut edges: Vec<_> = self.g.edges_directed(node, Direction::Outgoing).collect();
+            edges.sort_by(|a, b| (a.weight(), &self.g[a.target()]).cmp(&(b.weight(), &self.g[b.target()])));
+            for (i, edge) in edges.iter().enumerate() {
+                let arrow = match self.resolved_edge_style(edge.id()).line {
+                    Some(LineStyle::Dashed | LineStyle::Dotted) => dashed,
+                    _ => solid,
+                };
+                write!(
+                    w,
+                    "  {} {} {} {}",
+                    if i + 1 == edges.len() { last_branch } else { branch },
+                    edge.weight(),
+                    arrow,
+                    label(&self.g[edge.target()])
+                )?;
+                if let Some(count) = self.edge_counts.get(&edge.id()) {
+                    write!(w, " ({})", count)?;
+                }
+                writeln!(w)?;
+            }
+        }
+        Ok(String::from_utf8(w)?)
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{Result, Visualizer};
+use oxigraph::io::{DatasetFormat, DatasetSerializer};
+use oxigraph::model::vocab::{rdf, rdfs};
+use oxigraph::model::*;
+use petgraph::visit::EdgeRef;
+use std::io::Write;
+
+static OWL_CLASS: NamedNodeRef<'static> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#Class");
+
+impl Visualizer<'_> {
+    /// The class graph as quads in the named graph `view`: every class is typed `owl:Class`
+    /// and every edge becomes a class-to-class triple with the edge's predicate
+    pub fn view_quads(&self, view: &str) -> Result<Vec<Quad>> {
+        let view = NamedNode::new(view)?;
+        let mut quads = Vec::new();
+        for (label, iri) in self.in_order(self.node_iris()) {
+            let class = NamedNode::new_unchecked(&**iri);
+            quads.push(Quad::new(class.clone(), rdf::TYPE, OWL_CLASS, view.clone()));
+            quads.push(Quad::new(
+                class,
+                rdfs::LABEL,
+                Literal::new_simple_literal(&**label),
+                view.clone(),
+            ));
+        }
+        let graph = self.graph();
+        for edge in graph.edge_references() {
+            quads.push(Quad::new(
+                NamedNode::new_unchecked(&*self.node_iris()[&graph[edge.source()]]),
+                NamedNode::new_unchecked(&*self.edge_predicates()[&edge.id()]),
+                NamedNode::new_unchecked(&*self.node_iris()[&graph[edge.target()]]),
+                view.clone(),
+            ));
+        }
+        Ok(quads)
+    }
+}
+
+/// Writes several views of a model, e.g. a full and a filtered render built from one
+/// [`crate::Snapshot`], as named graphs of a single TriG document. Each view is a pair of
+/// graph name IRI and the visualizer holding its class graph.
+pub fn write_trig<'v, 'a: 'v>(
+    views: impl IntoIterator<Item = (&'v str, &'v Visualizer<'a>)>,
+    writer: impl Write,
+) -> Result<()> {
+    let mut writer = DatasetSerializer::from_format(DatasetFormat::TriG).quad_writer(writer)?;
+    for (name, viz) in views {
+        for quad in viz.view_quads(name)? {
+            writer.write(&quad)?;
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
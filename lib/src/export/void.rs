@@ -0,0 +1,96 @@
+use crate::{Result, Visualizer};
+use oxigraph::io::{GraphFormat, GraphSerializer};
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::*;
+use petgraph::visit::EdgeRef;
+use std::collections::BTreeMap;
+
+/// Subject of the VoID description written by [`Visualizer::graph_to_void`]
+pub const VOID_DATASET: &str = "urn:rdf_class_viz:summary";
+
+const VOID: &str = "http://rdfs.org/ns/void#";
+
+fn void(term: &str) -> NamedNode {
+    NamedNode::new_unchecked(format!("{}{}", VOID, term))
+}
+
+impl Visualizer<'_> {
+    /// The class graph as a VoID description of the dataset `dataset`: a `void:classPartition`
+    /// with the `void:entities` count of every class, a `void:propertyPartition` with the
+    /// `void:triples` count of every predicate, and a `void:Linkset` per edge linking the
+    /// partitions of its two classes by its `void:linkPredicate`. Counts are left out where the
+    /// graph has none, e.g. for schema graphs.
+    pub fn void_triples(&self, dataset: &str) -> Result<Vec<Triple>> {
+        let dataset = NamedNode::new(dataset)?;
+        let count = |n: usize| Literal::from(n as u64);
+        let mut triples = vec![Triple::new(dataset.clone(), rdf::TYPE, void("Dataset"))];
+        triples.push(Triple::new(dataset.clone(), void("classes"), count(self.node_iris().len())));
+
+        let mut partitions: BTreeMap<&str, BlankNode> = BTreeMap::new();
+        for (i, (label, iri)) in self.in_order(self.node_iris()).into_iter().enumerate() {
+            let partition = BlankNode::new_unchecked(format!("class{}", i));
+            triples.push(Triple::new(dataset.clone(), void("classPartition"), partition.clone()));
+            triples.push(Triple::new(
+                partition.clone(),
+                void("class"),
+                NamedNode::new_unchecked(&**iri),
+            ));
+            if let Some(n) = self.instance_counts().get(&**label) {
+                triples.push(Triple::new(partition.clone(), void("entities"), count(*n)));
+            }
+            partitions.insert(label.as_ref(), partition);
+        }
+
+        // the triples of a predicate are the instance pairs of all its edges
+        let graph = self.graph();
+        let mut predicates: BTreeMap<&str, Option<usize>> = BTreeMap::new();
+        for edge in graph.edge_references() {
+            let n = self.edge_counts().get(&edge.id()).copied();
+            let total = predicates.entry(&*self.edge_predicates()[&edge.id()]).or_insert(Some(0));
+            *total = total.zip(n).map(|(total, n)| total + n);
+        }
+        let properties = predicates.len();
+        triples.push(Triple::new(dataset.clone(), void("properties"), count(properties)));
+        for (i, (predicate, n)) in predicates.into_iter().enumerate() {
+            let partition = BlankNode::new_unchecked(format!("property{}", i));
+            triples.push(Triple::new(dataset.clone(), void("propertyPartition"), partition.clone()));
+            triples.push(Triple::new(partition.clone(), void("property"), NamedNode::new_unchecked(predicate)));
+            if let Some(n) = n {
+                triples.push(Triple::new(partition, void("triples"), count(n)));
+            }
+        }
+
+        let mut edges: Vec<_> = graph.edge_references().collect();
+        edges.sort_by_key(|edge| (&graph[edge.source()], &graph[edge.target()], edge.weight()));
+        for (i, edge) in edges.into_iter().enumerate() {
+            let (Some(from), Some(to)) = (partitions.get(&*graph[edge.source()]), partitions.get(&*graph[edge.target()])) else {
+                continue;
+            };
+            let linkset = BlankNode::new_unchecked(format!("link{}", i));
+            triples.push(Triple::new(dataset.clone(), void("subset"), linkset.clone()));
+            triples.push(Triple::new(linkset.clone(), rdf::TYPE, void("Linkset")));
+            triples.push(Triple::new(linkset.clone(), void("subjectsTarget"), from.clone()));
+            triples.push(Triple::new(linkset.clone(), void("objectsTarget"), to.clone()));
+            triples.push(Triple::new(
+                linkset.clone(),
+                void("linkPredicate"),
+                NamedNode::new_unchecked(&*self.edge_predicates()[&edge.id()]),
+            ));
+            if let Some(n) = self.edge_counts().get(&edge.id()) {
+                triples.push(Triple::new(linkset, void("triples"), count(*n)));
+            }
+        }
+        Ok(triples)
+    }
+
+    /// [`Visualizer::void_triples`] of [`VOID_DATASET`] as Turtle
+    pub fn graph_to_void(&self) -> Result<String> {
+        let mut w = Vec::new();
+        let mut writer = GraphSerializer::from_format(GraphFormat::Turtle).triple_writer(&mut w)?;
+        for triple in self.void_triples(VOID_DATASET)? {
+            writer.write(&triple)?;
+        }
+        writer.finish()?;
+        Ok(String::from_utf8(w)?)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{split_iri, Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use serde_json::json;
+
+impl Visualizer<'_> {
+    /// VOWL JSON document of the class graph, as loaded by WebVOWL. Every class becomes an
+    /// `owl:Class` node and every edge an `owl:objectProperty` between its domain and range;
+    /// derived edges are marked with the `derived` attribute.
+    pub fn graph_to_vowl(&self) -> Result<String> {
+        let graph = self.graph();
+        let class_id = |idx: usize| format!("class{}", idx);
+        let property_id = |idx: usize| format!("property{}", idx);
+
+        let mut classes = Vec::new();
+        let mut class_attributes = Vec::new();
+        for idx in graph.node_indices() {
+            let label = &graph[idx];
+            let iri = self.node_iris().get(label).map(AsRef::as_ref).unwrap_or("");
+            classes.push(json!({ "id": class_id(idx.index()), "type": "owl:Class" }));
+            class_attributes.push(json!({
+                "id": class_id(idx.index()),
+                "iri": iri,
+                "baseIri": split_iri(iri).0,
+                "label": { "undefined": &**label },
+                "instances": self.instance_counts().get(&**label).unwrap_or(&0),
+            }));
+        }
+
+        let mut properties = Vec::new();
+        let mut property_attributes = Vec::new();
+        for edge in graph.edge_references() {
+            let id = property_id(edge.id().index());
+            let iri = self
+                .edge_predicates()
+                .get(&edge.id())
+                .map(AsRef::as_ref)
+                .unwrap_or("");
+            let mut attributes = vec!["object"];
+            if self.derived_edges().contains(&edge.id()) {
+                attributes.push("derived");
+            }
+            properties.push(json!({ "id": id, "type": "owl:objectProperty" }));
+            property_attributes.push(json!({
+                "id": id,
+                "iri": iri,
+                "baseIri": split_iri(iri).0,
+                "label": { "undefined": &**edge.weight() },
+                "domain": class_id(edge.source().index()),
+                "range": class_id(edge.target().index()),
+                "attributes": attributes,
+            }));
+        }
+
+        let vowl = json!({
+            "header": {
+                "languages": ["undefined"],
+                "title": { "undefined": "rdf_class_viz class graph" },
+            },
+            "namespace": [],
+            "class": classes,
+            "classAttribute": class_attributes,
+            "property": properties,
+            "propertyAttribute": property_attributes,
+            "metrics": {
+                "classCount": graph.node_count(),
+                "objectPropertyCount": graph.edge_count(),
+            },
+        });
+        serde_json::to_string_pretty(&vowl).map_err(|e| crate::Error::Render(e.to_string()))
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{Result, Rgb, Visualizer};
+use oxigraph::model::{NamedNode, Term};
+use oxigraph::sparql::QueryResults;
+use std::collections::{HashMap, VecDeque};
+
+/// Lightness the subclasses of a root approach as they get deeper
+const DEEPEST_LIGHTNESS: f64 = 0.95;
+/// Share of the remaining distance to [`DEEPEST_LIGHTNESS`] kept at each level
+const LIGHTNESS_DECAY: f64 = 0.7;
+
+/// A hue for a root class outside every category that stays the same across runs
+fn hue_of(iri: &str) -> f64 {
+    // FNV-1a, as std's hasher may change between releases
+    let hash = iri
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    (hash % 360) as f64
+}
+
+impl Visualizer<'_> {
+    /// Color every class by its place in the taxonomy instead of its category alone: the hue
+    /// comes from its root, the class color map category it falls under (or else its topmost
+    /// superclass), and the fill gets lighter with every `rdfs:subClassOf` level below it.
+    /// Replaces category and provenance fills; fills set with [`Visualizer::with_class_style`]
+    /// still win.
+    pub fn with_depth_colors(mut self, depth_colors: bool) -> Self {
+        self.depth_colors = depth_colors;
+        self
+    }
+
+    /// Direct superclasses of every class, by IRI, leaving out the trivial classes
+    fn superclasses(&self) -> Result<HashMap<String, Vec<String>>> {
+        let q = "PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 SELECT ?sub ?sup WHERE {
+                     ?sub rdfs:subClassOf ?sup .
+                     FILTER(isIRI(?sub) && isIRI(?sup) && ?sub != ?sup)
+                 }";
+        let mut parents: HashMap<String, Vec<String>> = HashMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(q)? {
+            for row in solutions {
+                let row = row?;
+                if let (Some(Term::NamedNode(sub)), Some(Term::NamedNode(sup))) = (row.get("sub"), row.get("sup")) {
+                    if !self.trivial_classes.iter().any(|class| class == sup.as_str()) {
+                        parents.entry(sub.as_str().to_owned()).or_default().push(sup.as_str().to_owned());
+                    }
+                }
+            }
+        }
+        // sorted so classes with several parents always get the same root
+        for sups in parents.values_mut() {
+            sups.sort();
+        }
+        Ok(parents)
+    }
+
+    /// Refills every class of the graph from the hue of its root and its depth below it
+    pub(crate) fn color_by_depth(&mut self) -> Result<()> {
+        let parents = self.superclasses()?;
+        let mut fills = Vec::new();
+        for (label, iri) in self.node_iris.iter() {
+            // the distance to every ancestor, nearest first
+            let mut distances: Vec<(String, usize)> = vec![(iri.to_string(), 0)];
+            let mut queue = VecDeque::from([(iri.to_string(), 0)]);
+            while let Some((class, level)) = queue.pop_front() {
+                for sup in parents.get(&class).into_iter().flatten() {
+                    if distances.iter().all(|(seen, _)| seen != sup) {
+                        distances.push((sup.clone(), level + 1));
+                        queue.push_back((sup.clone(), level + 1));
+                    }
+                }
+            }
+            let category = self.categories.get(label);
+            let (root, depth) = match category.and_then(|category| distances.iter().find(|(class, _)| **class == **category)) {
+                Some(found) => found.clone(),
+                None => distances.last().cloned().expect("the class itself is always there"),
+            };
+            let (hue, saturation, lightness) = match category
+                .and_then(|category| self.class_color_map.get(&**category))
+                .and_then(|color| Rgb::parse(color))
+            {
+                Some(rgb) => rgb.to_hsl(),
+                None => (hue_of(&root), 0.6, 0.45),
+            };
+            let lightness = DEEPEST_LIGHTNESS - (DEEPEST_LIGHTNESS - lightness) * LIGHTNESS_DECAY.powi(depth as i32);
+            fills.push((label.clone(), iri.to_string(), Rgb::from_hsl(hue, saturation, lightness).to_hex()));
+        }
+
+        for (label, iri, fill) in fills {
+            let node = Term::NamedNode(NamedNode::new_unchecked(iri));
+            if self.resolve_style(&node)?.fill.is_some() {
+                continue;
+            }
+            if let Some(style) = self.styles.get_mut(&label) {
+                style.fill = Some(fill.clone());
+            }
+            self.colors.insert(label, fill);
+        }
+        Ok(())
+    }
+}
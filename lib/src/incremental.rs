@@ -0,0 +1,185 @@
+use crate::{Error, GraphSource, Instant, Result, Visualizer};
+use oxigraph::io::{GraphFormat, GraphParser};
+use oxigraph::model::vocab::rdf;
+use oxigraph::model::{GraphName, Subject, Term};
+use std::collections::{BTreeSet, HashSet};
+use std::io::BufRead;
+
+/// A `VALUES` pattern binding `var` to each class IRI
+fn values(var: &str, classes: &BTreeSet<String>) -> String {
+    let iris: Vec<String> = classes.iter().map(|class| format!("<{}>", class)).collect();
+    format!("VALUES ?{} {{ {} }}", var, iris.join(" "))
+}
+
+impl Visualizer<'_> {
+    /// Whether a class graph was built, so that [`Visualizer::add_data`] adds to it instead of
+    /// only loading triples
+    pub fn is_built(&self) -> bool {
+        self.built
+    }
+
+    /// Whether the graph can be updated from the classes new triples touch. Literal summaries,
+    /// attributes, annotations, property chains and datatype edges read the whole data graph,
+    /// and diffs build over named graphs; those graphs are built again from scratch instead.
+    pub(crate) fn is_incremental(&self) -> bool {
+        self.source == GraphSource::Instances
+            && self.default_graph.is_none()
+            && !self.literals
+            && !self.show_attributes
+            && !self.statement_annotations
+            && !self.property_chains
+            && !self.datatype_nodes
+    }
+
+    /// Loads more data into a built graph. The edge rows and instance counts of the classes the
+    /// subjects and objects of the new triples are typed with are queried again and merged
+    /// into what the graph was built from; every other class pair keeps its rows.
+    pub(crate) fn update_graph(&mut self, data_graph: impl BufRead, format: GraphFormat) -> Result<()> {
+        if self.summary.is_none() {
+            self.load("load data", data_graph, format)?;
+            return self.build_graph();
+        }
+        let resources = self.insert_triples(data_graph, format)?;
+        let classes = self.types_of(&resources)?;
+        let mut summary = self.summary.take().expect("checked above");
+        tracing::info!(resources = resources.len(), classes = classes.len(), "updating class pairs");
+        if classes.is_empty() {
+            self.summary = Some(summary);
+            return Ok(());
+        }
+
+        summary
+            .edges
+            .retain(|edge| !classes.contains(&edge.from) && !classes.contains(&edge.to));
+        let mut seen = HashSet::new();
+        for scope in [values("from", &classes), values("to", &classes)] {
+            for edge in self.cached_edges(&scope)? {
+                if seen.insert((edge.from.clone(), edge.predicate.clone(), edge.to.clone())) {
+                    summary.edges.push(edge);
+                }
+            }
+        }
+        let scope = values("class", &classes);
+        summary.instance_counts.retain(|class, _| !classes.contains(class));
+        summary.instance_counts.extend(self.query_instance_counts(&scope)?);
+        summary.undefined_classes.retain(|class, _| !classes.contains(class));
+        summary.undefined_classes.extend(self.query_undefined_classes(&scope)?);
+        self.build_graph_from_cache(summary)
+    }
+
+    /// Adds the triples of a data graph to the store, returning their subjects and resource
+    /// objects
+    fn insert_triples(&mut self, data_graph: impl BufRead, format: GraphFormat) -> Result<HashSet<Subject>> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        let stage = "load data";
+        let start = Instant::now();
+        let _span = tracing::info_span!("stage", stage).entered();
+        self.report(|sink| sink.stage_started(stage));
+        let triples = GraphParser::from_format(format)
+            .read_triples(data_graph)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut resources = HashSet::new();
+        for triple in triples.iter() {
+            resources.insert(triple.subject.clone());
+            match &triple.object {
+                Term::NamedNode(node) => resources.insert(node.clone().into()),
+                Term::BlankNode(node) => resources.insert(node.clone().into()),
+                _ => false,
+            };
+        }
+        self.store
+            .extend(triples.into_iter().map(|triple| triple.in_graph(GraphName::DefaultGraph)))?;
+        self.triples = self.store.len()?;
+        let elapsed = start.elapsed();
+        tracing::info!(triples = self.triples, ?elapsed, "loaded");
+        self.report(|sink| sink.stage_finished(stage, elapsed));
+        self.load_stages.push((stage, elapsed));
+        Ok(resources)
+    }
+
+    /// IRIs of the types of the resources in any graph
+    fn types_of(&self, resources: &HashSet<Subject>) -> Result<BTreeSet<String>> {
+        let mut classes = BTreeSet::new();
+        for resource in resources.iter() {
+            for quad in self.store.quads_for_pattern(Some(resource.as_ref()), Some(rdf::TYPE), None, None) {
+                if let Term::NamedNode(class) = quad?.object {
+                    classes.insert(class.into_string());
+                }
+            }
+        }
+        Ok(classes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{add_data, visualizer};
+    use crate::Visualizer;
+    use petgraph::visit::EdgeRef;
+    use std::collections::BTreeMap;
+
+    const ONTOLOGY: &str = "
+        brick:AHU a owl:Class .
+        brick:VAV a owl:Class .
+        brick:Sensor a owl:Class .
+        brick:Damper a owl:Class .
+    ";
+    const DATA: &str = "
+        ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 .
+        ex:vav1 a brick:VAV ; brick:hasPoint ex:sensor1 .
+        ex:sensor1 a brick:Sensor .
+    ";
+    /// A second AHU feeding a second VAV bumps AHU feeds VAV; the damper is a new class pair
+    const MORE: &str = "
+        ex:ahu2 a brick:AHU ; brick:feeds ex:vav2 .
+        ex:vav2 a brick:VAV ; brick:hasPart ex:damper1 .
+        ex:damper1 a brick:Damper .
+    ";
+
+    /// Counts of the (from, predicate, to) edges and of the instances of every class
+    type Summary = (BTreeMap<(String, String, String), usize>, BTreeMap<String, usize>);
+
+    fn summary(v: &Visualizer) -> Summary {
+        let g = v.graph();
+        let edges = g
+            .edge_references()
+            .map(|edge| {
+                let key = (g[edge.source()].to_string(), edge.weight().to_string(), g[edge.target()].to_string());
+                (key, v.edge_counts()[&edge.id()])
+            })
+            .collect();
+        let counts = v.instance_counts().iter().map(|(class, count)| (class.clone(), *count)).collect();
+        (edges, counts)
+    }
+
+    #[test]
+    fn adding_data_to_a_built_graph_matches_building_it_from_scratch() {
+        let mut updated = visualizer(ONTOLOGY, DATA);
+        updated.build_graph().unwrap();
+        assert!(updated.is_built() && updated.is_incremental());
+        add_data(&mut updated, MORE);
+
+        let mut scratch = visualizer(ONTOLOGY, &format!("{}{}", DATA, MORE));
+        scratch.build_graph().unwrap();
+
+        let (edges, counts) = summary(&updated);
+        assert_eq!((edges.clone(), counts.clone()), summary(&scratch));
+        let key = |from: &str, p: &str, to: &str| (from.to_owned(), p.to_owned(), to.to_owned());
+        assert_eq!(edges[&key("brick_AHU", "brick_feeds", "brick_VAV")], 2);
+        assert_eq!(edges[&key("brick_VAV", "brick_hasPart", "brick_Damper")], 1);
+        assert_eq!(edges[&key("brick_VAV", "brick_hasPoint", "brick_Sensor")], 1);
+        assert_eq!(counts["brick_AHU"], 2);
+        assert_eq!(counts["brick_Damper"], 1);
+    }
+
+    #[test]
+    fn data_without_known_classes_leaves_the_graph_alone() {
+        let mut v = visualizer(ONTOLOGY, DATA);
+        v.build_graph().unwrap();
+        let before = summary(&v);
+        add_data(&mut v, "ex:thing ex:label \"untyped\" .");
+        assert_eq!(summary(&v), before);
+    }
+}
@@ -0,0 +1,81 @@
+//! Symbol table of the labels of the class graph: every class label, predicate and IRI is
+//! rewritten once and allocated once, then shared by every row, node and edge that mentions it
+
+use crate::{iri, rewrite_term};
+use oxigraph::model::{NamedNode, Term};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// The strings of one term, as the class graph and the edge filter use them
+#[derive(Debug, Clone)]
+pub(crate) struct Symbols {
+    /// [`rewrite_term`], the node label
+    pub(crate) label: Arc<str>,
+    /// [`iri`]
+    pub(crate) iri: Arc<str>,
+    /// `Term::to_string`, the `<iri>` form the filters see
+    pub(crate) term: Arc<str>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Interner {
+    symbols: HashSet<Arc<str>>,
+    /// The symbols of every term seen so far, by IRI so a named node is looked up without
+    /// allocating
+    terms: HashMap<Arc<str>, Symbols>,
+}
+
+impl Interner {
+    /// The shared copy of `s`
+    pub(crate) fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(symbol) = self.symbols.get(s) {
+            return symbol.clone();
+        }
+        let symbol: Arc<str> = Arc::from(s);
+        self.symbols.insert(symbol.clone());
+        symbol
+    }
+
+    /// The symbols of `term`, only worked out the first time the term is seen
+    pub(crate) fn symbols(&mut self, term: &Term) -> &Symbols {
+        let key = match term {
+            Term::NamedNode(node) => node.as_str(),
+            // blank nodes and literals are rare in the class graph
+            _ => &iri(term),
+        };
+        if !self.terms.contains_key(key) {
+            let symbols = Symbols {
+                label: self.intern(&rewrite_term(term)),
+                iri: self.intern(key),
+                term: self.intern(&term.to_string()),
+            };
+            self.terms.insert(symbols.iri.clone(), symbols);
+        }
+        &self.terms[key]
+    }
+
+    /// The node label of `term`
+    pub(crate) fn label(&mut self, term: &Term) -> Arc<str> {
+        self.symbols(term).label.clone()
+    }
+
+    /// The node label of the class `iri`, as [`Interner::label`] of its named node
+    pub(crate) fn iri_label(&mut self, iri: &str) -> Arc<str> {
+        match self.terms.get(iri) {
+            Some(symbols) => symbols.label.clone(),
+            None => self.label(&Term::NamedNode(NamedNode::new_unchecked(iri))),
+        }
+    }
+
+    /// The IRI of `term`, e.g. a predicate
+    pub(crate) fn iri(&mut self, term: &Term) -> Arc<str> {
+        self.symbols(term).iri.clone()
+    }
+
+    /// Forgets every symbol, so a graph built again from other data does not keep the labels
+    /// of the last one alive
+    pub(crate) fn clear(&mut self) {
+        self.symbols.clear();
+        self.terms.clear();
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{Result, Visualizer};
+use oxigraph::model::Term;
+use oxigraph::sparql::QueryResults;
+
+impl Visualizer<'_> {
+    /// Draw edges for both properties of an `owl:inverseOf` pair in one direction only: an
+    /// edge `B isFedBy A` is drawn as `A feeds B`. The canonical property of a pair is the one
+    /// with the smaller IRI, which picks the active form (`feeds`, `hasPoint`, `hasPart`) for
+    /// Brick's naming.
+    pub fn with_inverse_dedup(mut self, dedup_inverses: bool) -> Self {
+        self.dedup_inverses = dedup_inverses;
+        self
+    }
+
+    /// Maps the non-canonical property of every `owl:inverseOf` pair to its canonical inverse
+    pub(crate) fn find_inverses(&mut self) -> Result<()> {
+        let q = "PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 SELECT DISTINCT ?p ?q WHERE {
+                     { ?p owl:inverseOf ?q } UNION { ?q owl:inverseOf ?p }
+                     FILTER(isIRI(?p) && isIRI(?q) && STR(?q) < STR(?p))
+                 }";
+        if let QueryResults::Solutions(solutions) = self.query(q)? {
+            for row in solutions {
+                let row = row?;
+                if let (Some(Term::NamedNode(p)), Some(Term::NamedNode(q))) = (row.get("p"), row.get("q")) {
+                    self.inverses.insert(p.as_str().to_owned(), q.as_str().to_owned());
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! Shortening long class labels: stripping namespace prefixes, truncating with an ellipsis and
+//! wrapping on word boundaries, separately for every renderer
+
+use crate::{split_iri, Engine, Visualizer, PREFIXES};
+use std::str::FromStr;
+
+/// Line width labels are wrapped at unless another is given
+pub const DEFAULT_WRAP_WIDTH: usize = 16;
+
+/// How the labels of class nodes are shortened; the graph keeps the full labels as node ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LabelPolicy {
+    /// `Supply_Air_Temperature_Sensor` instead of `brick_Supply_Air_Temperature_Sensor`
+    pub strip_prefix: bool,
+    /// Cut labels longer than this many characters, ending them in `…`
+    pub truncate: Option<usize>,
+    /// Break labels into lines of at most this many characters, after underscores, hyphens
+    /// and before the capitals of CamelCase words. Longer words get a line of their own.
+    pub wrap: Option<usize>,
+}
+
+/// Parses a comma-separated list of `strip`, `truncate=N` and `wrap[=WIDTH]`, or `none`, e.g.
+/// `strip,wrap=20`
+impl FromStr for LabelPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = LabelPolicy::default();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (key, value) = match part.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (part, None),
+            };
+            let width = |value: Option<&str>| match value {
+                Some(value) => match value.parse::<usize>() {
+                    Ok(width) if width > 0 => Ok(Some(width)),
+                    _ => Err(format!("invalid width '{}' for '{}', expected a positive number", value, key)),
+                },
+                None => Ok(None),
+            };
+            match key {
+                "none" => policy = LabelPolicy::default(),
+                "strip" => policy.strip_prefix = true,
+                "truncate" => {
+                    policy.truncate = Some(width(value)?.ok_or("'truncate' needs a length, e.g. truncate=24")?)
+                }
+                "wrap" => policy.wrap = Some(width(value)?.unwrap_or(DEFAULT_WRAP_WIDTH)),
+                _ => {
+                    return Err(format!(
+                        "unknown label policy '{}', expected none, strip, truncate=N or wrap[=WIDTH]",
+                        part
+                    ))
+                }
+            }
+        }
+        Ok(policy)
+    }
+}
+
+impl LabelPolicy {
+    /// The lines `label` is drawn as; `iri` is the class IRI behind it, if known
+    pub fn apply(&self, label: &str, iri: Option<&str>) -> Vec<String> {
+        let mut label = label.to_owned();
+        if self.strip_prefix {
+            label = strip_prefix(&label, iri);
+        }
+        if let Some(max) = self.truncate {
+            if label.chars().count() > max {
+                label = label.chars().take(max.saturating_sub(1)).collect();
+                label.push('…');
+            }
+        }
+        match self.wrap {
+            Some(width) => wrap(&label, width),
+            None => vec![label],
+        }
+    }
+}
+
+fn strip_prefix(label: &str, iri: Option<&str>) -> String {
+    for (prefix, _) in PREFIXES.iter() {
+        if let Some(local) = label.strip_prefix(&format!("{}_", prefix)) {
+            return local.to_owned();
+        }
+    }
+    // classes outside the known prefixes are labeled with their full IRIs
+    match iri {
+        Some(iri) if iri == label => split_iri(iri).1.to_owned(),
+        _ => label.to_owned(),
+    }
+}
+
+/// Words of `label` with their trailing separators, e.g. `Supply_`, `Air_`, `has`, `Point`
+fn words(label: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous: Option<char> = None;
+    for c in label.chars() {
+        let camel = c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase());
+        if camel && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+        if c == '_' || c == '-' || c == ' ' {
+            words.push(std::mem::take(&mut word));
+        }
+        previous = Some(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn wrap(label: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in words(label) {
+        if !line.is_empty() && line.chars().count() + word.trim_end().chars().count() > width {
+            lines.push(std::mem::take(&mut line).trim_end().to_owned());
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line.trim_end().to_owned());
+    }
+    lines
+}
+
+impl Visualizer<'_> {
+    /// Shorten class labels in every renderer with `policy`, unless
+    /// [`Visualizer::with_engine_label_policy`] sets another one for it
+    pub fn with_label_policy(mut self, policy: LabelPolicy) -> Self {
+        self.label_policy = policy;
+        self
+    }
+
+    /// Shorten class labels with `policy` in the output of `engine` only: the D2 text for
+    /// [`Engine::D2`], the DOT text for [`Engine::Dot`] and the SVG of [`Engine::Builtin`]
+    pub fn with_engine_label_policy(mut self, engine: Engine, policy: LabelPolicy) -> Self {
+        self.engine_label_policies.retain(|(existing, _)| *existing != engine);
+        self.engine_label_policies.push((engine, policy));
+        self
+    }
+
+    /// Lines the class labeled `node` is drawn as in the output of `engine`
+    pub(crate) fn display_label(&self, engine: Engine, node: &str) -> Vec<String> {
+        let policy = self
+            .engine_label_policies
+            .iter()
+            .find(|(existing, _)| *existing == engine)
+            .map_or(self.label_policy, |(_, policy)| *policy);
+        policy.apply(node, self.node_iris.get(node).map(AsRef::as_ref))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_split_after_separators_and_before_camel_case() {
+        assert_eq!(words("Supply_Air_Temperature_Sensor"), ["Supply_", "Air_", "Temperature_", "Sensor"]);
+        assert_eq!(words("hasPoint"), ["has", "Point"]);
+        assert_eq!(words("Air Handler-Unit"), ["Air ", "Handler-", "Unit"]);
+        // runs of capitals stay one word
+        assert_eq!(words("HVAC_Zone"), ["HVAC_", "Zone"]);
+        assert!(words("").is_empty());
+    }
+
+    #[test]
+    fn wrap_fills_lines_up_to_the_width() {
+        assert_eq!(wrap("Supply_Air_Temperature_Sensor", 16), ["Supply_Air_", "Temperature_", "Sensor"]);
+        assert_eq!(wrap("Air Handler Unit", 9), ["Air", "Handler", "Unit"]);
+        assert_eq!(wrap("Air Handler Unit", 16), ["Air Handler Unit"]);
+    }
+
+    #[test]
+    fn wrap_gives_long_words_their_own_line() {
+        assert_eq!(wrap("isPartOfSupercalifragilistic", 8), ["isPartOf", "Supercalifragilistic"]);
+        assert_eq!(wrap("", 8), [""]);
+    }
+
+    #[test]
+    fn apply_strips_truncates_then_wraps() {
+        let policy: LabelPolicy = "strip,truncate=12,wrap=8".parse().unwrap();
+        assert_eq!(policy.apply("brick_Supply_Air_Temperature_Sensor", None), ["Supply_", "Air_…"]);
+        assert_eq!("none".parse::<LabelPolicy>().unwrap(), LabelPolicy::default());
+        assert!("truncate".parse::<LabelPolicy>().is_err());
+        assert!("wrap=0".parse::<LabelPolicy>().is_err());
+    }
+}
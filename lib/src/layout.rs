@@ -0,0 +1,150 @@
+//! Layout settings passed through to the renderers
+
+use crate::Visualizer;
+use std::str::FromStr;
+
+/// Layout engine D2 places the diagram with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum D2Layout {
+    Dagre,
+    Elk,
+    /// Needs a licensed install of TALA
+    Tala,
+}
+
+impl D2Layout {
+    /// Name of the engine in `d2-config` and the `--layout` flag of the `d2` CLI
+    pub fn name(&self) -> &'static str {
+        match self {
+            D2Layout::Dagre => "dagre",
+            D2Layout::Elk => "elk",
+            D2Layout::Tala => "tala",
+        }
+    }
+}
+
+impl FromStr for D2Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dagre" => Ok(D2Layout::Dagre),
+            "elk" => Ok(D2Layout::Elk),
+            "tala" => Ok(D2Layout::Tala),
+            _ => Err(format!("unknown D2 layout '{}', expected dagre, elk or tala", s)),
+        }
+    }
+}
+
+/// Direction D2 lays the diagram out in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn name(&self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Direction::Up),
+            "down" => Ok(Direction::Down),
+            "left" => Ok(Direction::Left),
+            "right" => Ok(Direction::Right),
+            _ => Err(format!("unknown direction '{}', expected up, down, left or right", s)),
+        }
+    }
+}
+
+/// Directives written at the top of the D2 output
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct D2Directives {
+    pub(crate) layout: Option<D2Layout>,
+    pub(crate) theme_id: Option<u32>,
+    pub(crate) sketch: bool,
+    pub(crate) direction: Option<Direction>,
+}
+
+impl D2Directives {
+    /// The `vars.d2-config` block and `direction` line, empty if nothing is set
+    pub(crate) fn header(&self) -> String {
+        let mut config = Vec::new();
+        if let Some(layout) = self.layout {
+            config.push(format!("layout-engine: {}", layout.name()));
+        }
+        if let Some(theme_id) = self.theme_id {
+            config.push(format!("theme-id: {}", theme_id));
+        }
+        if self.sketch {
+            config.push("sketch: true".to_owned());
+        }
+        let mut header = String::new();
+        if !config.is_empty() {
+            header.push_str("vars: {\n  d2-config: {\n");
+            for line in config {
+                header.push_str(&format!("    {}\n", line));
+            }
+            header.push_str("  }\n}\n");
+        }
+        if let Some(direction) = self.direction {
+            header.push_str(&format!("direction: {}\n", direction.name()));
+        }
+        header
+    }
+}
+
+impl Visualizer<'_> {
+    /// Sets a Graphviz graph attribute of the DOT output, e.g. `rankdir` to `LR`, `splines` to
+    /// `ortho` or `nodesep` to `0.5`; setting one again replaces its value
+    pub fn with_dot_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        self.dot_attributes.retain(|(existing, _)| *existing != name);
+        self.dot_attributes.push((name, value.into()));
+        self
+    }
+
+    /// Font family of the labels of the DOT output and the builtin engine's SVG
+    pub fn with_font(mut self, family: impl Into<String>) -> Self {
+        self.font = Some(family.into());
+        self
+    }
+
+    /// Layout engine of the D2 output, written to its `d2-config` and used by
+    /// [`Visualizer::render_with`]; elk if unset
+    pub fn with_d2_layout(mut self, layout: D2Layout) -> Self {
+        self.d2.layout = Some(layout);
+        self
+    }
+
+    /// Id of the D2 theme the D2 output is rendered with, e.g. `200` for Dark Mauve
+    pub fn with_d2_theme(mut self, theme_id: u32) -> Self {
+        self.d2.theme_id = Some(theme_id);
+        self
+    }
+
+    /// Render the D2 output in D2's hand-drawn sketch mode
+    pub fn with_d2_sketch(mut self, sketch: bool) -> Self {
+        self.d2.sketch = sketch;
+        self
+    }
+
+    /// Direction the D2 output is laid out in
+    pub fn with_d2_direction(mut self, direction: Direction) -> Self {
+        self.d2.direction = Some(direction);
+        self
+    }
+}
@@ -0,0 +1,60 @@
+//! A legend of the prefixes and predicates in the diagram, so shared images explain themselves
+
+use crate::{curie, split_iri, Visualizer, PREFIXES};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+impl Visualizer<'_> {
+    /// Add a legend block to the D2, DOT and builtin SVG outputs, listing the namespace of
+    /// every prefix used by a class or predicate and the CURIE of every predicate on an edge
+    pub fn with_legend(mut self, legend: bool) -> Self {
+        self.legend = legend;
+        self
+    }
+
+    /// `(prefix, namespace)` of every known prefix the diagram uses, sorted by prefix
+    pub fn legend_namespaces(&self) -> Vec<(&'static str, &'static str)> {
+        let used: BTreeSet<&str> = self
+            .g
+            .node_weights()
+            .filter_map(|node| self.node_iris.get(node))
+            .chain(self.predicates())
+            .map(|iri| split_iri(iri).0)
+            .collect();
+        let mut namespaces: Vec<(&'static str, &'static str)> = PREFIXES
+            .iter()
+            .filter(|(_, namespace)| used.contains(*namespace))
+            .map(|(prefix, namespace)| (*prefix, *namespace))
+            .collect();
+        namespaces.sort();
+        namespaces
+    }
+
+    /// CURIEs of the predicates on the edges of the diagram, or their full IRIs outside the
+    /// known prefixes, sorted
+    pub fn legend_predicates(&self) -> Vec<String> {
+        let predicates: BTreeSet<String> = self.predicates().map(|iri| curie(iri)).collect();
+        predicates.into_iter().collect()
+    }
+
+    /// Full IRIs of the predicates on the edges still in the graph
+    fn predicates(&self) -> impl Iterator<Item = &Arc<str>> {
+        self.g.edge_indices().filter_map(|idx| self.edge_predicates.get(&idx))
+    }
+
+    /// Lines of the legend, when [`Visualizer::with_legend`] is set
+    pub(crate) fn legend_lines(&self) -> Option<Vec<String>> {
+        if !self.legend {
+            return None;
+        }
+        let mut lines = vec!["Namespaces".to_owned()];
+        lines.extend(
+            self.legend_namespaces()
+                .into_iter()
+                .map(|(prefix, namespace)| format!("{}: {}", prefix, namespace)),
+        );
+        lines.push("Predicates".to_owned());
+        lines.extend(self.legend_predicates());
+        Some(lines)
+    }
+}
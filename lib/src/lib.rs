@@ -1,23 +1,123 @@
-use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
 use oxigraph::io::GraphFormat;
 use oxigraph::model::*;
-use oxigraph::sparql::QueryResults;
+use oxigraph::sparql::{EvaluationError, Query, QueryResults};
+#[cfg(not(target_family = "wasm"))]
+use oxigraph::store::BulkLoader;
 use oxigraph::store::Store;
-use petgraph::dot::Dot;
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
 use petgraph::visit::EdgeRef;
 use petgraph::Graph;
-use std::collections::HashMap;
-use std::fs::File;
+use regex::Regex;
+use interner::Symbols;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::io::BufRead;
 use std::io::Write;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+// std's clock panics on wasm32-unknown-unknown; web-time reads the browser's instead
+#[cfg(not(target_family = "wasm"))]
+pub(crate) use std::time::Instant;
+#[cfg(target_family = "wasm")]
+pub(crate) use web_time::Instant;
+
+mod aggregate;
+mod annotations;
+mod cache;
+mod capabilities;
+mod class_declaration;
+mod color;
+mod color_maps;
+mod components;
+mod cycles;
+mod deprecation;
+mod diff;
+mod error;
+mod export;
+mod gradient;
+mod incremental;
+mod interner;
+mod inverse;
+mod label_policy;
+mod layout;
+mod legend;
+mod limits;
+mod links;
+mod manifest;
+mod metrics;
+mod multi_type;
+mod named_graphs;
+mod ordering;
+mod parsed;
+mod paths;
+mod progress;
+mod prune;
+mod provenance;
+mod render;
+mod report;
+mod rollup;
+mod scaling;
+#[cfg(feature = "rhai")]
+mod rhai_filter;
+mod sparql_filter;
+mod stats;
+mod style;
+#[cfg(test)]
+mod testing;
+mod theme;
+mod tooltips;
+mod top_k;
+mod undefined;
+mod usage;
+mod validation;
+pub use annotations::Annotations;
+pub use cache::{CachedEdge, GraphCache, GRAPH_CACHE_VERSION};
+pub use capabilities::{Capabilities, Formats};
+pub use class_declaration::ClassDeclaration;
+pub use color::Rgb;
+pub use color_maps::ColorMap;
+pub use cycles::{Cycle, CYCLE_COLOR};
+pub use deprecation::Deprecation;
+pub use diff::{EdgeChange, NodeChange};
+pub use label_policy::{LabelPolicy, DEFAULT_WRAP_WIDTH};
+pub use layout::{D2Layout, Direction};
+pub use limits::{LimitedResults, QueryLimits};
+#[cfg(not(target_family = "wasm"))]
+pub use limits::MAX_WORKERS;
+pub use error::{Error, Result};
+pub use export::{
+    write_trig, ClassGraphJson, EdgeJson, NodeJson, TermCharset, CLASS_GRAPH_JSON_VERSION, VOID_DATASET,
+};
+pub use manifest::{Artifact, Manifest};
+pub use metrics::ClassMetrics;
+pub use multi_type::MultiTypePolicy;
+pub use named_graphs::GraphSelection;
+pub use parsed::ParsedGraph;
+pub use progress::{LoadProgress, ProgressSink, RowProgress};
+use progress::ROW_PROGRESS_INTERVAL;
+pub use render::{Engine, ImageFormat};
+#[cfg(feature = "rhai")]
+pub use rhai_filter::RhaiFilter;
+pub use sparql_filter::SparqlFilter;
+pub use stats::Stats;
+pub use scaling::DEFAULT_NODE_SIZES;
+pub use style::{EdgeStyle, LineStyle, NodeShape, NodeStyle, StyleSheet};
+pub use theme::Theme;
+pub use top_k::{NodeRank, OTHER_NODE};
+pub use usage::ResourceUsage;
+
+pub use petgraph;
+pub use regex;
 
 static PREFIXES: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
     let mut map = HashMap::new();
     map.insert("brick", "https://brickschema.org/schema/Brick#");
     map.insert("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#");
     map.insert("owl", "http://www.w3.org/2002/07/owl#");
+    map.insert("xsd", "http://www.w3.org/2001/XMLSchema#");
     map
 });
 
@@ -30,161 +130,2082 @@ fn rewrite_term(node: &Term) -> String {
     s.trim_matches(matches).to_owned()
 }
 
-fn graph_to_dot(graph: &petgraph::Graph<&str, &str>, filename: &str) -> Result<()> {
-    let mut file = File::create(filename)?;
-    write!(file, "{:?}", Dot::with_config(graph, &[]))?;
-    Ok(())
+/// How predicates are labeled on the edges of the class graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabelStyle {
+    /// `brick_hasPoint`: known prefix and local name joined by an underscore
+    #[default]
+    Prefixed,
+    /// `brick:hasPoint`
+    Curie,
+    /// `hasPoint`
+    LocalName,
+    /// `has point`
+    Humanized,
+}
+
+impl FromStr for LabelStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "prefixed" => Ok(LabelStyle::Prefixed),
+            "curie" => Ok(LabelStyle::Curie),
+            "local" => Ok(LabelStyle::LocalName),
+            "human" => Ok(LabelStyle::Humanized),
+            _ => Err(format!(
+                "unknown label style '{}', expected one of prefixed, curie, local, human",
+                s
+            )),
+        }
+    }
+}
+
+/// Where the edges of the class graph come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphSource {
+    /// Predicates connecting instances of two classes in the data graph
+    #[default]
+    Instances,
+    /// The `rdfs:domain` and `rdfs:range` of every property, so a schema can be drawn without
+    /// instance data
+    Schema,
+    /// SHACL node shapes, with an edge along the `sh:path` of every property shape to its
+    /// `sh:class` or `sh:node`
+    Shapes,
+}
+
+/// Language of an edge filter script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterLang {
+    /// A SPARQL `ASK` template, see [`SparqlFilter`]
+    #[default]
+    Sparql,
+    /// [Rhai](https://rhai.rs), see `RhaiFilter`; needs the `rhai` feature
+    Rhai,
+}
+
+impl FromStr for FilterLang {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "rhai" => Ok(FilterLang::Rhai),
+            "sparql" => Ok(FilterLang::Sparql),
+            _ => Err(format!("unknown filter language '{}', expected rhai or sparql", s)),
+        }
+    }
+}
+
+/// What a filter given to [`Visualizer::with_context_filter`] knows about a candidate edge
+/// besides its endpoints and predicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterContext<'c> {
+    /// Number of instance pairs connected by the edge; 1 for edges drawn from schema
+    /// statements, restrictions and literal properties
+    pub count: usize,
+    /// Number of instances of the source class
+    pub from_instances: usize,
+    /// Number of instances of the target class (0 for a literal datatype)
+    pub to_instances: usize,
+    /// Namespace IRI of the source class
+    pub from_namespace: &'c str,
+    /// Namespace IRI of the target class or datatype
+    pub to_namespace: &'c str,
+    /// Namespace IRI of the predicate
+    pub edge_namespace: &'c str,
+}
+
+fn split_iri(iri: &str) -> (&str, &str) {
+    match iri.rfind(['#', '/']) {
+        Some(idx) => iri.split_at(idx + 1),
+        None => ("", iri),
+    }
+}
+
+fn curie(iri: &str) -> String {
+    let (namespace, local) = split_iri(iri);
+    match PREFIXES.iter().find(|(_, ns)| **ns == namespace) {
+        Some((prefix, _)) => format!("{}:{}", prefix, local),
+        None => iri.to_owned(),
+    }
+}
+
+fn humanize(local: &str) -> String {
+    let mut s = String::new();
+    for c in local.chars() {
+        if c == '_' || c == '-' {
+            s.push(' ');
+        } else if c.is_uppercase() {
+            if !s.is_empty() && !s.ends_with(' ') {
+                s.push(' ');
+            }
+            s.extend(c.to_lowercase());
+        } else {
+            s.push(c);
+        }
+    }
+    s
+}
+
+fn style_label(iri: &str, style: LabelStyle) -> String {
+    match style {
+        LabelStyle::Prefixed => rewrite_term(&NamedNode::new_unchecked(iri).into()),
+        LabelStyle::Curie => curie(iri),
+        LabelStyle::LocalName => split_iri(iri).1.to_owned(),
+        LabelStyle::Humanized => humanize(split_iri(iri).1),
+    }
+}
+
+/// Labels every predicate IRI in the given style. Predicates whose local names clash across
+/// namespaces would become indistinguishable in the local-name styles, so those fall back to
+/// CURIEs.
+fn predicate_labels<'i>(
+    iris: impl IntoIterator<Item = &'i str>,
+    style: LabelStyle,
+) -> HashMap<String, String> {
+    let mut by_label: HashMap<String, Vec<&str>> = HashMap::new();
+    for iri in iris {
+        let entry = by_label.entry(style_label(iri, style)).or_default();
+        if !entry.contains(&iri) {
+            entry.push(iri);
+        }
+    }
+    let mut labels = HashMap::new();
+    for (label, iris) in by_label {
+        if iris.len() == 1 {
+            labels.insert(iris[0].to_owned(), label);
+        } else {
+            for iri in iris {
+                labels.insert(iri.to_owned(), curie(iri));
+            }
+        }
+    }
+    labels
+}
+
+/// The namespace IRI of a known prefix (with or without the trailing colon), or `namespace`
+/// itself
+fn resolve_namespace(namespace: &str) -> String {
+    let prefix = namespace.trim_end_matches(':');
+    match PREFIXES.get(prefix) {
+        Some(iri) => (*iri).to_owned(),
+        None => namespace.to_owned(),
+    }
+}
+
+/// The IRI of a CURIE of a known prefix (`brick:feeds`, or `brick:` for the namespace), or `s`
+/// itself
+fn expand_curie(s: &str) -> String {
+    match s.split_once(':') {
+        Some((prefix, local)) if PREFIXES.contains_key(prefix) => format!("{}{}", PREFIXES[prefix], local),
+        _ => s.to_owned(),
+    }
+}
+
+fn iri(node: &Term) -> String {
+    match node {
+        Term::NamedNode(node) => node.as_str().to_owned(),
+        _ => node.to_string(),
+    }
+}
+
+/// Value of a `COUNT` binding
+fn count_of(count: Option<&Term>) -> usize {
+    match count {
+        Some(Term::Literal(count)) => count.value().parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Escapes the characters that structure Graphviz record labels
+fn record_escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in dot_escape(s).chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+type FilterFn = Arc<dyn Fn(&str, &str, &str, &FilterContext) -> bool + Send + Sync>;
+type NodeFilterFn = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// (predicate label, datatype local name) pairs of each class label, see [`Visualizer::attributes`]
+pub type Attributes = HashMap<Arc<str>, BTreeSet<(Arc<str>, Arc<str>)>>;
+
+/// Classes that carry no information about a model, such as the `owl:NamedIndividual` type
+/// Protégé gives every individual; they are dropped from both ends of every edge by default
+pub static TRIVIAL_CLASSES: &[&str] = &[
+    "http://www.w3.org/2002/07/owl#Thing",
+    "http://www.w3.org/2002/07/owl#Nothing",
+    "http://www.w3.org/2002/07/owl#NamedIndividual",
+    "http://www.w3.org/2000/01/rdf-schema#Resource",
+];
+
+/// Fill of the datatype nodes drawn by [`Visualizer::with_datatype_nodes`]
+static DATATYPE_FILL: &str = "#eeeeee";
+
+static NUMERIC_DATATYPES: &[&str] = &[
+    "http://www.w3.org/2001/XMLSchema#decimal",
+    "http://www.w3.org/2001/XMLSchema#double",
+    "http://www.w3.org/2001/XMLSchema#float",
+    "http://www.w3.org/2001/XMLSchema#integer",
+    "http://www.w3.org/2001/XMLSchema#int",
+    "http://www.w3.org/2001/XMLSchema#long",
+    "http://www.w3.org/2001/XMLSchema#short",
+    "http://www.w3.org/2001/XMLSchema#nonNegativeInteger",
+    "http://www.w3.org/2001/XMLSchema#positiveInteger",
+    "http://www.w3.org/2001/XMLSchema#unsignedInt",
+];
+
+/// Summary of the numeric values a datatype property takes on the instances of a class
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueRange {
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: usize,
 }
 
-type ColorFn = fn(node: &str) -> String;
-type FilterFn = fn(from: &str, to: &str, edge: &str) -> bool;
+impl ValueRange {
+    fn new(value: f64) -> Self {
+        ValueRange {
+            min: value,
+            max: value,
+            sum: value,
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &ValueRange) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        self.count += other.count;
+    }
+
+    pub fn avg(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+fn numeric_value(literal: &Literal) -> Option<f64> {
+    if NUMERIC_DATATYPES.contains(&literal.datatype().as_str()) {
+        literal.value().parse().ok()
+    } else {
+        None
+    }
+}
 
 pub struct Visualizer<'a> {
     store: Store,
-    labels: Vec<String>,
-    g: Graph<&'a str, &'a str>,
-    nodes: HashMap<&'a str, NodeIndex>,
+    read_only: bool,
+    g: Graph<Arc<str>, Arc<str>>,
+    nodes: HashMap<Arc<str>, NodeIndex>,
+    /// Edges by endpoints and weight, so adding a row does not scan every parallel edge; only
+    /// valid while the graph is built through `edge_index`
+    edge_lookup: HashMap<(NodeIndex, NodeIndex, Arc<str>), EdgeIndex>,
+    /// Labels and IRIs shared by every row that mentions them
+    interner: interner::Interner,
+    /// Extraction results the graph is being built from instead of the store
+    cache: Option<Arc<GraphCache>>,
+    /// Extraction results of the built graph, to update it when more data is added
+    summary: Option<GraphCache>,
+    built: bool,
     filter: FilterFn,
-    class_color_map: HashMap<&'a str, &'a str>,
-    colors: HashMap<String, String>
+    node_filter: Option<NodeFilterFn>,
+    prune_isolated: bool,
+    min_edge_count: usize,
+    class_color_map: HashMap<Cow<'a, str>, Cow<'a, str>>,
+    class_shape_map: HashMap<&'a str, NodeShape>,
+    colors: HashMap<Arc<str>, String>,
+    class_styles: HashMap<String, NodeStyle>,
+    styles: HashMap<Arc<str>, NodeStyle>,
+    edge_styles: Vec<(String, EdgeStyle)>,
+    categories: HashMap<Arc<str>, Arc<str>>,
+    category_chips: bool,
+    node_iris: HashMap<Arc<str>, Arc<str>>,
+    instance_counts: HashMap<String, usize>,
+    deprecations: HashMap<String, Deprecation>,
+    tooltips: bool,
+    definitions: HashMap<String, String>,
+    link_template: Option<String>,
+    theme: Option<Theme>,
+    depth_colors: bool,
+    dot_attributes: Vec<(String, String)>,
+    font: Option<String>,
+    legend: bool,
+    component: Option<usize>,
+    max_nodes: Option<(usize, NodeRank)>,
+    metric_annotations: bool,
+    highlight_cycles: bool,
+    path: Option<(String, String)>,
+    highlighted_cycle_edges: HashSet<EdgeIndex>,
+    d2: layout::D2Directives,
+    violations: HashMap<String, usize>,
+    edge_predicates: HashMap<EdgeIndex, Arc<str>>,
+    edge_counts: HashMap<EdgeIndex, usize>,
+    edge_widths: bool,
+    node_sizes: Option<(f64, f64)>,
+    edge_changes: HashMap<EdgeIndex, EdgeChange>,
+    node_changes: HashMap<String, NodeChange>,
+    literals: bool,
+    attributes: Attributes,
+    show_attributes: bool,
+    property_chains: bool,
+    source: GraphSource,
+    derived: HashSet<EdgeIndex>,
+    show_restrictions: bool,
+    restrictions: HashMap<EdgeIndex, String>,
+    value_ranges: HashMap<(Arc<str>, Arc<str>), ValueRange>,
+    label_style: LabelStyle,
+    label_policy: LabelPolicy,
+    engine_label_policies: Vec<(Engine, LabelPolicy)>,
+    trivial_classes: Vec<String>,
+    multi_type: MultiTypePolicy,
+    class_declaration: ClassDeclaration,
+    allowed_namespaces: Vec<String>,
+    denied_namespaces: Vec<String>,
+    kept_predicates: Vec<Regex>,
+    dropped_predicates: Vec<Regex>,
+    focus: Option<(String, usize)>,
+    rollup: Option<(String, usize)>,
+    property_rollup: bool,
+    property_tops: HashMap<String, String>,
+    dedup_inverses: bool,
+    inverses: HashMap<String, String>,
+    aggregate_edges: bool,
+    datatype_nodes: bool,
+    statement_annotations: bool,
+    edge_annotations: HashMap<EdgeIndex, Annotations>,
+    aggregated: HashMap<EdgeIndex, Vec<String>>,
+    progress: Vec<Arc<dyn ProgressSink>>,
+    default_graph: Option<Vec<GraphName>>,
+    graph_selection: GraphSelection,
+    undefined_classes: BTreeMap<String, usize>,
+    sorted_output: bool,
+    unknown_class_nodes: bool,
+    sources: Vec<String>,
+    provenance: bool,
+    class_sources: HashMap<String, String>,
+    selected_graphs: Option<Vec<GraphName>>,
+    triples: usize,
+    queries: AtomicUsize,
+    limits: limits::QueryLimits,
+    load_stages: Vec<(&'static str, Duration)>,
+    build_stages: Vec<(&'static str, Duration)>,
+}
+
+/// Read-only view of the ontologies and data loaded into a [`Visualizer`]. Any number of
+/// renders with different filters and color maps can be built from one snapshot, including
+/// concurrently from several threads, without reloading or re-parsing the inputs.
+#[derive(Clone)]
+pub struct Snapshot {
+    store: Store,
+}
+
+impl Snapshot {
+    /// Creates a visualizer over the snapshot's triples. It can build and render graphs but
+    /// refuses to load anything, so it never changes what other renders see.
+    pub fn visualizer<'a>(
+        &self,
+        filter: impl Fn(&str, &str, &str) -> bool + Send + Sync + 'static,
+        class_color_map: HashMap<&'a str, &'a str>,
+    ) -> Visualizer<'a> {
+        Visualizer::from_store(
+            self.store.clone(),
+            true,
+            Arc::new(move |from, to, edge, _: &FilterContext| filter(from, to, edge)),
+            class_color_map,
+        )
+    }
 }
 
 impl<'a> Visualizer<'a> {
-    pub fn new(filter: FilterFn, class_color_map: HashMap<&'a str, &'a str>) -> Result<Self> {
-        Ok(Visualizer {
-            store: Store::new()?,
-            labels: Vec::new(),
+    /// Creates a visualizer with an empty store. `filter` is called with the `<iri>` of the
+    /// source class, target class (or literal datatype) and predicate of every candidate edge
+    /// and keeps it when it returns true; it may capture state, e.g. a compiled script.
+    pub fn new(
+        filter: impl Fn(&str, &str, &str) -> bool + Send + Sync + 'static,
+        class_color_map: HashMap<&'a str, &'a str>,
+    ) -> Result<Self> {
+        Ok(Self::from_store(
+            Store::new()?,
+            false,
+            Arc::new(move |from, to, edge, _: &FilterContext| filter(from, to, edge)),
+            class_color_map,
+        ))
+    }
+
+    /// Replaces the edge filter with one that also receives a [`FilterContext`], for rules
+    /// such as "only keep edges seen at least 5 times"
+    pub fn with_context_filter(
+        mut self,
+        filter: impl Fn(&str, &str, &str, &FilterContext) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = Arc::new(filter);
+        self
+    }
+
+    fn from_store(
+        store: Store,
+        read_only: bool,
+        filter: FilterFn,
+        class_color_map: HashMap<&'a str, &'a str>,
+    ) -> Self {
+        Visualizer {
+            store,
+            read_only,
             g: Graph::new(),
             nodes: HashMap::new(),
+            edge_lookup: HashMap::new(),
+            interner: interner::Interner::default(),
+            cache: None,
+            summary: None,
+            built: false,
             colors: HashMap::new(),
-            class_color_map,
+            class_styles: HashMap::new(),
+            styles: HashMap::new(),
+            edge_styles: Vec::new(),
+            categories: HashMap::new(),
+            category_chips: false,
+            node_iris: HashMap::new(),
+            instance_counts: HashMap::new(),
+            deprecations: HashMap::new(),
+            tooltips: false,
+            definitions: HashMap::new(),
+            link_template: None,
+            theme: None,
+            depth_colors: false,
+            dot_attributes: Vec::new(),
+            font: None,
+            legend: false,
+            component: None,
+            max_nodes: None,
+            metric_annotations: false,
+            highlight_cycles: false,
+            path: None,
+            highlighted_cycle_edges: HashSet::new(),
+            d2: layout::D2Directives::default(),
+            violations: HashMap::new(),
+            edge_predicates: HashMap::new(),
+            edge_counts: HashMap::new(),
+            edge_widths: false,
+            node_sizes: None,
+            edge_changes: HashMap::new(),
+            node_changes: HashMap::new(),
+            class_color_map: class_color_map
+                .into_iter()
+                .map(|(class, color)| (Cow::Borrowed(class), Cow::Borrowed(color)))
+                .collect(),
+            class_shape_map: HashMap::new(),
             filter,
-        })
+            node_filter: None,
+            prune_isolated: false,
+            min_edge_count: 0,
+            literals: false,
+            attributes: HashMap::new(),
+            show_attributes: false,
+            property_chains: false,
+            source: GraphSource::default(),
+            derived: HashSet::new(),
+            show_restrictions: false,
+            restrictions: HashMap::new(),
+            value_ranges: HashMap::new(),
+            label_style: LabelStyle::default(),
+            label_policy: LabelPolicy::default(),
+            engine_label_policies: Vec::new(),
+            trivial_classes: TRIVIAL_CLASSES.iter().map(|class| class.to_string()).collect(),
+            multi_type: MultiTypePolicy::default(),
+            class_declaration: ClassDeclaration::default(),
+            allowed_namespaces: Vec::new(),
+            denied_namespaces: Vec::new(),
+            kept_predicates: Vec::new(),
+            dropped_predicates: Vec::new(),
+            focus: None,
+            rollup: None,
+            property_rollup: false,
+            property_tops: HashMap::new(),
+            dedup_inverses: false,
+            inverses: HashMap::new(),
+            aggregate_edges: false,
+            datatype_nodes: false,
+            statement_annotations: false,
+            edge_annotations: HashMap::new(),
+            aggregated: HashMap::new(),
+            progress: Vec::new(),
+            default_graph: None,
+            graph_selection: GraphSelection::default(),
+            undefined_classes: BTreeMap::new(),
+            sorted_output: true,
+            unknown_class_nodes: false,
+            sources: Vec::new(),
+            provenance: false,
+            class_sources: HashMap::new(),
+            selected_graphs: None,
+            triples: 0,
+            queries: AtomicUsize::new(0),
+            limits: limits::QueryLimits::default(),
+            load_stages: Vec::new(),
+            build_stages: Vec::new(),
+        }
+    }
+
+    /// Also summarize literal-valued (datatype) properties of each class. Numeric values are
+    /// aggregated into min/avg/max ranges which are rendered on the class node.
+    pub fn with_literals(mut self, literals: bool) -> Self {
+        self.literals = literals;
+        self
+    }
+
+    /// List the datatype (literal-valued) properties used by instances of each class inside its
+    /// node, like the attribute compartment of a UML class: d2 `class` shapes and Graphviz
+    /// record labels
+    pub fn with_attributes(mut self, attributes: bool) -> Self {
+        self.show_attributes = attributes;
+        self
+    }
+
+    /// Draw datatype properties as edges to a node per datatype (`xsd_float`, `xsd_string`),
+    /// so it shows where raw values attach to the class structure
+    pub fn with_datatype_nodes(mut self, datatype_nodes: bool) -> Self {
+        self.datatype_nodes = datatype_nodes;
+        self
+    }
+
+    /// Draw the shortcut edges implied by `owl:propertyChainAxiom` definitions in the
+    /// ontology (e.g. a property defined as `feeds ∘ hasPoint`), labeled and styled as derived
+    pub fn with_property_chains(mut self, property_chains: bool) -> Self {
+        self.property_chains = property_chains;
+        self
+    }
+
+    /// Draw an edge `Class -P-> C` for every `owl:Restriction` on a class, e.g.
+    /// `Class rdfs:subClassOf [ owl:onProperty P ; owl:someValuesFrom C ]`, labeled with the
+    /// restriction type (`some`, `only`, `min 1`, ...)
+    pub fn with_restrictions(mut self, restrictions: bool) -> Self {
+        self.show_restrictions = restrictions;
+        self
+    }
+
+    /// What the class graph is built from, see [`GraphSource`]. The schema and shapes sources
+    /// need no data graph: load the ontologies or shapes graphs with
+    /// [`Visualizer::add_ontology`] and call [`Visualizer::build_graph`].
+    pub fn with_source(mut self, source: GraphSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Replaces the list of class IRIs that are never drawn, [`TRIVIAL_CLASSES`] by default;
+    /// pass an empty list to keep every class
+    pub fn with_trivial_classes(mut self, classes: Vec<String>) -> Self {
+        self.trivial_classes = classes;
+        self
+    }
+
+    /// Only draw classes in `namespace`, given as an IRI prefix or one of the known prefixes
+    /// (`brick`, `rdf`, `owl`). Once any namespace is allowed, classes outside every allowed
+    /// namespace are dropped before the filter runs.
+    pub fn allow_namespace(mut self, namespace: &str) -> Self {
+        self.allowed_namespaces.push(resolve_namespace(namespace));
+        self
+    }
+
+    /// Drops the classes in `namespace` (an IRI prefix or known prefix, as for
+    /// [`Visualizer::allow_namespace`]) before the filter runs
+    pub fn deny_namespace(mut self, namespace: &str) -> Self {
+        self.denied_namespaces.push(resolve_namespace(namespace));
+        self
+    }
+
+    /// Also color classes by `class_color_map`, class IRI to fill like the map given to
+    /// [`Visualizer::new`], but owned, e.g. for a map built at runtime
+    pub fn with_color_map(mut self, class_color_map: HashMap<String, String>) -> Self {
+        self.class_color_map.extend(
+            class_color_map
+                .into_iter()
+                .map(|(class, color)| (Cow::Owned(class), Cow::Owned(color))),
+        );
+        self
+    }
+
+    /// Only draw classes `keep_node` accepts. It sees classes in the same `<iri>` form as the edge
+    /// filter and applies to both endpoints of every edge, after the namespace lists; like the
+    /// edge filter it may capture state, e.g. a class list loaded at runtime.
+    pub fn with_node_filter(mut self, keep_node: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.node_filter = Some(Arc::new(keep_node));
+        self
+    }
+
+    /// Drop classes left without any edge once filtering is done, e.g. classes that would only
+    /// show up for their datatype property summaries
+    pub fn with_prune_isolated(mut self, prune_isolated: bool) -> Self {
+        self.prune_isolated = prune_isolated;
+        self
+    }
+
+    /// Drop edges supported by fewer than `min_edge_count` instance pairs (see
+    /// [`Visualizer::edge_counts`]); edges drawn from restrictions are not counted and always
+    /// kept
+    pub fn with_min_edge_count(mut self, min_edge_count: usize) -> Self {
+        self.min_edge_count = min_edge_count;
+        self
+    }
+
+    /// Only draw edges whose predicate IRI matches `pattern` (or one of the other kept
+    /// patterns), before the filter runs
+    pub fn keep_edges_matching(mut self, pattern: Regex) -> Self {
+        self.kept_predicates.push(pattern);
+        self
+    }
+
+    /// Drops edges whose predicate IRI matches `pattern`, e.g. `hasTag|hasUnit`, before the
+    /// filter runs
+    pub fn drop_edges_matching(mut self, pattern: Regex) -> Self {
+        self.dropped_predicates.push(pattern);
+        self
+    }
+
+    /// Only keep the classes within `hops` edges (in either direction) of the class `iri`, so
+    /// large models can be explored one neighborhood at a time
+    pub fn with_focus(mut self, iri: impl Into<String>, hops: usize) -> Self {
+        self.focus = Some((iri.into(), hops));
+        self
+    }
+
+    /// Style used for predicate labels, see [`LabelStyle`]
+    pub fn with_label_style(mut self, label_style: LabelStyle) -> Self {
+        self.label_style = label_style;
+        self
+    }
+
+    /// Draws each class in the shape of the first class of `class_shape_map` it is a subclass
+    /// of, the way the class color map gives it its fill, e.g. points as circles and equipment
+    /// as rectangles (see [`ColorMap::shapes`]). Other classes keep the renderer's default.
+    pub fn with_shape_map(mut self, class_shape_map: HashMap<&'a str, NodeShape>) -> Self {
+        self.class_shape_map = class_shape_map;
+        self
+    }
+
+    /// Overrides the style of `class` (an IRI) and its subclasses. The class color map gives
+    /// each category its default fill; overrides are merged on top of it field by field, with
+    /// the most specific overriding class applied last.
+    pub fn with_class_style(mut self, class: impl Into<String>, style: NodeStyle) -> Self {
+        self.class_styles.insert(class.into(), style);
+        self
+    }
+
+    /// Adds an "is-a" chip naming each node's category from the class color map (e.g.
+    /// `«Equipment»`) below its label, so categories remain distinguishable without color, e.g.
+    /// when printed in grayscale
+    pub fn with_category_chips(mut self, category_chips: bool) -> Self {
+        self.category_chips = category_chips;
+        self
+    }
+
+    /// The triple store holding everything loaded so far
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// The class graph built by [`Visualizer::create_graph`]: nodes are class labels and edges
+    /// are predicate labels
+    pub fn graph(&self) -> &Graph<Arc<str>, Arc<str>> {
+        &self.g
+    }
+
+    /// Fill color of each class label in [`Visualizer::graph`]
+    pub fn colors(&self) -> &HashMap<Arc<str>, String> {
+        &self.colors
+    }
+
+    /// Fully resolved style of each class label in [`Visualizer::graph`]
+    pub fn styles(&self) -> &HashMap<Arc<str>, NodeStyle> {
+        &self.styles
+    }
+
+    /// IRI of the class color map category each class label in [`Visualizer::graph`] falls
+    /// under. Classes outside every category are absent.
+    pub fn categories(&self) -> &HashMap<Arc<str>, Arc<str>> {
+        &self.categories
+    }
+
+    /// Full IRI of each class label in [`Visualizer::graph`]
+    pub fn node_iris(&self) -> &HashMap<Arc<str>, Arc<str>> {
+        &self.node_iris
+    }
+
+    /// Number of distinct instances of each class label in [`Visualizer::graph`]
+    pub fn instance_counts(&self) -> &HashMap<String, usize> {
+        &self.instance_counts
+    }
+
+    /// Deprecated classes in [`Visualizer::graph`], keyed by class label
+    pub fn deprecations(&self) -> &HashMap<String, Deprecation> {
+        &self.deprecations
+    }
+
+    /// Number of SHACL violations on the instances of each class label in
+    /// [`Visualizer::graph`], from the report given to [`Visualizer::add_validation_report`]
+    pub fn violations(&self) -> &HashMap<String, usize> {
+        &self.violations
+    }
+
+    /// Full predicate IRI of each edge in [`Visualizer::graph`]
+    pub fn edge_predicates(&self) -> &HashMap<EdgeIndex, Arc<str>> {
+        &self.edge_predicates
+    }
+
+    /// Number of instance pairs supporting each edge in [`Visualizer::graph`]
+    pub fn edge_counts(&self) -> &HashMap<EdgeIndex, usize> {
+        &self.edge_counts
+    }
+
+    /// How each edge changed, when the graph was built by [`Visualizer::diff`]
+    pub fn edge_changes(&self) -> &HashMap<EdgeIndex, EdgeChange> {
+        &self.edge_changes
+    }
+
+    /// How each class label changed, when the graph was built by
+    /// [`Visualizer::diff_ontologies`]
+    pub fn node_changes(&self) -> &HashMap<String, NodeChange> {
+        &self.node_changes
+    }
+
+    /// Edges of [`Visualizer::graph`] derived from property chains rather than asserted
+    pub fn derived_edges(&self) -> &HashSet<EdgeIndex> {
+        &self.derived
+    }
+
+    /// (predicate label, datatype local name) of every datatype property used by the instances
+    /// of each class label, when [`Visualizer::with_attributes`] is on
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    /// Edges of [`Visualizer::graph`] drawn from OWL restrictions, with the restriction type
+    pub fn restriction_edges(&self) -> &HashMap<EdgeIndex, String> {
+        &self.restrictions
+    }
+
+    /// Value ranges of numeric datatype properties, keyed by (class, predicate) label
+    pub fn value_ranges(&self) -> &HashMap<(Arc<str>, Arc<str>), ValueRange> {
+        &self.value_ranges
+    }
+
+    /// Triples loaded, queries run and time spent in each stage so far
+    pub fn usage(&self) -> ResourceUsage {
+        ResourceUsage {
+            triples: self.triples,
+            queries: self.queries.load(Ordering::Relaxed),
+            stages: self
+                .load_stages
+                .iter()
+                .chain(self.build_stages.iter())
+                .copied()
+                .collect(),
+            peak_memory: usage::peak_memory(),
+        }
+    }
+
+    /// Evaluates a query against the store, counting it towards [`Visualizer::usage`]
+    fn query(&self, q: &str) -> Result<QueryResults> {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        tracing::trace!(query = q, "evaluating query");
+        let mut query = Query::parse(q, None).map_err(EvaluationError::from)?;
+        if let Some(graphs) = self.default_graph.as_ref().or(self.selected_graphs.as_ref()) {
+            query.dataset_mut().set_default_graph(graphs.clone());
+        }
+        self.evaluate(query)
+    }
+
+    /// Runs one stage of graph building and records how long it took
+    fn timed(&mut self, stage: &'static str, run: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let start = Instant::now();
+        let _span = tracing::info_span!("stage", stage).entered();
+        self.report(|sink| sink.stage_started(stage));
+        run(self)?;
+        let elapsed = start.elapsed();
+        self.report(|sink| sink.stage_finished(stage, elapsed));
+        self.build_stages.push((stage, elapsed));
+        Ok(())
+    }
+
+    /// Counts one more solution of a `stage` query started at `start`, reporting progress
+    /// every [`ROW_PROGRESS_INTERVAL`] rows
+    fn count_row(&self, stage: &'static str, rows: &mut u64, start: Instant) {
+        *rows += 1;
+        if rows.is_multiple_of(ROW_PROGRESS_INTERVAL) {
+            let progress = RowProgress {
+                stage,
+                rows: *rows,
+                elapsed: start.elapsed(),
+            };
+            self.report(|sink| sink.rows(progress));
+        }
+    }
+
+    /// The edge from `from` to `to` for `predicate`, added if it does not exist yet
+    fn edge_index(&mut self, from: NodeIndex, to: NodeIndex, predicate: Arc<str>) -> EdgeIndex {
+        let g = &mut self.g;
+        *self
+            .edge_lookup
+            .entry((from, to, predicate))
+            .or_insert_with_key(|(from, to, predicate)| g.add_edge(*from, *to, predicate.clone()))
+    }
+
+    fn node_index(&mut self, label: &str) -> NodeIndex {
+        if let Some(idx) = self.nodes.get(label) {
+            return *idx;
+        }
+        let label = self.interner.intern(label);
+        let idx = self.g.add_node(label.clone());
+        self.nodes.insert(label, idx);
+        idx
     }
 
     pub fn add_ontology(&mut self, content: impl BufRead, format: GraphFormat) -> Result<()> {
-        Ok(self.store.bulk_loader().load_graph(
-            content,
-            format,
-            GraphNameRef::DefaultGraph,
-            None,
-        )?)
+        self.load("load ontology", content, format)
+    }
+
+    /// Loads a data graph without building the class graph, e.g. for [`Visualizer::stats`] or to
+    /// [`Visualizer::build_graph`] later. Once a class graph is built, the triples are instead
+    /// added to it: only the class pairs their subjects and objects take part in are evaluated
+    /// again (see [`Visualizer::is_built`]).
+    pub fn add_data(&mut self, data_graph: impl BufRead, format: GraphFormat) -> Result<()> {
+        if self.built {
+            return self.update_graph(data_graph, format);
+        }
+        self.load("load data", data_graph, format)
+    }
+
+    /// Loads a data graph and captures everything loaded so far into a [`Snapshot`]
+    pub fn snapshot(mut self, data_graph: impl BufRead, format: GraphFormat) -> Result<Snapshot> {
+        self.load("load data", data_graph, format)?;
+        Ok(Snapshot { store: self.store })
+    }
+
+    fn load(&mut self, stage: &'static str, content: impl BufRead, format: GraphFormat) -> Result<()> {
+        self.load_into(stage, content, format, GraphNameRef::DefaultGraph)
+    }
+
+    fn load_into(
+        &mut self,
+        stage: &'static str,
+        content: impl BufRead,
+        format: GraphFormat,
+        graph: GraphNameRef,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let _span = tracing::info_span!("stage", stage).entered();
+        self.report(|sink| sink.stage_started(stage));
+        self.bulk_loader(stage, start)?.load_graph(content, format, graph, None)?;
+        self.triples = self.store.len()?;
+        let elapsed = start.elapsed();
+        tracing::info!(triples = self.triples, ?elapsed, "loaded");
+        self.report(|sink| sink.stage_finished(stage, elapsed));
+        self.load_stages.push((stage, elapsed));
+        Ok(())
+    }
+
+    /// A bulk loader into the store reporting to the progress sinks, for a `stage` started at
+    /// `start`
+    #[cfg(not(target_family = "wasm"))]
+    fn bulk_loader(&self, stage: &'static str, start: Instant) -> Result<BulkLoader> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        let mut loader = self.store.bulk_loader();
+        if !self.progress.is_empty() {
+            let sinks = self.progress.clone();
+            loader = loader.on_progress(move |triples| {
+                let progress = LoadProgress {
+                    stage,
+                    triples,
+                    elapsed: start.elapsed(),
+                };
+                for sink in sinks.iter() {
+                    sink.loaded(progress);
+                }
+            });
+        }
+        Ok(loader)
+    }
+
+    /// The store itself, as oxigraph has no bulk loader on wasm; it loads the same inputs but
+    /// reports no progress
+    #[cfg(target_family = "wasm")]
+    fn bulk_loader(&self, _stage: &'static str, _start: Instant) -> Result<&Store> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        Ok(&self.store)
     }
 
     pub fn graph_to_d2lang(&self) -> Result<String> {
         let mut w = Vec::new();
+        w.extend_from_slice(self.d2.header().as_bytes());
+
+        // the theme's defaults, which the styles of single nodes and edges below override
+        if let Some(theme) = self.theme {
+            writeln!(w, "style.fill: \"{}\"", theme.background())?;
+            writeln!(w, "*.style.font-color: \"{}\"", theme.font_color())?;
+            writeln!(w, "*.style.stroke: \"{}\"", theme.line_color())?;
+            writeln!(w, "(* -> *)[*].style.stroke: \"{}\"", theme.line_color())?;
+            writeln!(w, "(* -> *)[*].style.font-color: \"{}\"", theme.font_color())?;
+        }
 
         // Write edge labels
+        let widths = self.edge_widths();
         for edge in self.g.edge_references() {
             let source = edge.source();
             let target = edge.target();
             let label = edge.weight();
+            let mut attrs = Vec::new();
+            let style = self.resolved_edge_style(edge.id());
+            if let Some(stroke) = &style.stroke {
+                attrs.push(format!("style.stroke: \"{}\"", stroke));
+            }
+            if let Some(font_color) = &style.font_color {
+                attrs.push(format!("style.font-color: \"{}\"", font_color));
+            }
+            match style.line {
+                Some(LineStyle::Dashed) => attrs.push("style.stroke-dash: 3".to_owned()),
+                Some(LineStyle::Dotted) => attrs.push("style.stroke-dash: 1".to_owned()),
+                _ => {}
+            }
+            // d2 only takes whole stroke widths
+            if let Some(width) = widths.get(&edge.id()) {
+                attrs.push(format!("style.stroke-width: {}", width.round()));
+            }
+            let style = if attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" {{ {} }}", attrs.join("; "))
+            };
             writeln!(
                 w,
-                "{} -> {}: {}",
+                "{} -> {}: \"{}\"{}",
                 self.g.node_weight(source).unwrap(),
                 self.g.node_weight(target).unwrap(),
-                label
+                label.replace('\n', "\\n"),
+                style
             )?;
         }
 
         // write colors
-        for (node, color) in self.colors.iter() {
+        for (node, color) in self.in_order(&self.colors) {
             writeln!(w, "{}.style.fill: \"{}\"", node, color)?;
         }
+        let font_sizes = self.node_font_sizes();
+        for (node, style) in self.in_order(&self.styles) {
+            // d2 only takes whole font sizes
+            if let Some(size) = font_sizes.get(node.as_ref()) {
+                writeln!(w, "{}.style.font-size: {}", node, size.round())?;
+            }
+            if let Some(stroke) = &style.stroke {
+                writeln!(w, "{}.style.stroke: \"{}\"", node, stroke)?;
+            }
+            if self.is_unknown_class(node) {
+                writeln!(w, "{}.style.stroke-dash: 3", node)?;
+            }
+            if let Some(font_color) = &style.font_color {
+                writeln!(w, "{}.style.font-color: \"{}\"", node, font_color)?;
+            }
+            // attribute compartments need the class shape
+            if let Some(shape) = style.shape.filter(|_| !self.attributes.contains_key(&**node)) {
+                writeln!(w, "{}.shape: {}", node, shape.d2_name())?;
+            }
+        }
+
+        // full IRIs and definitions, and how to migrate away from deprecated classes, on hover,
+        // and documentation links
+        for (class, _) in self.in_order(&self.colors) {
+            if let Some(tooltip) = self.tooltip(class) {
+                writeln!(w, "{}.tooltip: \"{}\"", class, dot_escape(&tooltip))?;
+            }
+            if let Some(link) = self.node_link(class) {
+                writeln!(w, "{}.link: \"{}\"", class, dot_escape(&link))?;
+            }
+        }
+
+        // write datatype properties as the fields of class shapes
+        for (class, attributes) in self.in_order(&self.attributes) {
+            writeln!(w, "{}.shape: class", class)?;
+            for (predicate, datatype) in attributes {
+                writeln!(w, "{}.\"{}\": \"{}\"", class, predicate, datatype)?;
+            }
+        }
+
+        // write shortened labels, category chips and value ranges of datatype properties into
+        // the node labels
+        let annotations = self.node_annotations();
+        for (class, _) in self.in_order(&self.colors) {
+            let mut lines = self.display_label(Engine::D2, class);
+            if let Some(annotations) = annotations.get(class.as_ref()) {
+                lines.extend(annotations.iter().cloned());
+            }
+            if lines.len() > 1 || lines[0] != **class {
+                let lines: Vec<String> = lines.iter().map(|line| dot_escape(line)).collect();
+                writeln!(w, "{}.label: \"{}\"", class, lines.join("\\n"))?;
+            }
+        }
+
+        // a markdown block in the corner explaining the prefixes and predicates
+        if self.legend {
+            writeln!(w, "rdf_class_viz_legend: |md")?;
+            writeln!(w, "  **Namespaces**\n")?;
+            for (prefix, namespace) in self.legend_namespaces() {
+                writeln!(w, "  - `{}`: {}", prefix, namespace)?;
+            }
+            writeln!(w, "\n  **Predicates**\n")?;
+            for predicate in self.legend_predicates() {
+                writeln!(w, "  - `{}`", predicate)?;
+            }
+            writeln!(w, "|")?;
+            writeln!(w, "rdf_class_viz_legend.near: top-right")?;
+        }
 
         Ok(String::from_utf8(w)?)
     }
 
-    fn to_color(&'a self, node: &Term) -> Result<&'a str> {
-        for (class_name, color) in self.class_color_map.iter() {
-            let q = format!("PREFIX owl: <http://www.w3.org/2002/07/owl#>
-                     PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
-                     ASK {{
-                        {0} (rdfs:subClassOf|owl:equivalentClass)* <{1}>
-                     }}", node, class_name);
-            if let QueryResults::Boolean(is_subclass) = self.store.query(&q)? {
-                if is_subclass {
-                    return Ok(color);
+    /// Graphviz rendering of the class graph, with nodes filled in their class colors
+    pub fn graph_to_dot(&self) -> Result<String> {
+        let mut w = Vec::new();
+        writeln!(w, "digraph {{")?;
+        for (name, value) in self.dot_attributes.iter() {
+            writeln!(w, "    {}=\"{}\";", name, dot_escape(value))?;
+        }
+        if let Some(font) = &self.font {
+            let font = dot_escape(font);
+            writeln!(w, "    fontname=\"{0}\";\n    node [fontname=\"{0}\"];\n    edge [fontname=\"{0}\"];", font)?;
+        }
+        match self.theme {
+            Some(theme) => {
+                writeln!(w, "    bgcolor=\"{}\";", theme.background())?;
+                writeln!(
+                    w,
+                    "    node [style=filled, color=\"{}\", fontcolor=\"{}\"];",
+                    theme.line_color(),
+                    theme.font_color()
+                )?;
+                writeln!(
+                    w,
+                    "    edge [color=\"{}\", fontcolor=\"{}\"];",
+                    theme.line_color(),
+                    theme.font_color()
+                )?;
+            }
+            None => writeln!(w, "    node [style=filled];")?,
+        }
+
+        let annotations = self.node_annotations();
+        let font_sizes = self.node_font_sizes();
+        for (node, color) in self.in_order(&self.colors) {
+            let mut lines = self.display_label(Engine::Dot, node);
+            if let Some(annotations) = annotations.get(node.as_ref()) {
+                lines.extend(annotations.iter().cloned());
+            }
+            let label: Vec<String> = lines.iter().map(|line| dot_escape(line)).collect();
+            let label = label.join("\\n");
+            let mut attrs = match self.attributes.get(&**node) {
+                Some(attributes) => {
+                    // records take their own escaping, so rebuild the title from scratch
+                    let title: Vec<String> = lines.iter().map(|line| record_escape(line)).collect();
+                    let fields: String = attributes
+                        .iter()
+                        .map(|(predicate, datatype)| {
+                            format!("{}\\l", record_escape(&format!("{}: {}", predicate, datatype)))
+                        })
+                        .collect();
+                    format!(
+                        "shape=record, label=\"{{{}|{}}}\", fillcolor=\"{}\"",
+                        title.join("\\n"),
+                        fields,
+                        dot_escape(color)
+                    )
+                }
+                None => format!("label=\"{}\", fillcolor=\"{}\"", label, dot_escape(color)),
+            };
+            if let Some(tooltip) = self.tooltip(node) {
+                attrs.push_str(&format!(", tooltip=\"{}\"", dot_escape(&tooltip)));
+            }
+            if let Some(link) = self.node_link(node) {
+                attrs.push_str(&format!(", URL=\"{}\"", dot_escape(&link)));
+            }
+            if let Some(style) = self.styles.get(node) {
+                if let Some(stroke) = &style.stroke {
+                    attrs.push_str(&format!(", color=\"{}\"", dot_escape(stroke)));
+                }
+                if let Some(font_color) = &style.font_color {
+                    attrs.push_str(&format!(", fontcolor=\"{}\"", dot_escape(font_color)));
                 }
+                // records draw the attribute compartments
+                if let Some(shape) = style.shape.filter(|_| !self.attributes.contains_key(&**node)) {
+                    attrs.push_str(&format!(", shape={}", shape.dot_name()));
+                }
+            }
+            if self.is_unknown_class(node) {
+                attrs.push_str(", style=\"filled,dashed\"");
             }
+            if let Some(size) = font_sizes.get(node.as_ref()) {
+                attrs.push_str(&format!(", fontsize={:.1}", size));
+            }
+            writeln!(w, "    \"{}\" [{}];", dot_escape(node), attrs)?;
+        }
 
+        let widths = self.edge_widths();
+        for edge in self.g.edge_references() {
+            let mut style = String::new();
+            let edge_style = self.resolved_edge_style(edge.id());
+            if let Some(stroke) = &edge_style.stroke {
+                style.push_str(&format!(", color=\"{}\"", dot_escape(stroke)));
+            }
+            if let Some(font_color) = &edge_style.font_color {
+                style.push_str(&format!(", fontcolor=\"{}\"", dot_escape(font_color)));
+            }
+            match edge_style.line {
+                Some(LineStyle::Dashed) => style.push_str(", style=dashed"),
+                Some(LineStyle::Dotted) => style.push_str(", style=dotted"),
+                _ => {}
+            }
+            if let Some(width) = widths.get(&edge.id()) {
+                style.push_str(&format!(", penwidth={:.2}", width));
+            }
+            writeln!(
+                w,
+                "    \"{}\" -> \"{}\" [label=\"{}\"{}];",
+                dot_escape(&self.g[edge.source()]),
+                dot_escape(&self.g[edge.target()]),
+                dot_escape(edge.weight()),
+                style
+            )?;
+        }
+
+        if let Some(lines) = self.legend_lines() {
+            let label: String = lines.iter().map(|line| format!("{}\\l", dot_escape(line))).collect();
+            writeln!(
+                w,
+                "    \"rdf_class_viz_legend\" [shape=note, fillcolor=\"{}\", label=\"{}\"];",
+                self.default_fill(),
+                label
+            )?;
         }
-        Ok("#ffffff")
+
+        writeln!(w, "}}")?;
+        Ok(String::from_utf8(w)?)
     }
 
-    pub fn create_graph(&'a mut self, data_graph: impl BufRead, format: GraphFormat) -> Result<String> {
-        // load into a graph
-        self.store.bulk_loader().load_graph(
-            data_graph,
-            format,
-            GraphNameRef::DefaultGraph,
-            None,
-        )?;
+    /// The [`Visualizer::edge_style`] of an edge, dashed when it is derived and in the color of
+    /// its cycle or its change in a diff
+    pub(crate) fn resolved_edge_style(&self, edge: EdgeIndex) -> EdgeStyle {
+        let mut style = self.edge_style(edge);
+        if self.derived.contains(&edge) {
+            style.line = Some(LineStyle::Dashed);
+        }
+        if self.highlighted_cycle_edges.contains(&edge) {
+            style.stroke = Some(CYCLE_COLOR.to_owned());
+            style.font_color = Some(CYCLE_COLOR.to_owned());
+        }
+        if let Some(change) = self.edge_changes.get(&edge) {
+            style.stroke = Some(change.color().to_owned());
+            style.font_color = Some(change.color().to_owned());
+        }
+        style
+    }
+
+    /// The category chip of a class label, if chips are enabled and the class has a category
+    pub(crate) fn category_chip(&self, class: &str) -> Option<String> {
+        if !self.category_chips {
+            return None;
+        }
+        let category = self.categories.get(class)?;
+        Some(format!("«{}»", split_iri(category).1))
+    }
+
+    /// Extra label lines of each class: its category chip, source ontology, unknown class
+    /// marker, version change and violation count followed by one "predicate: min .. max (avg)" line per numeric datatype property
+    fn node_annotations(&self) -> HashMap<&str, Vec<String>> {
+        let mut annotations: HashMap<&str, Vec<String>> = HashMap::new();
+        for ((class, predicate), range) in self.value_ranges.iter() {
+            annotations.entry(&**class).or_default().push(format!(
+                "{}: {:.2} .. {:.2} (avg {:.2})",
+                predicate,
+                range.min,
+                range.max,
+                range.avg()
+            ));
+        }
+        for lines in annotations.values_mut() {
+            lines.sort();
+        }
+        for (class, count) in self.violations.iter() {
+            let noun = if *count == 1 { "violation" } else { "violations" };
+            annotations
+                .entry(class.as_str())
+                .or_default()
+                .insert(0, format!("{} {}", count, noun));
+        }
+        for (class, change) in self.node_changes.iter() {
+            annotations
+                .entry(class.as_str())
+                .or_default()
+                .insert(0, change.describe());
+        }
+        for class in self.colors.keys() {
+            if self.is_unknown_class(class) {
+                annotations.entry(class.as_ref()).or_default().insert(0, "unknown class".to_owned());
+            }
+        }
+        for (class, source) in self.class_sources.iter() {
+            annotations
+                .entry(class.as_str())
+                .or_default()
+                .insert(0, format!("from {}", source));
+        }
+        for class in self.colors.keys() {
+            if let Some(chip) = self.category_chip(class) {
+                annotations.entry(class.as_ref()).or_default().insert(0, chip);
+            }
+        }
+        for (class, metrics) in self.metric_lines() {
+            if let Some(class) = self.colors.get_key_value(class.as_str()).map(|(class, _)| class.as_ref()) {
+                annotations.entry(class).or_default().push(metrics);
+            }
+        }
+        annotations
+    }
+
+    /// Whether a class is not trivial and passes the namespace allow and deny lists and then the
+    /// node filter
+    fn keeps_class(&self, class: &Term) -> bool {
+        self.class_exclusion(class).is_none()
+    }
+
+    /// Why [`Visualizer::keeps_class`] leaves out a class, if it does
+    fn class_exclusion(&self, class: &Term) -> Option<&'static str> {
+        let iri = iri(class);
+        let in_any = |namespaces: &[String]| namespaces.iter().any(|ns| iri.starts_with(ns.as_str()));
+        if self.trivial_classes.contains(&iri) {
+            Some("trivial class")
+        } else if !self.allowed_namespaces.is_empty() && !in_any(&self.allowed_namespaces) {
+            Some("class namespace not allowed")
+        } else if in_any(&self.denied_namespaces) {
+            Some("class namespace denied")
+        } else if self.node_filter.as_ref().is_some_and(|keep| !keep(class.to_string().as_str())) {
+            Some("class dropped by the node filter")
+        } else {
+            None
+        }
+    }
+
+    /// Why a candidate edge supported by `count` instance pairs is left out of the graph, if it
+    /// is: a class filtered out at either end, a dropped predicate or the edge filter
+    fn edge_exclusion(&mut self, from: &Term, to: &Term, p: &Term, count: usize) -> Option<&'static str> {
+        self.class_exclusion(from)
+            .or_else(|| self.class_exclusion(to))
+            .or_else(|| (!self.predicate_allowed(&iri(p))).then_some("predicate dropped by --keep-edge or --drop-edge patterns"))
+            .or_else(|| (!self.keeps_edge(from, to, p, count)).then_some("edge filter returned false"))
+    }
+
+    /// Whether an edge predicate IRI passes the keep and drop patterns
+    fn predicate_allowed(&self, predicate: &str) -> bool {
+        (self.kept_predicates.is_empty() || self.kept_predicates.iter().any(|re| re.is_match(predicate)))
+            && !self.dropped_predicates.iter().any(|re| re.is_match(predicate))
+    }
+
+    fn is_subclass(&self, node: &str, class: &str) -> Result<bool> {
+        // shapes are colored and styled by the classes they target
+        let target = match self.source {
+            GraphSource::Shapes => "sh:targetClass?/",
+            _ => "",
+        };
+        let q = format!("PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX sh: <http://www.w3.org/ns/shacl#>
+                 ASK {{
+                    {0} {2}(rdfs:subClassOf|owl:equivalentClass)* <{1}>
+                 }}", node, class, target);
+        if let QueryResults::Boolean(is_subclass) = self.query(&q)? {
+            return Ok(is_subclass);
+        }
+        Ok(false)
+    }
+
+    /// The (category IRI, color) entry of the class color map `node` falls under
+    fn category(&self, node: &Term) -> Result<Option<(&str, &str)>> {
+        for (class_name, color) in self.class_color_map.iter() {
+            if self.is_subclass(&node.to_string(), class_name)? {
+                return Ok(Some((class_name, color)));
+            }
+        }
+        Ok(None)
+    }
 
+    /// The shape of the entry of the class shape map `node` falls under
+    fn category_shape(&self, node: &Term) -> Result<Option<NodeShape>> {
+        for (class_name, shape) in self.class_shape_map.iter() {
+            if self.is_subclass(&node.to_string(), class_name)? {
+                return Ok(Some(*shape));
+            }
+        }
+        Ok(None)
+    }
+
+    fn resolve_style(&self, node: &Term) -> Result<NodeStyle> {
+        let node = node.to_string();
+        let mut matches = Vec::new();
+        for (class, style) in self.class_styles.iter() {
+            if self.is_subclass(&node, class)? {
+                matches.push((class, style));
+            }
+        }
+
+        // rank each matching override by how many of the others it specializes, so the most
+        // specific one is merged last
+        let mut ranked = Vec::new();
+        for (class, style) in matches.iter() {
+            let mut depth = 0;
+            for (other, _) in matches.iter() {
+                if other != class && self.is_subclass(&format!("<{}>", class), other)? {
+                    depth += 1;
+                }
+            }
+            ranked.push((depth, *style));
+        }
+        ranked.sort_by_key(|(depth, _)| *depth);
+
+        Ok(ranked
+            .into_iter()
+            .fold(NodeStyle::default(), |style, (_, over)| style.merge(over)))
+    }
+
+    /// Property chains declared with `owl:propertyChainAxiom` as (property, chain) IRIs. Chains
+    /// with members that are not plain properties (e.g. inverses) are skipped.
+    fn chains(&self) -> Result<Vec<(String, Vec<String>)>> {
+        // the position of each list member is the number of list nodes up to and including it
         let q = "PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
                  PREFIX owl: <http://www.w3.org/2002/07/owl#>
-                 SELECT ?from ?p ?to WHERE {
-                     ?x rdf:type ?from .
-                     ?x ?p ?y .
-                     ?y rdf:type ?to .
-                     ?from a owl:Class .
-                     ?to a owl:Class .
-                 }";
+                 SELECT ?p ?list ?member (COUNT(?mid) AS ?pos) WHERE {
+                     ?p owl:propertyChainAxiom ?list .
+                     ?list rdf:rest* ?mid .
+                     ?mid rdf:rest* ?node .
+                     ?node rdf:first ?member .
+                 } GROUP BY ?p ?list ?node ?member";
 
-        if let QueryResults::Solutions(solutions) = self.store.query(q)? {
-            let mut edges: Vec<(usize, usize, usize)> = Vec::new();
+        // (position, member IRI) of the members of each (property, list)
+        type Members = Vec<(u64, Option<String>)>;
+        let mut members: HashMap<(String, String), Members> = HashMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(q)? {
             for row in solutions {
                 let row = row?;
+                let Some(Term::NamedNode(p)) = row.get("p") else {
+                    continue;
+                };
+                let list = row.get("list").unwrap().to_string();
+                let pos = match row.get("pos") {
+                    Some(Term::Literal(pos)) => pos.value().parse().unwrap_or(0),
+                    _ => 0,
+                };
+                let member = match row.get("member") {
+                    Some(Term::NamedNode(member)) => Some(member.as_str().to_owned()),
+                    _ => None,
+                };
+                members
+                    .entry((p.as_str().to_owned(), list))
+                    .or_default()
+                    .push((pos, member));
+            }
+        }
 
-                {
-                    let from = row.get("from").unwrap().to_string();
-                    let to = row.get("to").unwrap().to_string();
-                    let p = row.get("p").unwrap().to_string();
+        let mut chains = Vec::new();
+        for ((p, _), mut chain) in members {
+            chain.sort();
+            if let Some(chain) = chain.into_iter().map(|(_, member)| member).collect() {
+                chains.push((p, chain));
+            }
+        }
+        Ok(chains)
+    }
 
-                    if !(self.filter)(from.as_str(), to.as_str(), p.as_str()) {
+    /// Adds an edge for every pair of classes whose instances are connected through a property
+    /// chain, unless the chain's property is already asserted between them
+    fn add_derived_edges(&mut self) -> Result<()> {
+        for (p, chain) in self.chains()? {
+            let path: Vec<String> = chain.iter().map(|member| format!("<{}>", member)).collect();
+            let q = format!("PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                     PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                     PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                     SELECT ?from ?to (COUNT(*) AS ?count) WHERE {{
+                         ?x {} ?y .
+                         ?x rdf:type ?from .
+                         ?y rdf:type ?to .
+                         {}
+                         {}
+                         {}
+                         {}
+                     }} GROUP BY ?from ?to",
+                path.join("/"),
+                self.class_pattern("from"),
+                self.class_pattern("to"),
+                self.type_policy("x", "from"),
+                self.type_policy("y", "to"));
+            let predicate = Term::NamedNode(NamedNode::new_unchecked(p.clone()));
+
+            if let QueryResults::Solutions(solutions) = self.query(&q)? {
+                for row in solutions {
+                    let row = row?;
+                    let from_term = row.get("from").unwrap();
+                    let to_term = row.get("to").unwrap();
+                    if !self.keeps_class(from_term) || !self.keeps_class(to_term) || !self.predicate_allowed(&p) {
+                        continue;
+                    }
+                    let count = count_of(row.get("count"));
+                    if !self.keeps_edge(from_term, to_term, &predicate, count) {
                         continue;
                     }
+
+                    let f = self.interner.label(from_term);
+                    self.style_node(&f, from_term)?;
+                    let t = self.interner.label(to_term);
+                    self.style_node(&t, to_term)?;
+
+                    let from_idx = self.node_index(&f);
+                    let to_idx = self.node_index(&t);
+                    let e = self.rolled_up_property(&p);
+                    let existing = self.edge_lookup.get(&(from_idx, to_idx, e.clone())).copied();
+                    let idx = match existing {
+                        Some(idx) if !self.derived.contains(&idx) => continue,
+                        Some(idx) => idx,
+                        None => {
+                            let idx = self.edge_index(from_idx, to_idx, e);
+                            self.derived.insert(idx);
+                            idx
+                        }
+                    };
+                    *self.edge_counts.entry(idx).or_insert(0) += count;
                 }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds an edge from every class to the class an OWL restriction on it points to, one per
+    /// (property, restriction type)
+    fn add_restriction_edges(&mut self) -> Result<()> {
+        let q = format!("PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 SELECT ?from ?p ?to ?kind ?n WHERE {{
+                     {}
+                     ?from rdfs:subClassOf|owl:equivalentClass ?r .
+                     ?r owl:onProperty ?p .
+                     {{
+                         ?r owl:someValuesFrom ?to . BIND(\"some\" AS ?kind)
+                     }} UNION {{
+                         ?r owl:allValuesFrom ?to . BIND(\"only\" AS ?kind)
+                     }} UNION {{
+                         ?r owl:onClass ?to ; owl:minQualifiedCardinality ?n . BIND(\"min\" AS ?kind)
+                     }} UNION {{
+                         ?r owl:onClass ?to ; owl:maxQualifiedCardinality ?n . BIND(\"max\" AS ?kind)
+                     }} UNION {{
+                         ?r owl:onClass ?to ; owl:qualifiedCardinality ?n . BIND(\"exactly\" AS ?kind)
+                     }}
+                     {}
+                 }}", self.class_pattern("from"), self.class_pattern("to"));
+
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
                 let from_term = row.get("from").unwrap();
-                let f = rewrite_term(&from_term);
-                if !self.colors.contains_key(&f) {
-                    self.colors.insert(f.clone(), self.to_color(&from_term).unwrap().to_owned());
+                let to_term = row.get("to").unwrap();
+                let p = row.get("p").unwrap();
+                if !self.keeps_class(from_term) || !self.keeps_class(to_term) || !self.predicate_allowed(&iri(p)) {
+                    continue;
+                }
+                if !self.keeps_edge(from_term, to_term, p, 1) {
+                    continue;
+                }
+                let mut kind = match row.get("kind") {
+                    Some(Term::Literal(kind)) => kind.value().to_owned(),
+                    _ => continue,
+                };
+                if let Some(Term::Literal(n)) = row.get("n") {
+                    kind = format!("{} {}", kind, n.value());
                 }
-                self.labels.push(f);
-                let f_idx = self.labels.len() - 1;
 
-                let to_term = row.get("to").unwrap();
-                let t = rewrite_term(&to_term);
-                if !self.colors.contains_key(&t) {
-                    self.colors.insert(t.clone(), self.to_color(&to_term).unwrap().to_owned());
+                let f = self.interner.label(from_term);
+                self.style_node(&f, from_term)?;
+                let t = self.interner.label(to_term);
+                self.style_node(&t, to_term)?;
+
+                let from_idx = self.node_index(&f);
+                let to_idx = self.node_index(&t);
+                let p = self.interner.iri(p);
+                let p = self.rolled_up_property(&p);
+                let exists = self.g.edges_connecting(from_idx, to_idx).any(|edge| {
+                    **edge.weight() == *p && self.restrictions.get(&edge.id()) == Some(&kind)
+                });
+                if !exists {
+                    let idx = self.g.add_edge(from_idx, to_idx, p);
+                    self.restrictions.insert(idx, kind);
                 }
-                self.labels.push(t);
-                let t_idx = self.labels.len() - 1;
+            }
+        }
+        Ok(())
+    }
 
-                let e = rewrite_term(row.get("p").unwrap());
-                self.labels.push(e);
-                let e_idx = self.labels.len() - 1;
-                edges.push((f_idx, t_idx, e_idx));
+    /// Counts the instances of every class that ended up in the graph
+    fn instance_count_query(&self, scope: &str) -> String {
+        format!("PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 SELECT ?class (COUNT(DISTINCT ?x) AS ?count) WHERE {{
+                     {}
+                     ?x rdf:type ?class .
+                     {}
+                     {}
+                 }} GROUP BY ?class", scope, self.class_pattern("class"), self.type_policy("x", "class"))
+    }
+
+    /// Instances of each class, keyed by IRI; `scope` is a pattern binding `?class` to the
+    /// classes to count, or empty for all of them
+    pub(crate) fn query_instance_counts(&self, scope: &str) -> Result<BTreeMap<String, usize>> {
+        let mut counts = BTreeMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(&self.instance_count_query(scope))? {
+            for row in solutions {
+                let row = row?;
+                counts.insert(iri(row.get("class").unwrap()), count_of(row.get("count")));
             }
+        }
+        Ok(counts)
+    }
 
-            // Now that we have collected all the data, update the graph outside the loop
-            for (from, to, edge) in edges {
-                let from: &'a str = self.labels[from].as_str();
-                let from_idx = *self
-                    .nodes
-                    .entry(from)
-                    .or_insert_with(|| self.g.add_node(from));
+    fn count_instances(&mut self) -> Result<()> {
+        if let Some(counts) = self.cached_instance_counts() {
+            self.instance_counts.extend(counts);
+            return Ok(());
+        }
+        if let QueryResults::Solutions(solutions) = self.query(&self.instance_count_query(""))? {
+            for row in solutions {
+                let row = row?;
+                let class = self.interner.symbols(row.get("class").unwrap()).clone();
+                if let Some(Term::Literal(count)) = row.get("count") {
+                    let count = count.value().parse().unwrap_or(0);
+                    if let Some(summary) = self.summary.as_mut() {
+                        summary.instance_counts.insert(class.iri.to_string(), count);
+                    }
+                    self.instance_counts.insert(class.label.to_string(), count);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the edge filter on a candidate edge supported by `count` instance pairs
+    fn keeps_edge(&mut self, from: &Term, to: &Term, p: &Term, count: usize) -> bool {
+        let [from, to, p] = [from, to, p].map(|term| self.interner.symbols(term).clone());
+        let instances = |symbols: &Symbols| self.instance_counts.get(&*symbols.label).copied().unwrap_or(0);
+        let context = FilterContext {
+            count,
+            from_instances: instances(&from),
+            to_instances: instances(&to),
+            from_namespace: split_iri(&from.iri).0,
+            to_namespace: split_iri(&to.iri).0,
+            edge_namespace: split_iri(&p.iri).0,
+        };
+        (self.filter)(&from.term, &to.term, &p.term, &context)
+    }
+
+    /// Resolves and records the fill color and style of a class label the first time it is seen
+    fn style_node(&mut self, label: &str, node: &Term) -> Result<()> {
+        if self.colors.contains_key(label) {
+            return Ok(());
+        }
+        let category = self.category(node)?.map(|(category, color)| (category.to_owned(), color.to_owned()));
+        let color = category.as_ref().map_or(self.default_fill(), |(_, color)| color.as_str());
+        let style = NodeStyle {
+            shape: self.category_shape(node)?,
+            ..NodeStyle::fill(color)
+        }
+        .merge(&self.resolve_style(node)?);
+        let label = self.interner.intern(label);
+        if let Some((category, _)) = category {
+            let category = self.interner.intern(&category);
+            self.categories.insert(label.clone(), category);
+        }
+        self.colors
+            .insert(label.clone(), style.fill.clone().unwrap_or_default());
+        self.styles.insert(label.clone(), style);
+        let iri = self.interner.iri(node);
+        self.node_iris.insert(label, iri);
+        Ok(())
+    }
+
+    fn summarize_literals(&mut self) -> Result<()> {
+        let q = format!("PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 SELECT ?class ?p ?value WHERE {{
+                     ?x rdf:type ?class .
+                     ?x ?p ?value .
+                     {}
+                     FILTER(isLiteral(?value))
+                     {}
+                 }}", self.class_pattern("class"), self.type_policy("x", "class"));
+
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                let Some(Term::Literal(literal)) = row.get("value") else {
+                    continue;
+                };
+                let Some(value) = numeric_value(literal) else {
+                    continue;
+                };
+                let class_term = row.get("class").unwrap();
+                let p = row.get("p").unwrap();
+
+                let datatype = Term::NamedNode(literal.datatype().into_owned());
+                if !self.keeps_class(class_term) {
+                    continue;
+                }
+                if !self.keeps_edge(class_term, &datatype, p, 1) {
+                    continue;
+                }
+
+                let class = self.interner.label(class_term);
+                self.style_node(&class, class_term)?;
+                let p = self.interner.iri(p);
+                self.value_ranges
+                    .entry((class, p))
+                    .and_modify(|range| range.add(value))
+                    .or_insert_with(|| ValueRange::new(value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds an edge from every class to a node for each datatype its instances have literal
+    /// values of, one per predicate
+    fn add_datatype_edges(&mut self) -> Result<()> {
+        let q = format!("PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 SELECT ?class ?p ?datatype (COUNT(*) AS ?count) WHERE {{
+                     ?x rdf:type ?class .
+                     ?x ?p ?value .
+                     {}
+                     FILTER(isLiteral(?value))
+                     BIND(DATATYPE(?value) AS ?datatype)
+                     {}
+                 }} GROUP BY ?class ?p ?datatype", self.class_pattern("class"), self.type_policy("x", "class"));
+
+        let (start, mut rows) = (Instant::now(), 0);
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                self.count_row("datatypes", &mut rows, start);
+                let class_term = row.get("class").unwrap();
+                let p = row.get("p").unwrap();
+                let Some(datatype) = row.get("datatype") else {
+                    continue;
+                };
+                let count = count_of(row.get("count"));
+                if !self.keeps_class(class_term)
+                    || !self.predicate_allowed(&iri(p))
+                    || !self.keeps_edge(class_term, datatype, p, count)
+                {
+                    continue;
+                }
 
-                let to: &'a str = self.labels[to].as_str();
-                let to_idx = *self.nodes.entry(to).or_insert_with(|| self.g.add_node(to));
+                let class = self.interner.label(class_term);
+                self.style_node(&class, class_term)?;
+                let node = self.interner.label(datatype);
+                if !self.colors.contains_key(&node) {
+                    self.colors.insert(node.clone(), DATATYPE_FILL.to_owned());
+                    self.styles.insert(node.clone(), NodeStyle::fill(DATATYPE_FILL));
+                    let iri = self.interner.iri(datatype);
+                    self.node_iris.insert(node.clone(), iri);
+                }
 
-                self.g
-                    .update_edge(from_idx, to_idx, self.labels[edge].as_str());
+                let from_idx = self.node_index(&class);
+                let to_idx = self.node_index(&node);
+                let p = self.interner.iri(p);
+                let e = self.rolled_up_property(&p);
+                let idx = self.edge_index(from_idx, to_idx, e);
+                *self.edge_counts.entry(idx).or_insert(0) += count;
             }
         }
+        Ok(())
+    }
+
+    /// Records the datatype properties used by the instances of each class
+    fn collect_attributes(&mut self) -> Result<()> {
+        let q = format!("PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 SELECT DISTINCT ?class ?p (DATATYPE(?value) AS ?datatype) WHERE {{
+                     ?x rdf:type ?class .
+                     ?x ?p ?value .
+                     {}
+                     FILTER(isLiteral(?value))
+                     {}
+                 }}", self.class_pattern("class"), self.type_policy("x", "class"));
 
-        graph_to_dot(&self.g, "output.dot")?;
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                let class_term = row.get("class").unwrap();
+                let p = row.get("p").unwrap();
+                let Some(Term::NamedNode(datatype)) = row.get("datatype") else {
+                    continue;
+                };
+                if !self.keeps_class(class_term) {
+                    continue;
+                }
+                if !self.keeps_edge(class_term, &Term::NamedNode(datatype.clone()), p, 1) {
+                    continue;
+                }
+
+                let class = self.interner.label(class_term);
+                self.style_node(&class, class_term)?;
+                let p = self.interner.iri(p);
+                let datatype = self.interner.intern(split_iri(datatype.as_str()).1);
+                self.attributes.entry(class).or_default().insert((p, datatype));
+            }
+        }
+        Ok(())
+    }
+
+    /// Query for the (from, predicate, to) rows of the [`GraphSource`], with the number of
+    /// instance pairs behind each as `?count` for instance data
+    fn edge_query(&self, scope: &str) -> String {
+        match self.source {
+            GraphSource::Instances => format!(
+                "PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 SELECT ?from ?p ?to (COUNT(*) AS ?count) WHERE {{
+                     {}
+                     ?x rdf:type ?from .
+                     ?x ?p ?y .
+                     ?y rdf:type ?to .
+                     {}
+                     {}
+                     {}
+                     {}
+                 }} GROUP BY ?from ?p ?to",
+                scope,
+                self.class_pattern("from"),
+                self.class_pattern("to"),
+                self.type_policy("x", "from"),
+                self.type_policy("y", "to")
+            ),
+            GraphSource::Schema => format!(
+                "PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 SELECT ?from ?p ?to WHERE {{
+                     ?p rdfs:domain ?from .
+                     ?p rdfs:range ?to .
+                     {}
+                     {}
+                 }}",
+                self.class_pattern("from"),
+                self.class_pattern("to")
+            ),
+            GraphSource::Shapes => {
+                "PREFIX sh: <http://www.w3.org/ns/shacl#>
+                 SELECT ?from ?p ?to WHERE {
+                     ?from a sh:NodeShape .
+                     ?from sh:property ?shape .
+                     ?shape sh:path ?p .
+                     ?shape sh:class|sh:node ?to .
+                     FILTER(isIRI(?p) && isIRI(?to))
+                 }"
+                .to_owned()
+            }
+        }
+    }
+
+    /// Adds an edge for every (from, predicate, to) found in the [`GraphSource`], or stored in
+    /// the [`GraphCache`] the graph is built from
+    fn add_edges(&mut self) -> Result<()> {
+        let mut inverse_counts = HashMap::new();
+        let (start, mut rows) = (Instant::now(), 0);
+        if let Some(cache) = self.cache.clone() {
+            let term = |iri: &str| Term::NamedNode(NamedNode::new_unchecked(iri));
+            for edge in cache.edges.iter() {
+                self.count_row("edges", &mut rows, start);
+                let (from, p, to) = (term(&edge.from), term(&edge.predicate), term(&edge.to));
+                self.add_edge_row(&from, &p, &to, edge.count, &mut inverse_counts)?;
+            }
+        } else if let QueryResults::Solutions(solutions) = self.query(&self.edge_query(""))? {
+            for row in solutions {
+                let row = row?;
+                self.count_row("edges", &mut rows, start);
+                // Schema and shape statements are counted once per row
+                let count = row.get("count").map_or(1, |count| count_of(Some(count)));
+                let (from, p, to) = (row.get("from").unwrap(), row.get("p").unwrap(), row.get("to").unwrap());
+                if let Some(summary) = self.summary.as_mut() {
+                    summary.edges.push(CachedEdge::new(from, p, to, count));
+                }
+                self.add_edge_row(from, p, to, count, &mut inverse_counts)?;
+            }
+        }
+        tracing::info!(rows, edges = self.g.edge_count(), "edge query done");
+        // Both directions of an inverse pair usually state the same facts, so the flipped
+        // edges are not added to the count of the canonical ones
+        for (idx, count) in inverse_counts {
+            let total = self.edge_counts.entry(idx).or_insert(0);
+            *total = (*total).max(count);
+        }
+        Ok(())
+    }
+
+    /// Adds the edge of one (from, predicate, to) row supported by `count` instance pairs,
+    /// unless it is filtered out; flipped inverse edges are counted into `inverse_counts`
+    fn add_edge_row(
+        &mut self,
+        from_term: &Term,
+        p_term: &Term,
+        to_term: &Term,
+        count: usize,
+        inverse_counts: &mut HashMap<EdgeIndex, usize>,
+    ) -> Result<()> {
+        if let Some(reason) = self.edge_exclusion(from_term, to_term, p_term, count) {
+            tracing::debug!(from = %from_term, predicate = %p_term, to = %to_term, count, reason, "dropped edge");
+            return Ok(());
+        }
+        let f = self.interner.label(from_term);
+        self.style_node(&f, from_term)?;
+
+        let t = self.interner.label(to_term);
+        self.style_node(&t, to_term)?;
+
+        let p = self.interner.iri(p_term);
+        let e = self.rolled_up_property(&p);
+
+        let from_idx = self.node_index(&f);
+        let to_idx = self.node_index(&t);
+        if let Some(canonical) = self.inverses.get(&*e) {
+            let canonical = self.interner.intern(canonical);
+            let idx = self.edge_index(to_idx, from_idx, canonical);
+            *inverse_counts.entry(idx).or_insert(0) += count;
+            return Ok(());
+        }
+        let idx = self.edge_index(from_idx, to_idx, e);
+        *self.edge_counts.entry(idx).or_insert(0) += count;
+        Ok(())
+    }
+
+    pub fn create_graph(&mut self, data_graph: impl BufRead, format: GraphFormat) -> Result<String> {
+        // load into a graph
+        self.load("load data", data_graph, format)?;
+        self.build_graph()?;
+
+        std::fs::write("output.dot", self.graph_to_dot()?)?;
         self.graph_to_d2lang()
     }
+
+    /// Builds the class graph from the triples currently in the store, replacing any graph
+    /// built before
+    pub fn build_graph(&mut self) -> Result<()> {
+        self.g.clear();
+        self.nodes.clear();
+        self.edge_lookup.clear();
+        self.colors.clear();
+        self.styles.clear();
+        self.categories.clear();
+        self.node_iris.clear();
+        self.instance_counts.clear();
+        self.deprecations.clear();
+        self.definitions.clear();
+        self.violations.clear();
+        self.edge_predicates.clear();
+        self.edge_counts.clear();
+        self.edge_changes.clear();
+        self.node_changes.clear();
+        self.derived.clear();
+        self.restrictions.clear();
+        self.value_ranges.clear();
+        self.attributes.clear();
+        self.build_stages.clear();
+        self.property_tops.clear();
+        self.inverses.clear();
+        self.aggregated.clear();
+        self.edge_annotations.clear();
+        self.class_sources.clear();
+        self.undefined_classes.clear();
+        self.interner.clear();
+        self.selected_graphs = self.selected_graphs()?;
+        // Kept to update the graph when more data is added, see [`Visualizer::add_data`]
+        self.summary = (self.cache.is_none() && self.is_incremental()).then(GraphCache::new);
+
+        if self.source == GraphSource::Instances {
+            self.timed("undefined classes", Self::find_undefined_classes)?;
+        }
+
+        // Counted up front for the filter context, then narrowed to the classes in the graph
+        self.timed("instance counts", Self::count_instances)?;
+        if self.literals {
+            self.timed("literals", Self::summarize_literals)?;
+        }
+        if self.show_attributes {
+            self.timed("attributes", Self::collect_attributes)?;
+        }
+        if self.property_rollup {
+            self.timed("property rollup", Self::find_property_tops)?;
+        }
+        if self.dedup_inverses {
+            self.timed("inverses", Self::find_inverses)?;
+        }
+        self.timed("edges", Self::add_edges)?;
+        if self.datatype_nodes {
+            self.timed("datatypes", Self::add_datatype_edges)?;
+        }
+        if self.statement_annotations {
+            self.timed("annotations", Self::collect_annotations)?;
+        }
+        if self.property_chains {
+            self.timed("property chains", Self::add_derived_edges)?;
+        }
+        if self.show_restrictions {
+            self.timed("restrictions", Self::add_restriction_edges)?;
+        }
+        let node_iris = &self.node_iris;
+        self.instance_counts.retain(|class, _| node_iris.contains_key(class.as_str()));
+        self.timed("deprecations", Self::find_deprecations)?;
+        if self.tooltips {
+            self.timed("definitions", Self::find_definitions)?;
+        }
+        if self.provenance {
+            self.timed("provenance", Self::find_provenance)?;
+        }
+        self.timed("violations", Self::count_violations)?;
+
+        // Edges and value ranges were keyed by full predicate IRIs; label them now that every
+        // predicate is known so clashing local names can be detected
+        let predicates = self
+            .g
+            .edge_weights()
+            .map(|p| &**p)
+            .chain(self.value_ranges.keys().map(|(_, p)| &**p))
+            .chain(self.attributes.values().flatten().map(|(p, _)| &**p));
+        let labels = predicate_labels(predicates, self.label_style);
+        for idx in self.g.edge_indices() {
+            let label = self.interner.intern(&labels[&*self.g[idx]]);
+            let predicate = std::mem::replace(&mut self.g[idx], label);
+            self.edge_predicates.insert(idx, predicate);
+        }
+        self.edge_lookup.clear();
+        for idx in self.derived.iter() {
+            self.g[*idx] = format!("{} (derived)", self.g[*idx]).into();
+        }
+        for (idx, kind) in self.restrictions.iter() {
+            self.g[*idx] = format!("{} ({})", self.g[*idx], kind).into();
+        }
+        self.value_ranges = self
+            .value_ranges
+            .drain()
+            .map(|((class, p), range)| ((class, self.interner.intern(&labels[&*p])), range))
+            .collect();
+        for attributes in self.attributes.values_mut() {
+            *attributes = std::mem::take(attributes)
+                .into_iter()
+                .map(|(p, datatype)| (self.interner.intern(&labels[&*p]), datatype))
+                .collect();
+        }
+
+        if let Some((root, depth)) = self.rollup.clone() {
+            self.roll_up(&root, depth)?;
+        }
+        if self.min_edge_count > 1 {
+            self.drop_rare_edges(self.min_edge_count);
+        }
+        if self.aggregate_edges {
+            self.aggregate_parallel_edges();
+        }
+        if self.prune_isolated {
+            self.prune_isolated_classes();
+        }
+        if let Some((iri, hops)) = self.focus.clone() {
+            self.focus_on(&iri, hops)?;
+        }
+        if let Some((from, to)) = self.path.clone() {
+            self.keep_paths(&from, &to)?;
+        }
+        if let Some((max, rank)) = self.max_nodes {
+            self.keep_top_classes(max, rank);
+        }
+        if let Some(component) = self.component {
+            self.keep_component(component);
+        }
+        if self.depth_colors {
+            self.timed("depth colors", Self::color_by_depth)?;
+        }
+        self.sort_graph();
+        if self.highlight_cycles {
+            self.timed("cycles", Self::find_cycles)?;
+        }
+        tracing::info!(nodes = self.g.node_count(), edges = self.g.edge_count(), "built class graph");
+        if self.g.node_count() == 0 {
+            tracing::debug!("the class graph is empty; no instance pair matched two declared, unfiltered classes");
+        }
+        self.built = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEEDS: &str = "https://brickschema.org/schema/Brick#feeds";
+    const HAS_PART: &str = "https://brickschema.org/schema/Brick#hasPart";
+    const EX_HAS_PART: &str = "urn:ex#hasPart";
+    const EX_HAS_PART_SNAKE: &str = "urn:ex#has_part";
+
+    fn labels(style: LabelStyle) -> HashMap<String, String> {
+        predicate_labels([FEEDS, HAS_PART, EX_HAS_PART, FEEDS], style)
+    }
+
+    #[test]
+    fn predicate_labels_fall_back_to_curies_on_clashes() {
+        let labels = labels(LabelStyle::LocalName);
+        assert_eq!(labels.len(), 3);
+        assert_eq!(labels[FEEDS], "feeds");
+        // both are hasPart: the Brick one gets its CURIE, the other has no prefix and keeps its IRI
+        assert_eq!(labels[HAS_PART], "brick:hasPart");
+        assert_eq!(labels[EX_HAS_PART], EX_HAS_PART);
+
+        // hasPart and has_part only clash once humanized
+        let iris = [HAS_PART, EX_HAS_PART_SNAKE];
+        let local = predicate_labels(iris, LabelStyle::LocalName);
+        assert_eq!((local[HAS_PART].as_str(), local[EX_HAS_PART_SNAKE].as_str()), ("hasPart", "has_part"));
+        let humanized = predicate_labels(iris, LabelStyle::Humanized);
+        assert_eq!(humanized[HAS_PART], "brick:hasPart");
+        assert_eq!(humanized[EX_HAS_PART_SNAKE], EX_HAS_PART_SNAKE);
+    }
+
+    #[test]
+    fn predicate_labels_without_clashes_keep_their_style() {
+        let labels = labels(LabelStyle::Prefixed);
+        assert_eq!(labels[FEEDS], "brick_feeds");
+        assert_eq!(labels[HAS_PART], "brick_hasPart");
+        assert_eq!(labels[EX_HAS_PART], EX_HAS_PART);
+        assert_eq!(predicate_labels([FEEDS], LabelStyle::Curie)[FEEDS], "brick:feeds");
+        assert_eq!(predicate_labels([HAS_PART], LabelStyle::Humanized)[HAS_PART], "has part");
+    }
 }
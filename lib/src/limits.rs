@@ -0,0 +1,454 @@
+//! Timeout and result limit of the extraction queries, so a pathological data graph cannot
+//! hang a build or flood it with rows
+
+use crate::{Error, Instant, Result, Visualizer};
+#[cfg(not(target_family = "wasm"))]
+use oxigraph::model::Term;
+use oxigraph::model::Triple;
+#[cfg(not(target_family = "wasm"))]
+use oxigraph::sparql::Variable;
+use oxigraph::sparql::{EvaluationError, Query, QueryResults, QuerySolutionIter};
+use oxigraph::store::Store;
+use std::rc::Rc;
+#[cfg(not(target_family = "wasm"))]
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// Limits on how long a query may run and how many rows it may return, set on a visualizer by
+/// [`Visualizer::with_query_timeout`] and [`Visualizer::with_max_results`] or used on their
+/// own to run ad-hoc queries, e.g. of a SPARQL endpoint
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryLimits {
+    /// Fail any query that runs longer than this with [`Error::Timeout`]
+    pub timeout: Option<Duration>,
+    /// Fail any query that returns more rows (or triples) than this with
+    /// [`Error::TooManyResults`]
+    pub max_results: Option<usize>,
+}
+
+/// What [`QueryLimits::evaluate`] returns. oxigraph cannot wrap triples in [`QueryResults`]
+/// again once they went through the limits, so CONSTRUCT and DESCRIBE queries come back as
+/// [`LimitedResults::Triples`].
+pub enum LimitedResults {
+    /// The results of a SELECT or ASK query
+    Results(QueryResults),
+    /// The triples of a CONSTRUCT or DESCRIBE query
+    Triples(Box<dyn Iterator<Item = std::result::Result<Triple, EvaluationError>>>),
+}
+
+/// The limit a query went over, carried through oxigraph's solution iterator as the source of
+/// an [`std::io::Error`] and turned back into [`Error::Timeout`] or [`Error::TooManyResults`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LimitExceeded {
+    Timeout(Duration),
+    TooManyResults(usize),
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitExceeded::Timeout(timeout) => write!(f, "query timed out after {:?}", timeout),
+            LimitExceeded::TooManyResults(max) => write!(f, "query returned more than {} results", max),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl From<LimitExceeded> for EvaluationError {
+    fn from(e: LimitExceeded) -> Self {
+        EvaluationError::Io(std::io::Error::other(e))
+    }
+}
+
+impl From<LimitExceeded> for Error {
+    fn from(e: LimitExceeded) -> Self {
+        match e {
+            LimitExceeded::Timeout(timeout) => Error::Timeout(timeout),
+            LimitExceeded::TooManyResults(max) => Error::TooManyResults(max),
+        }
+    }
+}
+
+/// The [`LimitExceeded`] an evaluation error carries, if it is one
+pub(crate) fn limit_exceeded(e: &EvaluationError) -> Option<LimitExceeded> {
+    match e {
+        EvaluationError::Io(io) => io.get_ref()?.downcast_ref().copied(),
+        _ => None,
+    }
+}
+
+impl Visualizer<'_> {
+    /// Fail any query that runs longer than `timeout` with [`Error::Timeout`] instead of
+    /// waiting for it. On native targets the query is evaluated on a worker thread so even a
+    /// query stuck before its first row is given up on; on wasm the time is only checked
+    /// between rows. A worker given up on keeps running until its next row, so at most
+    /// [`MAX_WORKERS`] run at once and a query waits for a free one within its timeout.
+    pub fn with_query_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail any query that returns more than `max` rows with [`Error::TooManyResults`]
+    pub fn with_max_results(mut self, max: usize) -> Self {
+        self.limits.max_results = Some(max);
+        self
+    }
+
+    /// Evaluates `query` against the store within the configured limits
+    pub(crate) fn evaluate(&self, query: Query) -> Result<QueryResults> {
+        match self.limits.evaluate(&self.store, query)? {
+            LimitedResults::Results(results) => Ok(results),
+            LimitedResults::Triples(_) => {
+                Err(EvaluationError::Io(std::io::Error::other("the visualizer only runs SELECT and ASK queries")).into())
+            }
+        }
+    }
+}
+
+impl QueryLimits {
+    /// Evaluates `query` against `store`, failing with [`Error::Timeout`] or
+    /// [`Error::TooManyResults`], either right away or from the row that went over a limit.
+    /// With a timeout on native targets the query runs on a worker thread, see
+    /// [`Visualizer::with_query_timeout`].
+    pub fn evaluate(&self, store: &Store, query: Query) -> Result<LimitedResults> {
+        let limits = *self;
+        if limits.timeout.is_none() && limits.max_results.is_none() {
+            return Ok(match store.query(query)? {
+                QueryResults::Graph(triples) => LimitedResults::Triples(Box::new(triples)),
+                results => LimitedResults::Results(results),
+            });
+        }
+        let deadline = limits.timeout.map(|timeout| (Instant::now() + timeout, timeout));
+        #[cfg(not(target_family = "wasm"))]
+        if let Some((deadline, timeout)) = deadline {
+            return on_worker(store.clone(), query, deadline, timeout, limits.max_results);
+        }
+        let mut rows = 0;
+        let mut next_row = move || -> std::result::Result<(), LimitExceeded> {
+            if let Some((deadline, timeout)) = deadline {
+                if Instant::now() > deadline {
+                    return Err(LimitExceeded::Timeout(timeout));
+                }
+            }
+            rows += 1;
+            match limits.max_results {
+                Some(max) if rows > max => Err(LimitExceeded::TooManyResults(max)),
+                _ => Ok(()),
+            }
+        };
+        match store.query(query)? {
+            QueryResults::Solutions(solutions) => {
+                let variables = Rc::new(solutions.variables().to_vec());
+                let columns = Rc::clone(&variables);
+                let rows = solutions.map(move |row| {
+                    next_row()?;
+                    let row = row?;
+                    Ok(columns.iter().map(|variable| row.get(variable).cloned()).collect())
+                });
+                Ok(LimitedResults::Results(QueryResults::Solutions(QuerySolutionIter::new(variables, rows))))
+            }
+            QueryResults::Graph(triples) => Ok(LimitedResults::Triples(Box::new(triples.map(move |triple| {
+                next_row()?;
+                triple
+            })))),
+            results => Ok(LimitedResults::Results(results)),
+        }
+    }
+}
+
+/// Most query workers running at once, counting the ones still finishing a query that timed out
+#[cfg(not(target_family = "wasm"))]
+pub const MAX_WORKERS: usize = 8;
+
+#[cfg(not(target_family = "wasm"))]
+static WORKERS: Workers = Workers::new(MAX_WORKERS);
+
+/// Counts the running query workers, so queries that keep timing out cannot pile up threads
+#[cfg(not(target_family = "wasm"))]
+struct Workers {
+    running: Mutex<usize>,
+    finished: Condvar,
+    max: usize,
+}
+
+/// A running worker, freed for the next query when dropped
+#[cfg(not(target_family = "wasm"))]
+struct Slot(&'static Workers);
+
+#[cfg(not(target_family = "wasm"))]
+impl Workers {
+    const fn new(max: usize) -> Self {
+        Workers {
+            running: Mutex::new(0),
+            finished: Condvar::new(),
+            max,
+        }
+    }
+
+    /// A slot for one more worker, waiting for one to finish if `max` are running, or `None`
+    /// if none did by `deadline`
+    fn start(&'static self, deadline: Instant) -> Option<Slot> {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        while *running >= self.max {
+            let left = deadline.checked_duration_since(Instant::now())?;
+            running = self.finished.wait_timeout(running, left).unwrap_or_else(|e| e.into_inner()).0;
+        }
+        *running += 1;
+        Some(Slot(self))
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Drop for Slot {
+    fn drop(&mut self) {
+        *self.0.running.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+        self.0.finished.notify_one();
+    }
+}
+
+/// What the worker thread evaluating a query sends back
+#[cfg(not(target_family = "wasm"))]
+enum Message {
+    Variables(Vec<Variable>),
+    Row(Vec<Option<Term>>),
+    Boolean(bool),
+    /// The query is a CONSTRUCT or DESCRIBE, whose triples follow
+    Triples,
+    Triple(Triple),
+    Failed(EvaluationError),
+    /// Every row was sent
+    Done,
+}
+
+/// Evaluates `query` on a worker thread and streams its rows back until `deadline`. A query
+/// given up on keeps its worker busy until its next row, which it then fails to send and
+/// stops; until then it holds one of the [`MAX_WORKERS`] slots.
+#[cfg(not(target_family = "wasm"))]
+fn on_worker(
+    store: Store,
+    query: Query,
+    deadline: Instant,
+    timeout: Duration,
+    max_results: Option<usize>,
+) -> Result<LimitedResults> {
+    let slot = WORKERS.start(deadline).ok_or(Error::Timeout(timeout))?;
+    let (sender, receiver) = std::sync::mpsc::sync_channel(256);
+    std::thread::spawn(move || {
+        let _slot = slot;
+        let send = |message| sender.send(message).is_ok();
+        match store.query(query) {
+            Ok(QueryResults::Solutions(solutions)) => {
+                let variables = solutions.variables().to_vec();
+                if !send(Message::Variables(variables.clone())) {
+                    return;
+                }
+                for row in solutions {
+                    let message = match row {
+                        Ok(row) => Message::Row(variables.iter().map(|variable| row.get(variable).cloned()).collect()),
+                        Err(e) => Message::Failed(e),
+                    };
+                    if !send(message) {
+                        return;
+                    }
+                }
+            }
+            Ok(QueryResults::Graph(triples)) => {
+                if !send(Message::Triples) {
+                    return;
+                }
+                for triple in triples {
+                    if !send(triple.map_or_else(Message::Failed, Message::Triple)) {
+                        return;
+                    }
+                }
+            }
+            Ok(QueryResults::Boolean(boolean)) => {
+                send(Message::Boolean(boolean));
+                return;
+            }
+            Err(e) => {
+                send(Message::Failed(e));
+                return;
+            }
+        }
+        send(Message::Done);
+    });
+
+    let receive = move || -> std::result::Result<Option<Message>, LimitExceeded> {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match receiver.recv_timeout(left) {
+            Ok(message) => Ok(Some(message)),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => Err(LimitExceeded::Timeout(timeout)),
+            // the worker only hangs up without a message if it panicked
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Ok(None),
+        }
+    };
+    match receive()? {
+        Some(Message::Variables(variables)) => {
+            let row = |message| match message {
+                Message::Row(row) => Some(row),
+                _ => None,
+            };
+            let rows = streamed(receive, max_results, row);
+            Ok(LimitedResults::Results(QueryResults::Solutions(QuerySolutionIter::new(Rc::new(variables), rows))))
+        }
+        Some(Message::Triples) => {
+            let triple = |message| match message {
+                Message::Triple(triple) => Some(triple),
+                _ => None,
+            };
+            Ok(LimitedResults::Triples(Box::new(streamed(receive, max_results, triple))))
+        }
+        Some(Message::Boolean(boolean)) => Ok(LimitedResults::Results(QueryResults::Boolean(boolean))),
+        Some(Message::Failed(e)) => Err(e.into()),
+        Some(Message::Row(_) | Message::Triple(_) | Message::Done) => {
+            Err(worker_failure("the query worker sent rows before its variables").into())
+        }
+        None => Err(worker_failure("the query worker stopped without a result").into()),
+    }
+}
+
+/// The rows a worker streams after its first message, each taken out of its message by
+/// `unpack`, until it is done or over `max_results`
+#[cfg(not(target_family = "wasm"))]
+fn streamed<T: 'static>(
+    mut receive: impl FnMut() -> std::result::Result<Option<Message>, LimitExceeded> + 'static,
+    max_results: Option<usize>,
+    unpack: fn(Message) -> Option<T>,
+) -> impl Iterator<Item = std::result::Result<T, EvaluationError>> + 'static {
+    let mut rows = 0;
+    std::iter::from_fn(move || match receive() {
+        Ok(Some(Message::Done)) => None,
+        Ok(Some(Message::Failed(e))) => Some(Err(e)),
+        Ok(Some(message)) => match unpack(message) {
+            Some(row) => {
+                rows += 1;
+                match max_results {
+                    Some(max) if rows > max => Some(Err(LimitExceeded::TooManyResults(max).into())),
+                    _ => Some(Ok(row)),
+                }
+            }
+            None => Some(Err(worker_failure("the query worker sent a second result"))),
+        },
+        Ok(None) => Some(Err(worker_failure("the query worker stopped before its last row"))),
+        Err(e) => Some(Err(e.into())),
+    })
+}
+
+/// The error of a query whose worker thread failed to deliver its results
+#[cfg(not(target_family = "wasm"))]
+fn worker_failure(message: &str) -> EvaluationError {
+    EvaluationError::Io(std::io::Error::other(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxigraph::io::GraphFormat;
+    use std::collections::HashMap;
+
+    /// A visualizer over `count` triples, whose three-way join below has `count`³ rows
+    fn visualizer(count: usize) -> Visualizer<'static> {
+        let data: String = (0..count)
+            .map(|i| format!("<urn:s{0}> <urn:p> <urn:o{0}> .\n", i))
+            .collect();
+        let mut v = Visualizer::new(|_: &str, _: &str, _: &str| true, HashMap::new()).unwrap();
+        v.add_data(data.as_bytes(), GraphFormat::NTriples).unwrap();
+        v
+    }
+
+    /// Runs `q` and reads every row
+    fn rows(v: &Visualizer, q: &str) -> Result<usize> {
+        let QueryResults::Solutions(solutions) = v.query(q)? else {
+            panic!("not a SELECT query");
+        };
+        let mut rows = 0;
+        for row in solutions {
+            row?;
+            rows += 1;
+        }
+        Ok(rows)
+    }
+
+    const JOIN: &str = "SELECT * WHERE { ?a ?p ?b . ?c ?q ?d . ?e ?r ?f }";
+
+    #[test]
+    fn slow_query_times_out() {
+        let timeout = Duration::from_millis(50);
+        let v = visualizer(300).with_query_timeout(timeout);
+        assert!(matches!(rows(&v, JOIN), Err(Error::Timeout(t)) if t == timeout));
+    }
+
+    #[test]
+    fn huge_query_goes_over_max_results() {
+        let v = visualizer(20).with_max_results(100);
+        assert!(matches!(rows(&v, JOIN), Err(Error::TooManyResults(100))));
+        // the worker thread enforces the limit too
+        let v = visualizer(20).with_max_results(100).with_query_timeout(Duration::from_secs(60));
+        assert!(matches!(rows(&v, JOIN), Err(Error::TooManyResults(100))));
+    }
+
+    #[test]
+    fn queries_within_the_limits_succeed() {
+        let v = visualizer(5).with_max_results(125).with_query_timeout(Duration::from_secs(60));
+        assert_eq!(rows(&v, JOIN).unwrap(), 125);
+        assert!(matches!(v.query("ASK { ?s ?p ?o }").unwrap(), QueryResults::Boolean(true)));
+    }
+
+    #[test]
+    fn construct_queries_are_limited_too() {
+        let construct = "CONSTRUCT { ?a <urn:q> ?f } WHERE { ?a ?p ?b . ?c ?q ?d . ?e ?r ?f }";
+        let triples = |limits: QueryLimits| -> Result<usize> {
+            let v = visualizer(5);
+            let LimitedResults::Triples(triples) = limits.evaluate(v.store(), Query::parse(construct, None).unwrap())? else {
+                panic!("not a CONSTRUCT query");
+            };
+            let mut count = 0;
+            for triple in triples {
+                triple?;
+                count += 1;
+            }
+            Ok(count)
+        };
+        assert_eq!(triples(QueryLimits::default()).unwrap(), 125);
+        let limits = QueryLimits {
+            timeout: Some(Duration::from_secs(60)),
+            max_results: Some(10),
+        };
+        assert!(matches!(triples(limits), Err(Error::TooManyResults(10))));
+        let limits = QueryLimits {
+            timeout: None,
+            max_results: Some(10),
+        };
+        assert!(matches!(triples(limits), Err(Error::TooManyResults(10))));
+    }
+
+    #[test]
+    fn workers_past_the_max_wait_for_a_slot() {
+        static POOL: Workers = Workers::new(1);
+        let soon = || Instant::now() + Duration::from_millis(50);
+        let slot = POOL.start(soon()).unwrap();
+        assert!(POOL.start(soon()).is_none());
+        let freed = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            drop(slot);
+        });
+        assert!(POOL.start(Instant::now() + Duration::from_secs(60)).is_some());
+        freed.join().unwrap();
+        assert_eq!(*POOL.running.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn evaluation_errors_carry_the_limit() {
+        let timeout = Duration::from_secs(3);
+        let e = EvaluationError::from(LimitExceeded::Timeout(timeout));
+        assert!(matches!(limit_exceeded(&e), Some(LimitExceeded::Timeout(t)) if t == timeout));
+        assert!(matches!(Error::from(e), Error::Timeout(t) if t == timeout));
+        let e = EvaluationError::from(LimitExceeded::TooManyResults(7));
+        assert!(matches!(Error::from(e), Error::TooManyResults(7)));
+        let e = EvaluationError::Io(std::io::Error::other("disk full"));
+        assert!(limit_exceeded(&e).is_none());
+        assert!(matches!(Error::from(e), Error::Query(_)));
+    }
+}
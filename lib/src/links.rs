@@ -0,0 +1,24 @@
+use crate::{curie, split_iri, Visualizer};
+
+impl Visualizer<'_> {
+    /// Make every class node a link to the URL `template` gives for it: `{iri}` stands for
+    /// the class IRI, `{local}` for its local name and `{curie}` for its CURIE, e.g.
+    /// `https://ontology.brickschema.org/brick/{local}.html`. Emitted as DOT `URL`, d2 `link`
+    /// and SVG anchors in the builtin engine.
+    pub fn with_node_links(mut self, template: impl Into<String>) -> Self {
+        self.link_template = Some(template.into());
+        self
+    }
+
+    /// The URL a class label links to, when [`Visualizer::with_node_links`] is set
+    pub fn node_link(&self, class: &str) -> Option<String> {
+        let template = self.link_template.as_ref()?;
+        let iri = self.node_iris.get(class)?;
+        Some(
+            template
+                .replace("{iri}", iri)
+                .replace("{local}", split_iri(iri).1)
+                .replace("{curie}", &curie(iri)),
+        )
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{Result, Visualizer};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// One file (or stream) produced by a run
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    /// Where the artifact was written; `-` for stdout
+    pub path: String,
+    /// Output format, e.g. `d2` or `dot`
+    pub format: String,
+    /// Hex encoded SHA-256 of the artifact contents
+    pub sha256: String,
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+/// Machine-readable list of every artifact produced by a run, so build systems can track and
+/// cache diagram outputs
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Manifest {
+    pub artifacts: Vec<Artifact>,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an artifact rendered from the current graph of `viz`
+    pub fn record(
+        &mut self,
+        path: impl Into<String>,
+        format: impl Into<String>,
+        contents: &[u8],
+        viz: &Visualizer,
+    ) {
+        self.artifacts.push(Artifact {
+            path: path.into(),
+            format: format.into(),
+            sha256: format!("{:x}", Sha256::digest(contents)),
+            nodes: viz.graph().node_count(),
+            edges: viz.graph().edge_count(),
+        });
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| crate::Error::Render(e.to_string()))
+    }
+}
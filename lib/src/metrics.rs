@@ -0,0 +1,176 @@
+//! Degree and betweenness centrality of the classes of the class graph, to spot hub classes
+//! and bottlenecks
+
+use crate::Visualizer;
+use petgraph::graph::NodeIndex;
+use petgraph::Direction;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Centrality of one class in the class graph
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ClassMetrics {
+    /// Edges into the class
+    pub in_degree: usize,
+    /// Edges out of the class
+    pub out_degree: usize,
+    /// Share of the shortest directed paths between two other classes that go through this
+    /// one, from 0 to 1
+    pub betweenness: f64,
+}
+
+impl ClassMetrics {
+    pub fn degree(&self) -> usize {
+        self.in_degree + self.out_degree
+    }
+
+    fn describe(&self) -> String {
+        format!("degree {}, betweenness {:.2}", self.degree(), self.betweenness)
+    }
+}
+
+impl Visualizer<'_> {
+    /// Write the degree and betweenness of every class into its node label
+    pub fn with_metric_annotations(mut self, metric_annotations: bool) -> Self {
+        self.metric_annotations = metric_annotations;
+        self
+    }
+
+    /// [`ClassMetrics`] of every class of the class graph, by label
+    pub fn class_metrics(&self) -> HashMap<String, ClassMetrics> {
+        let betweenness = self.betweenness();
+        self.g
+            .node_indices()
+            .map(|idx| {
+                let metrics = ClassMetrics {
+                    in_degree: self.g.edges_directed(idx, Direction::Incoming).count(),
+                    out_degree: self.g.edges_directed(idx, Direction::Outgoing).count(),
+                    betweenness: betweenness[idx.index()],
+                };
+                (self.g[idx].to_string(), metrics)
+            })
+            .collect()
+    }
+
+    /// Annotation lines of the classes, when [`Visualizer::with_metric_annotations`] is set
+    pub(crate) fn metric_lines(&self) -> HashMap<String, String> {
+        if !self.metric_annotations {
+            return HashMap::new();
+        }
+        self.class_metrics()
+            .into_iter()
+            .map(|(class, metrics)| (class, metrics.describe()))
+            .collect()
+    }
+
+    /// Brandes' algorithm over the unweighted directed class graph, ignoring parallel edges and
+    /// self loops, normalized by the number of ordered pairs of other classes
+    fn betweenness(&self) -> Vec<f64> {
+        let n = self.g.node_count();
+        let mut centrality = vec![0.0; n];
+        let successors: Vec<Vec<usize>> = self
+            .g
+            .node_indices()
+            .map(|idx| {
+                let mut next: Vec<usize> = self
+                    .g
+                    .neighbors_directed(idx, Direction::Outgoing)
+                    .map(NodeIndex::index)
+                    .filter(|next| *next != idx.index())
+                    .collect();
+                next.sort_unstable();
+                next.dedup();
+                next
+            })
+            .collect();
+        for source in 0..n {
+            let mut order = Vec::with_capacity(n);
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+            let mut paths = vec![0.0; n];
+            let mut distance: Vec<Option<usize>> = vec![None; n];
+            paths[source] = 1.0;
+            distance[source] = Some(0);
+            let mut queue = VecDeque::from([source]);
+            while let Some(node) = queue.pop_front() {
+                order.push(node);
+                let d = distance[node].unwrap_or_default();
+                for &next in successors[node].iter() {
+                    if distance[next].is_none() {
+                        distance[next] = Some(d + 1);
+                        queue.push_back(next);
+                    }
+                    if distance[next] == Some(d + 1) {
+                        paths[next] += paths[node];
+                        predecessors[next].push(node);
+                    }
+                }
+            }
+            let mut dependency = vec![0.0; n];
+            for &node in order.iter().rev() {
+                for &previous in predecessors[node].iter() {
+                    dependency[previous] += paths[previous] / paths[node] * (1.0 + dependency[node]);
+                }
+                if node != source {
+                    centrality[node] += dependency[node];
+                }
+            }
+        }
+        if n > 2 {
+            let pairs = ((n - 1) * (n - 2)) as f64;
+            for value in centrality.iter_mut() {
+                *value /= pairs;
+            }
+        }
+        centrality
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::visualizer;
+
+    /// AHU -> VAV -> zone and VAV -> sensor, with a parallel AHU -> VAV edge and a VAV self
+    /// loop that do not change any shortest path
+    fn built(metric_annotations: bool) -> Visualizer<'static> {
+        let ontology = "
+            brick:AHU a owl:Class .
+            brick:VAV a owl:Class .
+            brick:HVAC_Zone a owl:Class .
+            brick:Sensor a owl:Class .
+        ";
+        let data = "
+            ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 ; brick:hasPart ex:vav1 .
+            ex:vav1 a brick:VAV ; brick:feeds ex:zone1, ex:vav2 ; brick:hasPoint ex:sensor1 .
+            ex:vav2 a brick:VAV .
+            ex:zone1 a brick:HVAC_Zone .
+            ex:sensor1 a brick:Sensor .
+        ";
+        let mut v = visualizer(ontology, data).with_metric_annotations(metric_annotations);
+        v.build_graph().unwrap();
+        v
+    }
+
+    #[test]
+    fn degree_counts_every_edge_and_betweenness_the_shortest_paths() {
+        let metrics = built(false).class_metrics();
+        let degrees = |class: &str| (metrics[class].in_degree, metrics[class].out_degree);
+        assert_eq!(degrees("brick_AHU"), (0, 2));
+        assert_eq!(degrees("brick_VAV"), (3, 3));
+        assert_eq!(degrees("brick_HVAC_Zone"), (1, 0));
+        assert_eq!(metrics["brick_VAV"].degree(), 6);
+        // AHU to zone and AHU to sensor, out of the 3 * 2 ordered pairs of other classes
+        assert!((metrics["brick_VAV"].betweenness - 2.0 / 6.0).abs() < 1e-9);
+        for class in ["brick_AHU", "brick_HVAC_Zone", "brick_Sensor"] {
+            assert_eq!(metrics[class].betweenness, 0.0);
+        }
+    }
+
+    #[test]
+    fn metric_lines_only_with_annotations() {
+        assert!(built(false).metric_lines().is_empty());
+        let lines = built(true).metric_lines();
+        assert_eq!(lines["brick_VAV"], "degree 6, betweenness 0.33");
+        assert_eq!(lines["brick_Sensor"], "degree 1, betweenness 0.00");
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{resolve_namespace, Visualizer};
+use std::str::FromStr;
+
+/// Which classes an instance with several `rdf:type`s is summarized under
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MultiTypePolicy {
+    /// Every type, so edges are drawn for every combination of the types of both ends
+    #[default]
+    All,
+    /// Only the types that are not a superclass (through `rdfs:subClassOf`) of another type of
+    /// the same instance
+    MostSpecific,
+    /// Only the types in the first of these namespaces (IRI prefixes) that any type of the
+    /// instance is in; every type when none is
+    PreferNamespace(Vec<String>),
+}
+
+impl FromStr for MultiTypePolicy {
+    type Err = String;
+
+    /// Parses `all`, `most-specific` or `prefer:NS[,NS...]`, where each namespace is an IRI
+    /// prefix or one of the known prefixes
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(MultiTypePolicy::All),
+            "most-specific" => Ok(MultiTypePolicy::MostSpecific),
+            _ => match s.strip_prefix("prefer:") {
+                Some(namespaces) if !namespaces.is_empty() => Ok(MultiTypePolicy::PreferNamespace(
+                    namespaces.split(',').map(resolve_namespace).collect(),
+                )),
+                _ => Err(format!(
+                    "unknown multi-type policy '{}', expected all, most-specific or prefer:NS[,NS...]",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl Visualizer<'_> {
+    /// Which classes an instance with several types is summarized under; every type by default
+    pub fn with_multi_type_policy(mut self, policy: MultiTypePolicy) -> Self {
+        self.multi_type = policy;
+        self
+    }
+
+    /// SPARQL group pattern restricting the type `?{class}` of the instance `?{instance}`
+    /// according to the multi-type policy; empty for [`MultiTypePolicy::All`]. Needs the `rdf`
+    /// and `rdfs` prefixes.
+    pub(crate) fn type_policy(&self, instance: &str, class: &str) -> String {
+        let other = format!("?{}_{}_other", instance, class);
+        let declared = self.class_pattern(&other[1..]);
+        match &self.multi_type {
+            MultiTypePolicy::All => String::new(),
+            MultiTypePolicy::MostSpecific => format!(
+                "FILTER NOT EXISTS {{
+                     ?{instance} rdf:type {other} .
+                     {declared}
+                     {other} rdfs:subClassOf+ ?{class} .
+                     FILTER({other} != ?{class})
+                 }}"
+            ),
+            MultiTypePolicy::PreferNamespace(namespaces) => {
+                // rank of a type: the index of the first preferred namespace it is in
+                let rank = |var: &str| {
+                    namespaces.iter().enumerate().rev().fold(
+                        namespaces.len().to_string(),
+                        |rest, (idx, ns)| {
+                            format!("IF(STRSTARTS(STR({}), \"{}\"), {}, {})", var, ns, idx, rest)
+                        },
+                    )
+                };
+                format!(
+                    "FILTER NOT EXISTS {{
+                         ?{instance} rdf:type {other} .
+                         {declared}
+                         FILTER({} < {})
+                     }}",
+                    rank(&other),
+                    rank(&format!("?{}", class))
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{edge, edges, visualizer};
+
+    const ONTOLOGY: &str = "
+        brick:Equipment a owl:Class .
+        brick:AHU a owl:Class ; rdfs:subClassOf brick:Equipment .
+        brick:VAV a owl:Class .
+        ex:Box a owl:Class .
+    ";
+    /// An AHU also typed as its superclass, feeding a VAV also typed in another namespace
+    const DATA: &str = "
+        ex:ahu1 a brick:AHU, brick:Equipment ; brick:feeds ex:vav1 .
+        ex:vav1 a brick:VAV, ex:Box .
+    ";
+
+    fn edges_under(policy: MultiTypePolicy) -> Vec<(String, String, String)> {
+        let mut v = visualizer(ONTOLOGY, DATA).with_multi_type_policy(policy);
+        v.build_graph().unwrap();
+        edges(&v)
+    }
+
+    #[test]
+    fn type_policy_picks_the_types_instances_are_summarized_under() {
+        assert_eq!(edges_under(MultiTypePolicy::All).len(), 4);
+        // the superclass is dropped, both types of the VAV from another namespace are kept
+        assert_eq!(edges_under(MultiTypePolicy::MostSpecific), vec![
+            edge("brick_AHU", "brick_feeds", "brick_VAV"),
+            edge("brick_AHU", "brick_feeds", "urn:ex#Box"),
+        ]);
+        // the Brick types win over the urn:ex# one, the AHU keeping both of its Brick types
+        let brick = MultiTypePolicy::from_str("prefer:brick").unwrap();
+        assert_eq!(edges_under(brick), vec![
+            edge("brick_AHU", "brick_feeds", "brick_VAV"),
+            edge("brick_Equipment", "brick_feeds", "brick_VAV"),
+        ]);
+        let ex = MultiTypePolicy::PreferNamespace(vec!["urn:ex#".to_owned()]);
+        assert_eq!(edges_under(ex), vec![
+            edge("brick_AHU", "brick_feeds", "urn:ex#Box"),
+            edge("brick_Equipment", "brick_feeds", "urn:ex#Box"),
+        ]);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{Instant, Result, Visualizer};
+use oxigraph::io::DatasetFormat;
+use oxigraph::model::{GraphName, NamedNode, NamedOrBlankNode};
+use std::io::BufRead;
+
+/// Prefix of the named graphs the visualizer loads side inputs (diffs, validation reports) into
+const INTERNAL_GRAPHS: &str = "urn:rdf_class_viz:";
+
+/// Which graphs of a dataset the class graph summarizes. Ontologies and plain RDF graphs are
+/// loaded into the default graph, which is always included.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum GraphSelection {
+    /// Only the default graph
+    #[default]
+    Default,
+    /// The default graph and every named graph loaded with [`Visualizer::add_dataset`]
+    All,
+    /// The default graph and these named graphs
+    Named(Vec<NamedNode>),
+}
+
+impl Visualizer<'_> {
+    /// Loads an N-Quads or TriG dataset, keeping each named graph separate, so the graphs to
+    /// summarize can be picked with [`Visualizer::with_graph_selection`]
+    pub fn add_dataset(&mut self, content: impl BufRead, format: DatasetFormat) -> Result<()> {
+        let (stage, start) = ("load dataset", Instant::now());
+        let _span = tracing::info_span!("stage", stage).entered();
+        self.report(|sink| sink.stage_started(stage));
+        self.bulk_loader(stage, start)?.load_dataset(content, format, None)?;
+        self.triples = self.store.len()?;
+        let elapsed = start.elapsed();
+        tracing::info!(triples = self.triples, ?elapsed, "loaded");
+        self.report(|sink| sink.stage_finished(stage, elapsed));
+        self.load_stages.push((stage, elapsed));
+        Ok(())
+    }
+
+    /// The named graphs loaded with [`Visualizer::add_dataset`]
+    pub fn named_graphs(&self) -> Result<Vec<NamedNode>> {
+        let mut graphs = Vec::new();
+        for graph in self.store.named_graphs() {
+            if let NamedOrBlankNode::NamedNode(graph) = graph? {
+                if !graph.as_str().starts_with(INTERNAL_GRAPHS) {
+                    graphs.push(graph);
+                }
+            }
+        }
+        graphs.sort();
+        Ok(graphs)
+    }
+
+    /// Which graphs of the loaded datasets to summarize; only the default graph by default
+    pub fn with_graph_selection(mut self, selection: GraphSelection) -> Self {
+        self.graph_selection = selection;
+        self
+    }
+
+    /// The default graph of every extraction query for the graph selection, or `None` to
+    /// leave the store's default graph
+    pub(crate) fn selected_graphs(&self) -> Result<Option<Vec<GraphName>>> {
+        let named = match &self.graph_selection {
+            GraphSelection::Default => return Ok(None),
+            GraphSelection::All => self.named_graphs()?,
+            GraphSelection::Named(graphs) => graphs.clone(),
+        };
+        let mut graphs = vec![GraphName::DefaultGraph];
+        graphs.extend(named.into_iter().map(GraphName::from));
+        Ok(Some(graphs))
+    }
+}
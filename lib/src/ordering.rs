@@ -0,0 +1,41 @@
+use crate::Visualizer;
+use std::collections::HashMap;
+
+impl Visualizer<'_> {
+    /// Write nodes and edges sorted by label so repeated runs produce identical files; on by
+    /// default, turn it off to skip the sorting on very large graphs
+    pub fn with_sorted_output(mut self, sorted_output: bool) -> Self {
+        self.sorted_output = sorted_output;
+        self
+    }
+
+    /// Renumbers the class graph so nodes come in label order and edges in (source, target,
+    /// label, predicate) order, the order every serializer walks them in
+    pub(crate) fn sort_graph(&mut self) {
+        if !self.sorted_output {
+            return;
+        }
+        let mut node_order: Vec<_> = self.g.node_indices().collect();
+        node_order.sort_by(|a, b| self.g[*a].cmp(&self.g[*b]));
+        let mut edge_order: Vec<_> = self.g.edge_indices().collect();
+        edge_order.sort_by_cached_key(|idx| {
+            let (source, target) = self.g.edge_endpoints(*idx).expect("edges of the graph");
+            (
+                &self.g[source],
+                &self.g[target],
+                &self.g[*idx],
+                self.edge_predicates.get(idx),
+            )
+        });
+        self.rebuild(node_order, edge_order);
+    }
+
+    /// The entries of a per-class map, sorted by class label unless sorted output is disabled
+    pub(crate) fn in_order<'m, K: Ord, V>(&self, map: &'m HashMap<K, V>) -> Vec<(&'m K, &'m V)> {
+        let mut entries: Vec<_> = map.iter().collect();
+        if self.sorted_output {
+            entries.sort_by_key(|(key, _)| *key);
+        }
+        entries
+    }
+}
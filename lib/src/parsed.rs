@@ -0,0 +1,69 @@
+//! Inputs parsed ahead of loading, so several ontologies can be parsed on parallel threads and
+//! then fed to the bulk loader one after the other
+
+use crate::provenance::ONTOLOGY_GRAPHS;
+use crate::{Instant, Result, Visualizer};
+use oxigraph::io::{GraphFormat, GraphParser};
+use oxigraph::model::{GraphName, NamedNode, Triple};
+use std::io::BufRead;
+
+/// The triples of one RDF graph, parsed but not loaded yet. Parsing does not need a
+/// [`Visualizer`], so it can run on any thread; see [`Visualizer::add_parsed_ontology`].
+#[derive(Debug, Clone, Default)]
+pub struct ParsedGraph {
+    triples: Vec<Triple>,
+}
+
+impl ParsedGraph {
+    /// Parses a whole graph in `format`, failing with [`crate::Error::Parse`] if it is not
+    /// valid RDF
+    pub fn parse(content: impl BufRead, format: GraphFormat) -> Result<Self> {
+        let triples = GraphParser::from_format(format)
+            .read_triples(content)?
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(ParsedGraph { triples })
+    }
+
+    /// Number of triples in the graph
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+}
+
+impl Visualizer<'_> {
+    /// Loads an ontology parsed with [`ParsedGraph::parse`], like [`Visualizer::add_ontology`]
+    pub fn add_parsed_ontology(&mut self, ontology: &ParsedGraph) -> Result<()> {
+        self.load_parsed("load ontology", ontology, GraphName::DefaultGraph)
+    }
+
+    /// Loads an ontology parsed with [`ParsedGraph::parse`], like
+    /// [`Visualizer::add_ontology_from`]
+    pub fn add_parsed_ontology_from(&mut self, source: impl Into<String>, ontology: &ParsedGraph) -> Result<()> {
+        self.load_parsed("load ontology", ontology, GraphName::DefaultGraph)?;
+        let graph = NamedNode::new_unchecked(format!("{}{}", ONTOLOGY_GRAPHS, self.sources.len()));
+        self.load_parsed("load ontology provenance", ontology, graph.into())?;
+        self.sources.push(source.into());
+        Ok(())
+    }
+
+    fn load_parsed(&mut self, stage: &'static str, parsed: &ParsedGraph, graph: GraphName) -> Result<()> {
+        let start = Instant::now();
+        let _span = tracing::info_span!("stage", stage).entered();
+        self.report(|sink| sink.stage_started(stage));
+        let quads = parsed.triples.iter().map(|triple| triple.clone().in_graph(graph.clone()));
+        #[cfg(not(target_family = "wasm"))]
+        self.bulk_loader(stage, start)?.load_quads(quads)?;
+        #[cfg(target_family = "wasm")]
+        self.bulk_loader(stage, start)?.extend(quads)?;
+        self.triples = self.store.len()?;
+        let elapsed = start.elapsed();
+        tracing::info!(triples = self.triples, ?elapsed, "loaded");
+        self.report(|sink| sink.stage_finished(stage, elapsed));
+        self.load_stages.push((stage, elapsed));
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+//! Keeping only the shortest relationship paths between two classes, answering questions like
+//! "how does an AHU relate to a Zone in this model?"
+
+use crate::{expand_curie, Error, Result, Visualizer};
+use petgraph::graph::NodeIndex;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+impl Visualizer<'_> {
+    /// Keep only the classes and edges on the shortest paths between the classes `from` and
+    /// `to`, given as IRIs or CURIEs of known prefixes (`brick:AHU`), following edges in either
+    /// direction. Applied after the other filters and [`Visualizer::with_focus`].
+    pub fn with_path(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.path = Some((expand_curie(&from.into()), expand_curie(&to.into())));
+        self
+    }
+
+    /// Keeps every class and edge on a shortest undirected path from `from` to `to`
+    pub(crate) fn keep_paths(&mut self, from: &str, to: &str) -> Result<()> {
+        let start = self.class_node(from)?;
+        let end = self.class_node(to)?;
+        let from_start = self.distances(start);
+        let from_end = self.distances(end);
+        let length = *from_start.get(&end).ok_or_else(|| Error::NoPath(from.to_owned(), to.to_owned()))?;
+
+        // on a shortest path, the distances from both ends add up to its length
+        let on_path = |idx: &NodeIndex| match (from_start.get(idx), from_end.get(idx)) {
+            (Some(a), Some(b)) => a + b == length,
+            _ => false,
+        };
+        let keep: HashSet<Arc<str>> = self.g.node_indices().filter(on_path).map(|idx| self.g[idx].clone()).collect();
+        let edges = self
+            .g
+            .edge_indices()
+            .filter(|idx| {
+                let (a, b) = self.g.edge_endpoints(*idx).expect("edges of the graph");
+                let step = |a: NodeIndex, b: NodeIndex| match (from_start.get(&a), from_end.get(&b)) {
+                    (Some(x), Some(y)) => x + 1 + y == length,
+                    _ => false,
+                };
+                step(a, b) || step(b, a)
+            })
+            .collect();
+        tracing::debug!(length, classes = keep.len(), "kept shortest paths");
+        self.retain(&keep, &edges);
+        Ok(())
+    }
+
+    fn class_node(&self, iri: &str) -> Result<NodeIndex> {
+        self.node_iris
+            .iter()
+            .find(|(_, node_iri)| ***node_iri == *iri)
+            .and_then(|(label, _)| self.nodes.get(label).copied())
+            .ok_or_else(|| Error::NotInGraph(iri.to_owned()))
+    }
+
+    /// Number of edges from `start` to every class reachable from it, in either direction
+    fn distances(&self, start: NodeIndex) -> HashMap<NodeIndex, usize> {
+        let mut distances = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(idx) = queue.pop_front() {
+            let next_distance = distances[&idx] + 1;
+            for next in self.g.neighbors_undirected(idx) {
+                if let Entry::Vacant(entry) = distances.entry(next) {
+                    entry.insert(next_distance);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{edge, edges, nodes, visualizer};
+
+    const ONTOLOGY: &str = "
+        brick:AHU a owl:Class .
+        brick:VAV a owl:Class .
+        brick:Fan a owl:Class .
+        brick:Damper a owl:Class .
+        brick:HVAC_Zone a owl:Class .
+        brick:Building a owl:Class .
+        brick:Floor a owl:Class .
+    ";
+    /// Two paths of two edges from the AHU to the zone, a longer one through the damper, and a
+    /// building and floor apart from the rest
+    const DATA: &str = "
+        ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 ; brick:hasPart ex:fan1, ex:damper1 .
+        ex:vav1 a brick:VAV ; brick:feeds ex:zone1 .
+        ex:fan1 a brick:Fan ; brick:feeds ex:zone1 .
+        ex:damper1 a brick:Damper ; brick:feeds ex:vav1 .
+        ex:zone1 a brick:HVAC_Zone .
+        ex:building1 a brick:Building ; brick:hasPart ex:floor1 .
+        ex:floor1 a brick:Floor .
+    ";
+
+    fn path(from: &str, to: &str) -> Result<Visualizer<'static>> {
+        let mut v = visualizer(ONTOLOGY, DATA).with_path(from, to);
+        v.build_graph()?;
+        Ok(v)
+    }
+
+    #[test]
+    fn every_shortest_path_is_kept() {
+        let v = path("brick:AHU", "brick:HVAC_Zone").unwrap();
+        assert_eq!(nodes(&v), vec!["brick_AHU", "brick_Fan", "brick_HVAC_Zone", "brick_VAV"]);
+        assert_eq!(edges(&v), vec![
+            edge("brick_AHU", "brick_feeds", "brick_VAV"),
+            edge("brick_AHU", "brick_hasPart", "brick_Fan"),
+            edge("brick_Fan", "brick_feeds", "brick_HVAC_Zone"),
+            edge("brick_VAV", "brick_feeds", "brick_HVAC_Zone"),
+        ]);
+        // edges are followed against their direction too
+        let back = path("brick:HVAC_Zone", "https://brickschema.org/schema/Brick#AHU").unwrap();
+        assert_eq!(edges(&back), edges(&v));
+    }
+
+    #[test]
+    fn unlinked_and_unknown_classes_fail() {
+        assert!(matches!(path("brick:AHU", "brick:Floor"), Err(Error::NoPath(..))));
+        let chiller = "https://brickschema.org/schema/Brick#Chiller";
+        assert!(matches!(path("brick:AHU", "brick:Chiller"), Err(Error::NotInGraph(iri)) if iri == chiller));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Visualizer;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Rows between two [`RowProgress`] reports
+pub(crate) const ROW_PROGRESS_INTERVAL: u64 = 100_000;
+
+/// Progress of a bulk load, reported about every million triples
+#[derive(Debug, Clone, Copy)]
+pub struct LoadProgress {
+    /// Load stage the triples belong to, e.g. "load ontology" or "load data"
+    pub stage: &'static str,
+    /// Triples loaded so far from the current file
+    pub triples: u64,
+    /// Time since the current file started loading
+    pub elapsed: Duration,
+}
+
+impl LoadProgress {
+    /// Triples loaded per second
+    pub fn rate(&self) -> f64 {
+        self.triples as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Progress through the solutions of an extraction query, reported every 100,000 rows. Rows
+/// are folded into the class graph as they arrive, so memory stays bounded by the number of
+/// distinct classes and edges however many rows there are.
+#[derive(Debug, Clone, Copy)]
+pub struct RowProgress {
+    /// Graph building stage the query belongs to, e.g. "edges"
+    pub stage: &'static str,
+    /// Solutions processed so far
+    pub rows: u64,
+    /// Time since the query started
+    pub elapsed: Duration,
+}
+
+/// Receives progress while a [`Visualizer`] bulk loads files, runs the queries of each graph
+/// building stage and renders images. Stages are the ones listed by [`Visualizer::usage`]
+/// plus "render"; they run one after the other, never nested. Every method does nothing by
+/// default, so a sink only implements the events it shows.
+pub trait ProgressSink: Send + Sync {
+    /// A stage, e.g. "load data", "edges" or "render", started
+    fn stage_started(&self, _stage: &'static str) {}
+
+    /// More triples of the current load stage were loaded
+    fn loaded(&self, _progress: LoadProgress) {}
+
+    /// More solutions of the current query stage were processed
+    fn rows(&self, _progress: RowProgress) {}
+
+    /// The stage started last finished after `elapsed`
+    fn stage_finished(&self, _stage: &'static str, _elapsed: Duration) {}
+}
+
+struct LoadCallback<F>(F);
+
+impl<F: Fn(LoadProgress) + Send + Sync> ProgressSink for LoadCallback<F> {
+    fn loaded(&self, progress: LoadProgress) {
+        (self.0)(progress)
+    }
+}
+
+struct RowCallback<F>(F);
+
+impl<F: Fn(RowProgress) + Send + Sync> ProgressSink for RowCallback<F> {
+    fn rows(&self, progress: RowProgress) {
+        (self.0)(progress)
+    }
+}
+
+impl Visualizer<'_> {
+    /// Reports progress to `sink`, on top of any sink or callback added before
+    pub fn with_progress_sink(mut self, sink: impl ProgressSink + 'static) -> Self {
+        self.progress.push(Arc::new(sink));
+        self
+    }
+
+    /// Calls `callback` with the number of triples loaded so far and the load rate while
+    /// ontologies and data graphs are bulk loaded
+    pub fn on_load_progress(self, callback: impl Fn(LoadProgress) + Send + Sync + 'static) -> Self {
+        self.with_progress_sink(LoadCallback(callback))
+    }
+
+    /// Calls `callback` as the solutions of the edge queries are folded into the class graph,
+    /// for models whose queries return millions of rows
+    pub fn on_row_progress(self, callback: impl Fn(RowProgress) + Send + Sync + 'static) -> Self {
+        self.with_progress_sink(RowCallback(callback))
+    }
+
+    /// Passes an event to every progress sink
+    pub(crate) fn report(&self, event: impl Fn(&dyn ProgressSink)) {
+        for sink in self.progress.iter() {
+            event(sink.as_ref());
+        }
+    }
+}
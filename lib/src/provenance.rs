@@ -0,0 +1,104 @@
+use crate::{iri, NodeStyle, Result, Visualizer};
+use oxigraph::io::GraphFormat;
+use oxigraph::model::{GraphNameRef, NamedNode, Term};
+use oxigraph::sparql::QueryResults;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::io::BufRead;
+
+/// Prefix of the named graphs holding a second copy of each ontology loaded with
+/// [`Visualizer::add_ontology_from`], numbered in load order
+pub(crate) const ONTOLOGY_GRAPHS: &str = "urn:rdf_class_viz:ontology:";
+
+/// Fills given to the classes of each source ontology, in load order
+static SOURCE_FILLS: &[&str] = &[
+    "LightBlue",
+    "PaleGreen",
+    "Khaki",
+    "Plum",
+    "LightSalmon",
+    "PaleTurquoise",
+    "Wheat",
+    "Thistle",
+];
+
+impl Visualizer<'_> {
+    /// Loads an ontology like [`Visualizer::add_ontology`], remembering that the classes it
+    /// declares come from `source` (e.g. its file name) for [`Visualizer::with_provenance`]
+    pub fn add_ontology_from(
+        &mut self,
+        source: impl Into<String>,
+        mut content: impl BufRead,
+        format: GraphFormat,
+    ) -> Result<()> {
+        let mut buffer = Vec::new();
+        content.read_to_end(&mut buffer)?;
+        self.load("load ontology", buffer.as_slice(), format)?;
+        let graph = NamedNode::new_unchecked(format!("{}{}", ONTOLOGY_GRAPHS, self.sources.len()));
+        self.load_into(
+            "load ontology provenance",
+            buffer.as_slice(),
+            format,
+            GraphNameRef::NamedNode(graph.as_ref()),
+        )?;
+        self.sources.push(source.into());
+        Ok(())
+    }
+
+    /// Fills each class with a color per source ontology and badges it with the source's
+    /// name. Classes declared by several ontologies belong to the first one loaded; classes
+    /// no ontology loaded with [`Visualizer::add_ontology_from`] declares keep their category
+    /// color.
+    pub fn with_provenance(mut self, provenance: bool) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// Source of the ontology that declares each class label in [`Visualizer::graph`], when
+    /// provenance is enabled
+    pub fn provenance(&self) -> &HashMap<String, String> {
+        &self.class_sources
+    }
+
+    /// Finds the first source ontology declaring each class in the graph and restyles the
+    /// class with that source's fill, keeping `--class-style` overrides on top
+    pub(crate) fn find_provenance(&mut self) -> Result<()> {
+        let q = format!("SELECT ?class ?g WHERE {{
+                     GRAPH ?g {{ {} }}
+                     FILTER(STRSTARTS(STR(?g), \"{}\"))
+                 }}", self.declared_classes("class"), ONTOLOGY_GRAPHS);
+
+        let mut first: HashMap<Arc<str>, usize> = HashMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                let class = self.interner.label(row.get("class").unwrap());
+                if !self.node_iris.contains_key(&class) {
+                    continue;
+                }
+                let index = iri(row.get("g").unwrap())[ONTOLOGY_GRAPHS.len()..]
+                    .parse()
+                    .unwrap_or(usize::MAX);
+                first
+                    .entry(class)
+                    .and_modify(|first| *first = index.min(*first))
+                    .or_insert(index);
+            }
+        }
+
+        for (class, index) in first {
+            let Some(source) = self.sources.get(index) else {
+                continue;
+            };
+            let (label, iri) = self.node_iris.get_key_value(&class).expect("classes in the graph");
+            let (label, node) = (label.clone(), Term::NamedNode(NamedNode::new_unchecked(&**iri)));
+            let fill = SOURCE_FILLS[index % SOURCE_FILLS.len()];
+            let style = NodeStyle::fill(fill).merge(&self.resolve_style(&node)?);
+            self.colors
+                .insert(label.clone(), style.fill.clone().unwrap_or_default());
+            self.styles.insert(label, style);
+            self.class_sources.insert(class.to_string(), source.clone());
+        }
+        Ok(())
+    }
+}
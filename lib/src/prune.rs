@@ -0,0 +1,228 @@
+use crate::{Error, Result, Visualizer};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+impl Visualizer<'_> {
+    /// Rebuilds the class graph with only the class labels in `nodes` and the edges among them
+    /// in `edges`, remapping every per-class and per-edge map to match
+    pub(crate) fn retain(&mut self, nodes: &HashSet<Arc<str>>, edges: &HashSet<EdgeIndex>) {
+        let node_order: Vec<NodeIndex> =
+            self.g.node_indices().filter(|idx| nodes.contains(&self.g[*idx])).collect();
+        let edge_order: Vec<EdgeIndex> = self.g.edge_indices().filter(|idx| edges.contains(idx)).collect();
+        tracing::debug!(
+            dropped_classes = self.g.node_count() - node_order.len(),
+            dropped_edges = self.g.edge_count() - edge_order.len(),
+            "pruned class graph"
+        );
+        self.rebuild(node_order, edge_order);
+    }
+
+    /// Rebuilds the class graph from the listed nodes and then edges, in that order, dropping
+    /// the rest and remapping every per-class and per-edge map to match
+    pub(crate) fn rebuild(&mut self, node_order: Vec<NodeIndex>, edge_order: Vec<EdgeIndex>) {
+        let old = std::mem::take(&mut self.g);
+        self.nodes.clear();
+        self.edge_lookup.clear();
+        let mut node_map: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        for idx in node_order {
+            node_map.insert(idx, self.node_index(&old[idx]));
+        }
+        let nodes: HashSet<Arc<str>> = self.g.node_weights().cloned().collect();
+        let mut edge_map: HashMap<EdgeIndex, EdgeIndex> = HashMap::new();
+        for idx in edge_order {
+            let (source, target) = old.edge_endpoints(idx).expect("edges of the old graph");
+            if let (Some(from), Some(to)) = (node_map.get(&source), node_map.get(&target)) {
+                edge_map.insert(idx, self.g.add_edge(*from, *to, old[idx].clone()));
+            }
+        }
+
+        fn remap<T>(map: &mut HashMap<EdgeIndex, T>, edge_map: &HashMap<EdgeIndex, EdgeIndex>) {
+            *map = std::mem::take(map)
+                .into_iter()
+                .filter_map(|(idx, value)| Some((*edge_map.get(&idx)?, value)))
+                .collect();
+        }
+        remap(&mut self.edge_predicates, &edge_map);
+        remap(&mut self.edge_counts, &edge_map);
+        remap(&mut self.edge_changes, &edge_map);
+        remap(&mut self.restrictions, &edge_map);
+        remap(&mut self.aggregated, &edge_map);
+        remap(&mut self.edge_annotations, &edge_map);
+        self.derived = self.derived.iter().filter_map(|idx| edge_map.get(idx).copied()).collect();
+
+        self.colors.retain(|label, _| nodes.contains(label));
+        self.styles.retain(|label, _| nodes.contains(label));
+        self.categories.retain(|label, _| nodes.contains(label));
+        self.class_sources.retain(|label, _| nodes.contains(label.as_str()));
+        self.node_iris.retain(|label, _| nodes.contains(label));
+        self.instance_counts.retain(|label, _| nodes.contains(label.as_str()));
+        self.deprecations.retain(|label, _| nodes.contains(label.as_str()));
+        self.violations.retain(|label, _| nodes.contains(label.as_str()));
+        self.node_changes.retain(|label, _| nodes.contains(label.as_str()));
+        self.attributes.retain(|label, _| nodes.contains(label));
+        self.value_ranges.retain(|(label, _), _| nodes.contains(label));
+    }
+
+    /// Drops the edges supported by fewer than `min` instance pairs
+    pub(crate) fn drop_rare_edges(&mut self, min: usize) {
+        let keep = self.g.node_weights().cloned().collect();
+        let edges = self
+            .g
+            .edge_indices()
+            .filter(|idx| {
+                let count = self.edge_counts.get(idx).copied();
+                let keep = count.is_none_or(|count| count >= min);
+                if !keep {
+                    let (from, to) = self.g.edge_endpoints(*idx).expect("edges of the graph");
+                    tracing::debug!(
+                        from = %self.g[from],
+                        edge = %self.g[*idx],
+                        to = %self.g[to],
+                        count,
+                        min,
+                        "dropped edge below the minimum count"
+                    );
+                }
+                keep
+            })
+            .collect();
+        self.retain(&keep, &edges);
+    }
+
+    /// Drops every class without an edge, including those only styled for their literals
+    pub(crate) fn prune_isolated_classes(&mut self) {
+        let keep = self
+            .g
+            .node_indices()
+            .filter(|idx| self.g.neighbors_undirected(*idx).next().is_some())
+            .map(|idx| self.g[idx].clone())
+            .collect();
+        let edges = self.g.edge_indices().collect();
+        self.retain(&keep, &edges);
+    }
+
+    /// Keeps only the classes within `hops` edges (in either direction) of the class `iri`
+    pub(crate) fn focus_on(&mut self, iri: &str, hops: usize) -> Result<()> {
+        let seed = self
+            .node_iris
+            .iter()
+            .find(|(_, node_iri)| ***node_iri == *iri)
+            .map(|(label, _)| label.clone())
+            .ok_or_else(|| Error::NotInGraph(iri.to_owned()))?;
+
+        let mut keep = HashSet::from([seed.clone()]);
+        if let Some(start) = self.nodes.get(&seed) {
+            let mut queue = VecDeque::from([(*start, 0)]);
+            let mut seen = HashSet::from([*start]);
+            while let Some((idx, depth)) = queue.pop_front() {
+                keep.insert(self.g[idx].clone());
+                if depth == hops {
+                    continue;
+                }
+                for next in self.g.neighbors_undirected(idx) {
+                    if seen.insert(next) {
+                        queue.push_back((next, depth + 1));
+                    }
+                }
+            }
+        }
+        let edges = self.g.edge_indices().collect();
+        self.retain(&keep, &edges);
+        Ok(())
+    }
+
+    /// Keeps only the class labels in `classes` and the edges among them labeled with one of
+    /// `predicates`, e.g. a selection picked interactively from the full graph. Building the
+    /// graph again brings back everything else.
+    pub fn keep_selection(&mut self, classes: &HashSet<String>, predicates: &HashSet<String>) {
+        let edges = self
+            .g
+            .edge_indices()
+            .filter(|idx| predicates.contains(&*self.g[*idx]))
+            .collect();
+        let classes = self.g.node_weights().filter(|label| classes.contains(&***label)).cloned().collect();
+        self.retain(&classes, &edges);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{edge, edges, nodes, visualizer};
+    use crate::{Error, Visualizer};
+
+    const ONTOLOGY: &str = "
+        brick:Fan a owl:Class .
+        brick:AHU a owl:Class .
+        brick:VAV a owl:Class .
+        brick:Damper a owl:Class .
+        brick:Sensor a owl:Class .
+    ";
+    /// The chain Fan - AHU - VAV - Damper - Sensor, whose middle two edges have two instance
+    /// pairs each and the outer two one
+    const DATA: &str = "
+        ex:fan1 a brick:Fan ; brick:feeds ex:ahu1 .
+        ex:ahu1 a brick:AHU ; brick:feeds ex:vav1, ex:vav2 .
+        ex:vav1 a brick:VAV ; brick:hasPart ex:damper1 .
+        ex:vav2 a brick:VAV ; brick:hasPart ex:damper2 .
+        ex:damper1 a brick:Damper ; brick:hasPoint ex:sensor1 .
+        ex:damper2 a brick:Damper .
+        ex:sensor1 a brick:Sensor .
+    ";
+    const AHU: &str = "https://brickschema.org/schema/Brick#AHU";
+
+    fn built(configure: impl FnOnce(Visualizer<'static>) -> Visualizer<'static>) -> Visualizer<'static> {
+        let mut v = configure(visualizer(ONTOLOGY, DATA));
+        v.build_graph().unwrap();
+        v
+    }
+
+    #[test]
+    fn rare_edges_are_dropped_and_their_classes_kept() {
+        let v = built(|v| v.with_min_edge_count(2));
+        assert_eq!(edges(&v), vec![
+            edge("brick_AHU", "brick_feeds", "brick_VAV"),
+            edge("brick_VAV", "brick_hasPart", "brick_Damper"),
+        ]);
+        assert!(v.edge_counts().values().all(|count| *count == 2));
+        assert_eq!(nodes(&v).len(), 5);
+    }
+
+    #[test]
+    fn classes_left_without_edges_are_pruned() {
+        let v = built(|v| v.with_min_edge_count(2).with_prune_isolated(true));
+        assert_eq!(nodes(&v), vec!["brick_AHU", "brick_Damper", "brick_VAV"]);
+        assert_eq!(edges(&v).len(), 2);
+        assert!(!v.node_iris().contains_key("brick_Fan"));
+        assert!(!v.instance_counts().contains_key("brick_Sensor"));
+
+        // nothing is isolated in the full graph
+        let v = built(|v| v.with_prune_isolated(true));
+        assert_eq!(nodes(&v).len(), 5);
+    }
+
+    #[test]
+    fn focus_keeps_the_classes_within_the_hop_radius() {
+        let v = built(|v| v.with_focus(AHU, 0));
+        assert_eq!(nodes(&v), vec!["brick_AHU"]);
+        assert!(edges(&v).is_empty());
+
+        let v = built(|v| v.with_focus(AHU, 1));
+        assert_eq!(nodes(&v), vec!["brick_AHU", "brick_Fan", "brick_VAV"]);
+        assert_eq!(edges(&v), vec![
+            edge("brick_AHU", "brick_feeds", "brick_VAV"),
+            edge("brick_Fan", "brick_feeds", "brick_AHU"),
+        ]);
+
+        let v = built(|v| v.with_focus(AHU, 2));
+        assert_eq!(nodes(&v), vec!["brick_AHU", "brick_Damper", "brick_Fan", "brick_VAV"]);
+        assert_eq!(edges(&v).len(), 3);
+    }
+
+    #[test]
+    fn focus_on_a_class_outside_the_graph_fails() {
+        let unknown = "https://brickschema.org/schema/Brick#Chiller";
+        let mut v = visualizer(ONTOLOGY, DATA).with_focus(unknown, 1);
+        assert!(matches!(v.build_graph(), Err(Error::NotInGraph(iri)) if iri == unknown));
+    }
+}
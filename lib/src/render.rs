@@ -0,0 +1,311 @@
+use crate::export::xml_escape;
+use crate::{D2Layout, Error, Instant, LineStyle, Result, Visualizer};
+use petgraph::visit::EdgeRef;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Layout engine used to turn the class graph into an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Engine {
+    /// The `d2` CLI, with the elk layout unless another D2 layout is set
+    #[default]
+    D2,
+    /// Graphviz `dot`
+    Dot,
+    /// Pure-Rust circular layout; needs no external tools but only produces SVG
+    Builtin,
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "d2" => Ok(Engine::D2),
+            "dot" => Ok(Engine::Dot),
+            "builtin" => Ok(Engine::Builtin),
+            _ => Err(format!(
+                "unknown engine '{}', expected one of d2, dot, builtin",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "svg" => Ok(ImageFormat::Svg),
+            "png" => Ok(ImageFormat::Png),
+            _ => Err(format!("unknown image format '{}', expected svg or png", s)),
+        }
+    }
+}
+
+/// Where the builtin engine and the draw.io export place the classes
+pub(crate) struct CircleLayout<'g> {
+    /// Side of the square canvas
+    pub(crate) size: f64,
+    /// Center of every class, by label
+    pub(crate) centers: Vec<(&'g str, (f64, f64))>,
+}
+
+static RENDER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs `program args...` with `input` on stdin and returns its stdout
+fn pipe(program: &str, args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::Render(format!("failed to run {}: {}", program, e)))?;
+    child.stdin.take().unwrap().write_all(input)?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::Render(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+impl Visualizer<'_> {
+    /// Renders the class graph to an image, shelling out to `d2` or `dot` unless the builtin
+    /// engine is chosen
+    pub fn render_with(&self, engine: Engine, format: ImageFormat) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let _span = tracing::info_span!("stage", stage = "render", ?engine, ?format).entered();
+        self.report(|sink| sink.stage_started("render"));
+        let image = self.render_image(engine, format)?;
+        let elapsed = start.elapsed();
+        self.report(|sink| sink.stage_finished("render", elapsed));
+        Ok(image)
+    }
+
+    fn render_image(&self, engine: Engine, format: ImageFormat) -> Result<Vec<u8>> {
+        match engine {
+            Engine::D2 => {
+                // d2 cannot write PNGs to stdout, so go through a temporary file
+                let path = std::env::temp_dir().join(format!(
+                    "rdf_class_viz-{}-{}.{}",
+                    std::process::id(),
+                    RENDER_COUNT.fetch_add(1, Ordering::Relaxed),
+                    format.extension()
+                ));
+                let out = path.to_string_lossy().into_owned();
+                pipe(
+                    "d2",
+                    &[
+                        "--layout",
+                        self.d2.layout.unwrap_or(D2Layout::Elk).name(),
+                        "-",
+                        out.as_str(),
+                    ],
+                    self.graph_to_d2lang()?.as_bytes(),
+                )?;
+                let image = std::fs::read(&path)?;
+                std::fs::remove_file(&path)?;
+                Ok(image)
+            }
+            Engine::Dot => pipe(
+                "dot",
+                &[format!("-T{}", format.extension()).as_str()],
+                self.graph_to_dot()?.as_bytes(),
+            ),
+            Engine::Builtin => match format {
+                ImageFormat::Svg => Ok(self.builtin_svg()?.into_bytes()),
+                ImageFormat::Png => Err(Error::Render(
+                    "the builtin engine only renders SVG".to_owned(),
+                )),
+            },
+        }
+    }
+
+    /// Places every class on a circle in label order
+    pub(crate) fn circle_layout(&self) -> CircleLayout<'_> {
+        let mut nodes: Vec<&str> = self.colors().keys().map(|label| &**label).collect();
+        nodes.sort();
+        let radius = (nodes.len() as f64 * 40.0).max(200.0);
+        let size = 2.0 * radius + 400.0;
+        let count = nodes.len().max(1) as f64;
+        let positions = nodes
+            .into_iter()
+            .enumerate()
+            .map(|(idx, label)| {
+                let angle = 2.0 * std::f64::consts::PI * idx as f64 / count;
+                (label, (size / 2.0 + radius * angle.cos(), size / 2.0 + radius * angle.sin()))
+            })
+            .collect();
+        CircleLayout { size, centers: positions }
+    }
+
+    /// Places the classes on a circle and draws straight edges between them
+    fn builtin_svg(&self) -> Result<String> {
+        let CircleLayout { size, centers: layout } = self.circle_layout();
+        let nodes: Vec<&str> = layout.iter().map(|(label, _)| *label).collect();
+        let position = |label: &str| {
+            layout
+                .iter()
+                .find(|(node, _)| *node == label)
+                .map_or((size / 2.0, size / 2.0), |(_, position)| *position)
+        };
+
+        let mut w = Vec::new();
+        writeln!(
+            w,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\" font-family=\"{1}\" font-size=\"12\">",
+            size,
+            xml_escape(self.font.as_deref().unwrap_or("sans-serif"))
+        )?;
+        writeln!(
+            w,
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\"><path d=\"M0,0 L10,5 L0,10 z\" fill=\"{}\"/></marker></defs>",
+            self.theme.map_or("#000", |theme| theme.line_color())
+        )?;
+        if let Some(theme) = self.theme {
+            writeln!(w, "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>", theme.background())?;
+        }
+        // (edge, node outline, label, chip) colors
+        let (line_color, outline, font, chip_color) = match self.theme {
+            Some(theme) => (theme.line_color(), theme.line_color(), theme.font_color(), theme.font_color()),
+            None => ("#555", "#333", "#000", "#333"),
+        };
+        let graph = self.graph();
+        let widths = self.edge_widths();
+        for edge in graph.edge_references() {
+            let (x1, y1) = position(&graph[edge.source()]);
+            let (x2, y2) = position(&graph[edge.target()]);
+            let style = self.resolved_edge_style(edge.id());
+            let dash = match style.line {
+                Some(LineStyle::Dashed) => " stroke-dasharray=\"4\"",
+                Some(LineStyle::Dotted) => " stroke-dasharray=\"1 3\"",
+                _ => "",
+            };
+            let color = xml_escape(style.stroke.as_deref().unwrap_or(line_color));
+            let font_color = style.font_color.as_deref().map_or_else(|| color.clone(), xml_escape);
+            let width = widths.get(&edge.id()).copied().unwrap_or(1.0);
+            writeln!(
+                w,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"{:.2}\"{} marker-end=\"url(#arrow)\"/>",
+                x1, y1, x2, y2, color, width, dash
+            )?;
+            writeln!(
+                w,
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"{}\">{}</text>",
+                (x1 + x2) / 2.0,
+                (y1 + y2) / 2.0,
+                font_color,
+                xml_escape(edge.weight())
+            )?;
+        }
+        let font_sizes = self.node_font_sizes();
+        for node in &nodes {
+            let (x, y) = position(node);
+            // boxes grow with the font, 12px by default
+            let font_size = font_sizes.get(*node).copied().unwrap_or(12.0);
+            let scale = font_size / 12.0;
+            let lines = self.display_label(Engine::Builtin, node);
+            let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+            let width = longest as f64 * 7.5 * scale + 20.0;
+            let height = (28.0 + 14.0 * (lines.len() - 1) as f64) * scale;
+            let style = &self.styles()[*node];
+            let stroke = style.stroke.as_deref().unwrap_or(outline);
+            let font_color = style.font_color.as_deref().unwrap_or(font);
+            let dash = if self.is_unknown_class(node) {
+                " stroke-dasharray=\"4\""
+            } else {
+                ""
+            };
+            // browsers show the title of a group when hovering any of its shapes
+            let link = self.node_link(node);
+            match &link {
+                Some(link) => writeln!(w, "<a href=\"{}\">", xml_escape(link))?,
+                None => writeln!(w, "<g>")?,
+            }
+            if let Some(tooltip) = self.tooltip(node) {
+                writeln!(w, "<title>{}</title>", xml_escape(&tooltip))?;
+            }
+            writeln!(
+                w,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"4\" fill=\"{}\" stroke=\"{}\"{}/>",
+                x - width / 2.0,
+                y - height / 2.0,
+                width,
+                height,
+                xml_escape(&self.colors()[*node]),
+                xml_escape(stroke),
+                dash
+            )?;
+            // one tspan per line, centered on the box together
+            let line_height = 14.0 * scale;
+            let first = y - line_height * (lines.len() - 1) as f64 / 2.0;
+            let text: String = lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    format!(
+                        "<tspan x=\"{:.1}\" y=\"{:.1}\">{}</tspan>",
+                        x,
+                        first + line_height * i as f64,
+                        xml_escape(line)
+                    )
+                })
+                .collect();
+            writeln!(
+                w,
+                "<text text-anchor=\"middle\" dominant-baseline=\"middle\" font-size=\"{:.1}\" fill=\"{}\">{}</text>",
+                font_size,
+                xml_escape(font_color),
+                text
+            )?;
+            if let Some(chip) = self.category_chip(node) {
+                writeln!(
+                    w,
+                    "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"10\" fill=\"{}\">{}</text>",
+                    x,
+                    y + height / 2.0 + 12.0,
+                    chip_color,
+                    xml_escape(&chip)
+                )?;
+            }
+            writeln!(w, "{}", if link.is_some() { "</a>" } else { "</g>" })?;
+        }
+        if let Some(lines) = self.legend_lines() {
+            for (i, line) in lines.iter().enumerate() {
+                writeln!(
+                    w,
+                    "<text x=\"20\" y=\"{}\" fill=\"{}\">{}</text>",
+                    30 + 16 * i,
+                    font,
+                    xml_escape(line)
+                )?;
+            }
+        }
+        writeln!(w, "</svg>")?;
+        Ok(String::from_utf8(w)?)
+    }
+}
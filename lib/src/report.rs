@@ -0,0 +1,114 @@
+//! A Markdown review report of a model: its class graph as a Mermaid diagram, the usage tables
+//! and the warnings, in one document to attach to a ticket
+
+use crate::{Result, Visualizer};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// A Markdown table cell
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+fn count_table(out: &mut Vec<u8>, title: &str, header: &str, counts: &BTreeMap<String, usize>) -> Result<()> {
+    writeln!(out, "### {} ({})\n", title, counts.len())?;
+    if counts.is_empty() {
+        writeln!(out, "None.\n")?;
+        return Ok(());
+    }
+    writeln!(out, "| {} | Count |\n| --- | ---: |", header)?;
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.cmp(b)));
+    for (iri, count) in counts {
+        writeln!(out, "| `{}` | {} |", cell(iri), count)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+impl Visualizer<'_> {
+    /// Markdown report of the built class graph titled `title`: a summary line, the class
+    /// graph as an embedded Mermaid diagram, the [`Visualizer::stats`] tables and the
+    /// warnings about deprecated and undeclared classes
+    pub fn markdown_report(&self, title: &str) -> Result<String> {
+        let stats = self.stats()?;
+        let mut out = Vec::new();
+        writeln!(out, "# {}\n", title)?;
+        let warnings = self.deprecations.len() + self.undefined_classes().len();
+        writeln!(
+            out,
+            "{} classes and {} edges from {} triples, {} warning{}.\n",
+            self.g.node_count(),
+            self.g.edge_count(),
+            self.usage().triples,
+            warnings,
+            if warnings == 1 { "" } else { "s" }
+        )?;
+
+        writeln!(out, "## Class graph\n")?;
+        writeln!(out, "```mermaid\n{}```\n", self.graph_to_mermaid()?)?;
+
+        writeln!(out, "## Warnings\n")?;
+        if warnings == 0 {
+            writeln!(out, "None.\n")?;
+        } else {
+            let mut deprecated: Vec<_> = self.deprecations.iter().collect();
+            deprecated.sort_by_key(|(class, _)| *class);
+            for (class, deprecation) in deprecated {
+                writeln!(out, "- {}", deprecation.suggestion(class))?;
+            }
+            for (class, count) in self.undefined_classes() {
+                let noun = if *count == 1 { "instance" } else { "instances" };
+                writeln!(
+                    out,
+                    "- `{}` is the type of {} {} but no ontology declares it as a class",
+                    class, count, noun
+                )?;
+            }
+            writeln!(out)?;
+        }
+
+        writeln!(out, "## Statistics\n")?;
+        count_table(&mut out, "Instances per class", "Class", &stats.instances)?;
+        count_table(&mut out, "Triples per predicate", "Predicate", &stats.predicates)?;
+        count_table(&mut out, "Classes used but not defined", "Class", &stats.undefined_classes)?;
+
+        writeln!(out, "### Class metrics\n")?;
+        if stats.metrics.is_empty() {
+            writeln!(out, "None.\n")?;
+        } else {
+            writeln!(out, "| Class | In | Out | Betweenness |\n| --- | ---: | ---: | ---: |")?;
+            let mut metrics: Vec<_> = stats.metrics.iter().collect();
+            metrics.sort_by(|(a, x), (b, y)| y.degree().cmp(&x.degree()).then(a.cmp(b)));
+            for (iri, metrics) in metrics {
+                writeln!(
+                    out,
+                    "| `{}` | {} | {} | {:.3} |",
+                    cell(iri),
+                    metrics.in_degree,
+                    metrics.out_degree,
+                    metrics.betweenness
+                )?;
+            }
+            writeln!(out)?;
+        }
+
+        writeln!(out, "### Cycles ({})\n", stats.cycles.len())?;
+        for cycle in stats.cycles.iter() {
+            let classes: Vec<String> = cycle.classes.iter().map(|class| format!("`{}`", class)).collect();
+            writeln!(out, "- `{}`: {}", cycle.predicate, classes.join(", "))?;
+        }
+        if stats.cycles.is_empty() {
+            writeln!(out, "None.")?;
+        }
+
+        writeln!(out, "\n### Orphan instances ({})\n", stats.orphans.len())?;
+        for orphan in stats.orphans.iter() {
+            writeln!(out, "- `{}`", orphan)?;
+        }
+        if stats.orphans.is_empty() {
+            writeln!(out, "None.")?;
+        }
+        Ok(String::from_utf8(out)?)
+    }
+}
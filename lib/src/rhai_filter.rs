@@ -0,0 +1,114 @@
+use crate::{Error, FilterContext, Result};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::sync::Arc;
+
+/// Edge filter written in [Rhai](https://rhai.rs), a small embedded scripting language. The
+/// script defines `fn filter(from, to, edge)` returning a bool, with the same arguments as a
+/// closure filter:
+///
+/// ```text
+/// fn filter(from, to, edge) {
+///     !edge.contains("hasTag")
+/// }
+/// ```
+///
+/// It may take a fourth `ctx` argument instead, a map with the fields of [`FilterContext`]
+/// (`ctx.count`, `ctx.from_instances`, `ctx.edge_namespace`, ...).
+#[derive(Clone)]
+pub struct RhaiFilter {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+    uses_context: bool,
+}
+
+impl RhaiFilter {
+    /// Compiles `script`, failing if it does not parse or does not define `filter`
+    pub fn new(script: &str) -> Result<Self> {
+        let engine = Engine::new();
+        let ast = engine
+            .compile(script)
+            .map_err(|e| Error::Filter(e.to_string()))?;
+        let arity = ast
+            .iter_functions()
+            .filter(|f| f.name == "filter")
+            .map(|f| f.params.len())
+            .find(|arity| *arity == 3 || *arity == 4)
+            .ok_or_else(|| {
+                Error::Filter("script does not define fn filter(from, to, edge)".to_owned())
+            })?;
+        Ok(RhaiFilter {
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+            uses_context: arity == 4,
+        })
+    }
+
+    /// Runs the script's `filter` on one edge. Edges for which the script fails or returns
+    /// something other than a bool are dropped.
+    pub fn keep(&self, from: &str, to: &str, edge: &str, context: &FilterContext) -> bool {
+        let (from, to, edge) = (from.to_owned(), to.to_owned(), edge.to_owned());
+        let mut scope = Scope::new();
+        let kept = if self.uses_context {
+            let mut ctx = Map::new();
+            ctx.insert("count".into(), Dynamic::from_int(context.count as i64));
+            ctx.insert("from_instances".into(), Dynamic::from_int(context.from_instances as i64));
+            ctx.insert("to_instances".into(), Dynamic::from_int(context.to_instances as i64));
+            ctx.insert("from_namespace".into(), context.from_namespace.into());
+            ctx.insert("to_namespace".into(), context.to_namespace.into());
+            ctx.insert("edge_namespace".into(), context.edge_namespace.into());
+            self.engine
+                .call_fn::<bool>(&mut scope, &self.ast, "filter", (from, to, edge, ctx))
+        } else {
+            self.engine
+                .call_fn::<bool>(&mut scope, &self.ast, "filter", (from, to, edge))
+        };
+        kept.unwrap_or(false)
+    }
+
+    /// The filter as a closure for [`crate::Visualizer::with_context_filter`]
+    pub fn into_fn(
+        self,
+    ) -> impl Fn(&str, &str, &str, &FilterContext) -> bool + Send + Sync + 'static {
+        move |from, to, edge, context| self.keep(from, to, edge, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{edge, edges, visualizer};
+
+    const ONTOLOGY: &str = "
+        brick:AHU a owl:Class .
+        brick:VAV a owl:Class .
+        brick:Sensor a owl:Class .
+    ";
+    const DATA: &str = "
+        ex:ahu a brick:AHU ; brick:feeds ex:vav ; brick:hasPoint ex:sensor .
+        ex:vav a brick:VAV .
+        ex:sensor a brick:Sensor .
+    ";
+
+    #[test]
+    fn keeps_the_edges_the_script_returns_true_for() {
+        let filter = RhaiFilter::new(r#"fn filter(from, to, edge) { !edge.contains("hasPoint") }"#).unwrap();
+        let mut v = visualizer(ONTOLOGY, DATA).with_context_filter(filter.into_fn());
+        v.build_graph().unwrap();
+        assert_eq!(edges(&v), [edge("brick_AHU", "brick_feeds", "brick_VAV")]);
+    }
+
+    #[test]
+    fn scripts_with_a_context_see_the_instance_counts() {
+        let filter = RhaiFilter::new(r#"fn filter(from, to, edge, ctx) { ctx.to_instances > 1 }"#).unwrap();
+        let data = format!("{}\nex:vav2 a brick:VAV . ex:ahu brick:feeds ex:vav2 .", DATA);
+        let mut v = visualizer(ONTOLOGY, &data).with_context_filter(filter.into_fn());
+        v.build_graph().unwrap();
+        assert_eq!(edges(&v), [edge("brick_AHU", "brick_feeds", "brick_VAV")]);
+    }
+
+    #[test]
+    fn scripts_without_a_filter_function_are_refused() {
+        assert!(RhaiFilter::new("fn keep(from, to, edge) { true }").is_err());
+        assert!(RhaiFilter::new("fn filter(from, to, edge) {").is_err());
+    }
+}
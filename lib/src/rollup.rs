@@ -0,0 +1,318 @@
+use crate::annotations::merge_annotations;
+use crate::{Annotations, Result, Visualizer};
+use oxigraph::model::{NamedNode, Term};
+use oxigraph::sparql::QueryResults;
+use petgraph::graph::EdgeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+impl Visualizer<'_> {
+    /// Collapse every class more than `depth` `rdfs:subClassOf` levels below the class `root`
+    /// into its ancestor at that depth, merging their edges and adding up their counts. With
+    /// `brick:Equipment` and depth 1, `brick:AHU` and `brick:VAV` both become `brick:HVAC_Equipment`.
+    pub fn with_rollup(mut self, root: impl Into<String>, depth: usize) -> Self {
+        self.rollup = Some((root.into(), depth));
+        self
+    }
+
+    /// The ancestor at `depth` below `root` of every deeper subclass of `root`, by IRI. Classes
+    /// with several parents go through the one closest to `root`.
+    fn rollup_targets(&self, root: &str, depth: usize) -> Result<HashMap<String, String>> {
+        let q = format!("PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 SELECT ?sub ?sup WHERE {{
+                     ?sub rdfs:subClassOf ?sup .
+                     ?sup rdfs:subClassOf* <{}> .
+                     FILTER(isIRI(?sub) && isIRI(?sup) && ?sub != ?sup)
+                 }}", root);
+        // sorted so classes with several parents always roll up the same way
+        let mut children: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                if let (Some(Term::NamedNode(sub)), Some(Term::NamedNode(sup))) = (row.get("sub"), row.get("sup")) {
+                    children.entry(sup.as_str().to_owned()).or_default().push(sub.as_str().to_owned());
+                }
+            }
+        }
+        for subs in children.values_mut() {
+            subs.sort();
+        }
+
+        let mut targets = HashMap::new();
+        let mut seen = HashSet::from([root.to_owned()]);
+        let mut queue = VecDeque::from([(root.to_owned(), 0, root.to_owned())]);
+        while let Some((class, level, target)) = queue.pop_front() {
+            for sub in children.get(&class).into_iter().flatten() {
+                if !seen.insert(sub.clone()) {
+                    continue;
+                }
+                let sub_target = if level < depth { sub.clone() } else { target.clone() };
+                if level >= depth {
+                    targets.insert(sub.clone(), sub_target.clone());
+                }
+                queue.push_back((sub.clone(), level + 1, sub_target));
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Merges every class of the graph into its rollup target
+    pub(crate) fn roll_up(&mut self, root: &str, depth: usize) -> Result<()> {
+        let targets = self.rollup_targets(root, depth)?;
+        let mut merged: HashMap<String, String> = HashMap::new();
+        for (label, iri) in self.node_iris.clone() {
+            if let Some(target) = targets.get(&*iri) {
+                let term = Term::NamedNode(NamedNode::new_unchecked(target.clone()));
+                let target_label = self.interner.label(&term);
+                self.style_node(&target_label, &term)?;
+                merged.insert(label.to_string(), target_label.to_string());
+            }
+        }
+        self.merge_classes(&merged);
+        Ok(())
+    }
+
+    /// Merges every class labeled with a key of `merged` into the class labeled with its
+    /// value, which must be styled already: their edges merge and their counts add up
+    pub(crate) fn merge_classes(&mut self, merged: &HashMap<String, String>) {
+        if merged.is_empty() {
+            return;
+        }
+        let target = |label: &str| merged.get(label).map_or(label, String::as_str).to_owned();
+
+        let old = std::mem::take(&mut self.g);
+        self.nodes.clear();
+        self.edge_lookup.clear();
+        for label in old.node_weights() {
+            self.node_index(&target(label));
+        }
+        let mut edge_predicates = HashMap::new();
+        let mut edge_counts: HashMap<EdgeIndex, usize> = HashMap::new();
+        let mut edge_changes = HashMap::new();
+        let mut restrictions = HashMap::new();
+        let mut edge_annotations: HashMap<EdgeIndex, Annotations> = HashMap::new();
+        let mut aggregated: HashMap<EdgeIndex, Vec<String>> = HashMap::new();
+        let mut asserted = HashSet::new();
+        for edge in old.edge_references() {
+            let from = self.nodes[target(&old[edge.source()]).as_str()];
+            let to = self.nodes[target(&old[edge.target()]).as_str()];
+            let idx = self.edge_index(from, to, edge.weight().clone());
+            *edge_counts.entry(idx).or_default() += self.edge_counts.get(&edge.id()).copied().unwrap_or(0);
+            if let Some(predicate) = self.edge_predicates.get(&edge.id()) {
+                edge_predicates.entry(idx).or_insert_with(|| predicate.clone());
+            }
+            if let Some(change) = self.edge_changes.get(&edge.id()) {
+                edge_changes.entry(idx).or_insert(*change);
+            }
+            if let Some(kind) = self.restrictions.get(&edge.id()) {
+                restrictions.entry(idx).or_insert_with(|| kind.clone());
+            }
+            if let Some(annotations) = self.edge_annotations.get(&edge.id()) {
+                merge_annotations(edge_annotations.entry(idx).or_default(), annotations);
+            }
+            if let Some(predicates) = self.aggregated.get(&edge.id()) {
+                let all = aggregated.entry(idx).or_default();
+                for predicate in predicates {
+                    if !all.contains(predicate) {
+                        all.push(predicate.clone());
+                    }
+                }
+            }
+            if !self.derived.contains(&edge.id()) {
+                asserted.insert(idx);
+            }
+        }
+        // a merged edge is derived only if none of the edges it stands for was asserted
+        self.derived = self.g.edge_indices().filter(|idx| !asserted.contains(idx)).collect();
+        self.edge_predicates = edge_predicates;
+        self.edge_counts = edge_counts;
+        self.edge_changes = edge_changes;
+        self.restrictions = restrictions;
+        self.edge_annotations = edge_annotations;
+        self.aggregated = aggregated;
+
+        for (label, target) in merged.iter() {
+            if let Some(count) = self.instance_counts.remove(label) {
+                *self.instance_counts.entry(target.clone()).or_default() += count;
+            }
+            if let Some(count) = self.violations.remove(label) {
+                *self.violations.entry(target.clone()).or_default() += count;
+            }
+            if let Some(attributes) = self.attributes.remove(label.as_str()) {
+                let target = self.interner.intern(target);
+                self.attributes.entry(target).or_default().extend(attributes);
+            }
+            self.colors.remove(label.as_str());
+            self.styles.remove(label.as_str());
+            self.categories.remove(label.as_str());
+            self.class_sources.remove(label);
+            self.node_iris.remove(label.as_str());
+            self.deprecations.remove(label);
+            self.node_changes.remove(label);
+        }
+        for ((label, p), range) in std::mem::take(&mut self.value_ranges) {
+            self.value_ranges
+                .entry((self.interner.intern(&target(&label)), p))
+                .and_modify(|merged| merged.merge(&range))
+                .or_insert(range);
+        }
+    }
+
+    /// Draw every edge with the most general property its predicate is an `rdfs:subPropertyOf`,
+    /// so e.g. all the specializations of `brick:hasPoint` merge into one `hasPoint` edge
+    pub fn with_property_rollup(mut self, property_rollup: bool) -> Self {
+        self.property_rollup = property_rollup;
+        self
+    }
+
+    /// Finds the most general superproperty of every property that has one
+    pub(crate) fn find_property_tops(&mut self) -> Result<()> {
+        let q = "PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 SELECT ?p ?top WHERE {
+                     ?p rdfs:subPropertyOf+ ?top .
+                     FILTER NOT EXISTS {
+                         ?top rdfs:subPropertyOf ?above .
+                         FILTER(?above != ?top && ?above NOT IN (owl:topObjectProperty, owl:topDataProperty))
+                     }
+                     FILTER(isIRI(?p) && isIRI(?top) && ?p != ?top)
+                     FILTER(?top NOT IN (owl:topObjectProperty, owl:topDataProperty))
+                 }";
+        if let QueryResults::Solutions(solutions) = self.query(q)? {
+            for row in solutions {
+                let row = row?;
+                if let (Some(Term::NamedNode(p)), Some(Term::NamedNode(top))) = (row.get("p"), row.get("top")) {
+                    // a property under several hierarchies goes to the first top in IRI order
+                    let top = top.as_str();
+                    self.property_tops
+                        .entry(p.as_str().to_owned())
+                        .and_modify(|current| {
+                            if top < current.as_str() {
+                                *current = top.to_owned();
+                            }
+                        })
+                        .or_insert_with(|| top.to_owned());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The IRI an edge for `predicate` is drawn with
+    pub(crate) fn rolled_up_property(&mut self, predicate: &str) -> Arc<str> {
+        self.interner.intern(self.property_tops.get(predicate).map_or(predicate, String::as_str))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{edge, edges, nodes, visualizer};
+    use std::collections::HashMap;
+
+    const BRICK: &str = "https://brickschema.org/schema/Brick#";
+    /// Equipment over HVAC and lighting equipment, with a combo unit under both and sensors
+    /// outside the hierarchy
+    const ONTOLOGY: &str = "
+        brick:Equipment a owl:Class .
+        brick:HVAC_Equipment a owl:Class ; rdfs:subClassOf brick:Equipment .
+        brick:AHU a owl:Class ; rdfs:subClassOf brick:HVAC_Equipment .
+        brick:VAV a owl:Class ; rdfs:subClassOf brick:HVAC_Equipment .
+        brick:Lighting_Equipment a owl:Class ; rdfs:subClassOf brick:Equipment .
+        brick:Luminaire a owl:Class ; rdfs:subClassOf brick:Lighting_Equipment .
+        brick:LED_Luminaire a owl:Class ; rdfs:subClassOf brick:Luminaire .
+        brick:Combo_Unit a owl:Class ; rdfs:subClassOf brick:HVAC_Equipment, brick:Luminaire .
+        brick:Sensor a owl:Class .
+    ";
+    const DATA: &str = "
+        ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 ; brick:hasPoint ex:sensor1 .
+        ex:ahu2 a brick:AHU ; brick:feeds ex:vav2 .
+        ex:vav1 a brick:VAV ; brick:hasPoint ex:sensor2 .
+        ex:vav2 a brick:VAV .
+        ex:sensor1 a brick:Sensor .
+        ex:sensor2 a brick:Sensor .
+    ";
+
+    /// [`Visualizer::rollup_targets`] under `brick:Equipment`, with the `brick:` prefix cut off
+    fn targets(depth: usize) -> HashMap<String, String> {
+        let v = visualizer(ONTOLOGY, DATA);
+        let local = |iri: &str| iri.strip_prefix(BRICK).unwrap().to_owned();
+        let targets = v.rollup_targets(&format!("{}Equipment", BRICK), depth).unwrap();
+        targets.iter().map(|(class, target)| (local(class), local(target))).collect()
+    }
+
+    fn expected(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(class, target)| (class.to_string(), target.to_string())).collect()
+    }
+
+    #[test]
+    fn rollup_targets_stop_at_the_depth() {
+        assert_eq!(targets(0), expected(&[
+            ("HVAC_Equipment", "Equipment"),
+            ("Lighting_Equipment", "Equipment"),
+            ("AHU", "Equipment"),
+            ("VAV", "Equipment"),
+            ("Luminaire", "Equipment"),
+            ("LED_Luminaire", "Equipment"),
+            ("Combo_Unit", "Equipment"),
+        ]));
+        // the combo unit goes through its parent closest to the root
+        assert_eq!(targets(1), expected(&[
+            ("AHU", "HVAC_Equipment"),
+            ("VAV", "HVAC_Equipment"),
+            ("Combo_Unit", "HVAC_Equipment"),
+            ("Luminaire", "Lighting_Equipment"),
+            ("LED_Luminaire", "Lighting_Equipment"),
+        ]));
+        assert_eq!(targets(2), expected(&[("LED_Luminaire", "Luminaire")]));
+    }
+
+    #[test]
+    fn rolled_up_classes_merge_their_edges_and_counts() {
+        let mut v = visualizer(ONTOLOGY, DATA).with_rollup(format!("{}Equipment", BRICK), 1);
+        v.build_graph().unwrap();
+        assert_eq!(nodes(&v), vec!["brick_HVAC_Equipment", "brick_Sensor"]);
+        assert_eq!(edges(&v), vec![
+            edge("brick_HVAC_Equipment", "brick_feeds", "brick_HVAC_Equipment"),
+            edge("brick_HVAC_Equipment", "brick_hasPoint", "brick_Sensor"),
+        ]);
+        // AHU hasPoint Sensor and VAV hasPoint Sensor add up
+        assert!(v.edge_counts().values().all(|count| *count == 2));
+        assert_eq!(v.instance_counts()["brick_HVAC_Equipment"], 4);
+        assert!(!v.instance_counts().contains_key("brick_AHU"));
+        assert!(v.node_iris().contains_key("brick_HVAC_Equipment"));
+    }
+
+    #[test]
+    fn properties_roll_up_to_their_most_general_superproperty() {
+        let ontology = "
+            brick:hasPoint rdfs:subPropertyOf owl:topObjectProperty .
+            brick:hasTemperaturePoint rdfs:subPropertyOf brick:hasPoint .
+            brick:hasSupplyTemperature rdfs:subPropertyOf brick:hasTemperaturePoint .
+            brick:isFedFrom rdfs:subPropertyOf brick:relatedTo, brick:connectedTo .
+        ";
+        let mut v = visualizer(ontology, "");
+        v.find_property_tops().unwrap();
+        let tops: HashMap<&str, &str> = v
+            .property_tops
+            .iter()
+            .map(|(p, top)| (p.strip_prefix(BRICK).unwrap(), top.strip_prefix(BRICK).unwrap()))
+            .collect();
+        // owl:topObjectProperty is not a top, and a property under two goes to the first
+        assert_eq!(tops, HashMap::from([
+            ("hasTemperaturePoint", "hasPoint"),
+            ("hasSupplyTemperature", "hasPoint"),
+            ("isFedFrom", "connectedTo"),
+        ]));
+
+        let data = "
+            ex:ahu1 a brick:AHU ; brick:hasSupplyTemperature ex:sensor1 ; brick:hasPoint ex:sensor2 .
+            ex:sensor1 a brick:Sensor .
+            ex:sensor2 a brick:Sensor .
+        ";
+        let ontology = format!("{} brick:AHU a owl:Class . brick:Sensor a owl:Class .", ontology);
+        let mut v = visualizer(&ontology, data).with_property_rollup(true);
+        v.build_graph().unwrap();
+        assert_eq!(edges(&v), vec![edge("brick_AHU", "brick_hasPoint", "brick_Sensor")]);
+    }
+}
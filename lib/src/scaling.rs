@@ -0,0 +1,63 @@
+//! Line widths and node sizes scaled by how many instances stand behind them
+
+use crate::Visualizer;
+use petgraph::graph::EdgeIndex;
+use std::collections::HashMap;
+
+/// Width of the edge with the fewest instance pairs
+pub(crate) const MIN_EDGE_WIDTH: f64 = 1.0;
+/// Width of the edge with the most instance pairs
+pub(crate) const MAX_EDGE_WIDTH: f64 = 8.0;
+
+/// Default (smallest, largest) font size of [`Visualizer::with_node_sizes`]
+pub const DEFAULT_NODE_SIZES: (f64, f64) = (12.0, 32.0);
+
+/// Maps `value` logarithmically from `1..=max` onto `low..=high`
+pub(crate) fn log_scale(value: usize, max: usize, low: f64, high: f64) -> f64 {
+    if max <= 1 {
+        return low;
+    }
+    let t = (value.max(1) as f64).ln() / (max as f64).ln();
+    low + (high - low) * t.clamp(0.0, 1.0)
+}
+
+impl Visualizer<'_> {
+    /// Draw each edge with a line width growing logarithmically with its number of instance
+    /// pairs (see [`Visualizer::edge_counts`]), so heavily used relationships dominate
+    pub fn with_edge_widths(mut self, edge_widths: bool) -> Self {
+        self.edge_widths = edge_widths;
+        self
+    }
+
+    /// Line width of each counted edge, when [`Visualizer::with_edge_widths`] is on
+    pub fn edge_widths(&self) -> HashMap<EdgeIndex, f64> {
+        if !self.edge_widths {
+            return HashMap::new();
+        }
+        let max = self.edge_counts.values().copied().max().unwrap_or(0);
+        self.edge_counts
+            .iter()
+            .map(|(idx, count)| (*idx, log_scale(*count, max, MIN_EDGE_WIDTH, MAX_EDGE_WIDTH)))
+            .collect()
+    }
+
+    /// Write the label of each class in a font size growing logarithmically with its number of
+    /// instances, from `min` points for a single instance to `max` for the largest one (see
+    /// [`DEFAULT_NODE_SIZES`]), so nodes grow with it
+    pub fn with_node_sizes(mut self, min: f64, max: f64) -> Self {
+        self.node_sizes = Some((min, max));
+        self
+    }
+
+    /// Font size of each counted class label, when [`Visualizer::with_node_sizes`] is set
+    pub fn node_font_sizes(&self) -> HashMap<&str, f64> {
+        let Some((min, max_size)) = self.node_sizes else {
+            return HashMap::new();
+        };
+        let max = self.instance_counts.values().copied().max().unwrap_or(0);
+        self.instance_counts
+            .iter()
+            .map(|(class, count)| (class.as_str(), log_scale(*count, max, min, max_size)))
+            .collect()
+    }
+}
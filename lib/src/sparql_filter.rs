@@ -0,0 +1,114 @@
+use crate::{Error, Result};
+use oxigraph::sparql::{Query, QueryResults};
+use oxigraph::store::Store;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Source class, target class and predicate of a candidate edge
+type Edge = (String, String, String);
+
+/// Edge filter that keeps an edge when a SPARQL `ASK` query over the store holds. `{from}`,
+/// `{to}` and `{p}` in the template are replaced by the `<iri>` of the source class, target
+/// class and predicate, so filters can use ontology knowledge:
+///
+/// ```text
+/// PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+/// PREFIX brick: <https://brickschema.org/schema/Brick#>
+/// ASK { {p} rdfs:subPropertyOf* brick:hasPoint }
+/// ```
+#[derive(Clone)]
+pub struct SparqlFilter {
+    store: Store,
+    template: String,
+    answers: Arc<Mutex<HashMap<Edge, bool>>>,
+}
+
+impl SparqlFilter {
+    /// Creates a filter asking `template` of `store`, usually [`crate::Visualizer::store`].
+    /// Fails if the template is not an `ASK` query once its placeholders are filled in.
+    pub fn new(store: Store, template: &str) -> Result<Self> {
+        let filter = SparqlFilter {
+            store,
+            template: template.to_owned(),
+            answers: Arc::default(),
+        };
+        let example = filter.substitute("<urn:from>", "<urn:to>", "<urn:p>");
+        let query = Query::parse(&example, None).map_err(|e| Error::Filter(e.to_string()))?;
+        match filter.store.query(query)? {
+            QueryResults::Boolean(_) => Ok(filter),
+            _ => Err(Error::Filter("the SPARQL filter must be an ASK query".to_owned())),
+        }
+    }
+
+    fn substitute(&self, from: &str, to: &str, p: &str) -> String {
+        self.template
+            .replace("{from}", from)
+            .replace("{to}", to)
+            .replace("{p}", p)
+    }
+
+    /// Asks the query for one edge; answers are cached per edge. Edges for which the query
+    /// fails are dropped.
+    pub fn keep(&self, from: &str, to: &str, edge: &str) -> bool {
+        let key = (from.to_owned(), to.to_owned(), edge.to_owned());
+        if let Some(answer) = self.answers.lock().unwrap().get(&key) {
+            return *answer;
+        }
+        let answer = matches!(
+            self.store.query(self.substitute(from, to, edge).as_str()),
+            Ok(QueryResults::Boolean(true))
+        );
+        self.answers.lock().unwrap().insert(key, answer);
+        answer
+    }
+
+    /// The filter as a closure for [`crate::Visualizer::new`]
+    pub fn into_fn(self) -> impl Fn(&str, &str, &str) -> bool + Send + Sync + 'static {
+        move |from, to, edge| self.keep(from, to, edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{edge, edges, visualizer};
+
+    const ONTOLOGY: &str = "
+        brick:AHU a owl:Class .
+        brick:VAV a owl:Class .
+        brick:Sensor a owl:Class .
+        brick:feeds rdfs:subPropertyOf brick:feedsAir .
+    ";
+    const DATA: &str = "
+        ex:ahu a brick:AHU ; brick:feeds ex:vav ; brick:hasPoint ex:sensor .
+        ex:vav a brick:VAV .
+        ex:sensor a brick:Sensor .
+    ";
+
+    #[test]
+    fn keeps_the_edges_the_query_holds_for() {
+        let v = visualizer(ONTOLOGY, DATA);
+        let filter = SparqlFilter::new(
+            v.store().clone(),
+            "ASK { {p} <http://www.w3.org/2000/01/rdf-schema#subPropertyOf> <https://brickschema.org/schema/Brick#feedsAir> }",
+        )
+        .unwrap();
+        let mut v = v.with_context_filter({
+            let filter = filter.clone().into_fn();
+            move |from, to, p, _| filter(from, to, p)
+        });
+        v.build_graph().unwrap();
+        assert_eq!(edges(&v), [edge("brick_AHU", "brick_feeds", "brick_VAV")]);
+
+        let brick = |name: &str| format!("<https://brickschema.org/schema/Brick#{}>", name);
+        assert!(filter.keep(&brick("AHU"), &brick("VAV"), &brick("feeds")));
+        assert!(!filter.keep(&brick("AHU"), &brick("Sensor"), &brick("hasPoint")));
+    }
+
+    #[test]
+    fn only_ask_queries_are_filters() {
+        let v = visualizer(ONTOLOGY, DATA);
+        assert!(SparqlFilter::new(v.store().clone(), "SELECT * WHERE { {from} ?p {to} }").is_err());
+        assert!(SparqlFilter::new(v.store().clone(), "ASK { {p} ").is_err());
+    }
+}
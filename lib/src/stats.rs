@@ -0,0 +1,193 @@
+use crate::{count_of, iri, ClassMetrics, Cycle, Result, Visualizer};
+use oxigraph::sparql::QueryResults;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// How a data graph uses its ontologies, by full IRI
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Stats {
+    /// Number of instances of each declared class
+    pub instances: BTreeMap<String, usize>,
+    /// Number of triples using each predicate with an instance as subject
+    pub predicates: BTreeMap<String, usize>,
+    /// Types of instances that no ontology declares as a class, with their instance counts
+    pub undefined_classes: BTreeMap<String, usize>,
+    /// Instances not linked to or from any other instance
+    pub orphans: Vec<String>,
+    /// Degree and betweenness of each class of the class graph, empty until it is built
+    pub metrics: BTreeMap<String, ClassMetrics>,
+    /// Cycles of a single predicate in the class graph, empty until it is built
+    pub cycles: Vec<Cycle>,
+}
+
+impl Stats {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let section = |f: &mut fmt::Formatter<'_>, title: &str, counts: &BTreeMap<String, usize>| {
+            writeln!(f, "{} ({}):", title, counts.len())?;
+            let mut counts: Vec<_> = counts.iter().collect();
+            counts.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.cmp(b)));
+            for (iri, count) in counts {
+                writeln!(f, "{:>8}  {}", count, iri)?;
+            }
+            Ok(())
+        };
+        section(f, "instances per class", &self.instances)?;
+        section(f, "triples per predicate", &self.predicates)?;
+        section(f, "classes used but not defined", &self.undefined_classes)?;
+        write!(f, "orphan instances ({}):", self.orphans.len())?;
+        for orphan in self.orphans.iter() {
+            write!(f, "\n          {}", orphan)?;
+        }
+        if !self.metrics.is_empty() {
+            write!(f, "\nclass metrics ({}):\n  degree  betweenness", self.metrics.len())?;
+            let mut metrics: Vec<_> = self.metrics.iter().collect();
+            metrics.sort_by(|(a, x), (b, y)| {
+                y.degree()
+                    .cmp(&x.degree())
+                    .then(y.betweenness.total_cmp(&x.betweenness))
+                    .then(a.cmp(b))
+            });
+            for (iri, metrics) in metrics {
+                write!(f, "\n{:>8}  {:>11.3}  {}", metrics.degree(), metrics.betweenness, iri)?;
+            }
+        }
+        write!(f, "\ncycles ({}):", self.cycles.len())?;
+        for cycle in self.cycles.iter() {
+            write!(f, "\n          {}: {}", cycle.predicate, cycle.classes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Visualizer<'_> {
+    /// Instance, predicate and class usage of the loaded data graph, leaving out the trivial
+    /// classes
+    pub fn stats(&self) -> Result<Stats> {
+        let instance = format!("?x a ?c . {}", self.class_pattern("c"));
+        let linked = self.class_pattern("d");
+        let instances = self.counts(&format!(
+            "SELECT ?class (COUNT(DISTINCT ?x) AS ?count) WHERE {{
+                 ?x a ?class . {}
+             }} GROUP BY ?class",
+            self.class_pattern("class")
+        ))?;
+        let predicates = self.counts(&format!(
+            "SELECT ?class (COUNT(*) AS ?count) WHERE {{
+                 ?x ?class ?o .
+                 FILTER EXISTS {{ {} }}
+             }} GROUP BY ?class",
+            instance
+        ))?;
+        let undefined_classes = self.query_undefined_classes("")?;
+
+        let mut orphans = Vec::new();
+        let q = format!(
+            "SELECT DISTINCT ?x WHERE {{
+                 {0}
+                 FILTER NOT EXISTS {{ ?x ?p ?y . ?y a ?d . {1} FILTER(?y != ?x) }}
+                 FILTER NOT EXISTS {{ ?y ?p ?x . ?y a ?d . {1} FILTER(?y != ?x) }}
+             }} ORDER BY ?x",
+            instance, linked
+        );
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                orphans.push(iri(row?.get("x").unwrap()));
+            }
+        }
+
+        // classes outside the known prefixes are labeled with their IRIs already
+        let class_iri = |class: String| self.node_iris.get(class.as_str()).map_or(class, |iri| iri.to_string());
+        let metrics = self
+            .class_metrics()
+            .into_iter()
+            .map(|(class, metrics)| (class_iri(class), metrics))
+            .collect();
+        let cycles = self
+            .cycles()
+            .into_iter()
+            .map(|cycle| Cycle {
+                classes: cycle.classes.into_iter().map(class_iri).collect(),
+                ..cycle
+            })
+            .collect();
+
+        Ok(Stats {
+            instances,
+            predicates,
+            undefined_classes,
+            orphans,
+            metrics,
+            cycles,
+        })
+    }
+
+    /// Collects the `?class ?count` rows of a query by IRI, skipping the trivial classes
+    pub(crate) fn counts(&self, q: &str) -> Result<BTreeMap<String, usize>> {
+        let mut counts = BTreeMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(q)? {
+            for row in solutions {
+                let row = row?;
+                let class = iri(row.get("class").unwrap());
+                if self.trivial_classes.contains(&class) {
+                    continue;
+                }
+                counts.insert(class, count_of(row.get("count")));
+            }
+        }
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::visualizer;
+
+    const BRICK: &str = "https://brickschema.org/schema/Brick#";
+
+    fn brick(name: &str) -> String {
+        format!("{}{}", BRICK, name)
+    }
+
+    #[test]
+    fn stats_count_instances_and_find_orphans_and_undefined_classes() {
+        let ontology = "
+            brick:AHU a owl:Class .
+            brick:VAV a owl:Class .
+            brick:Sensor a owl:Class .
+        ";
+        // the sensor, the labeled AHU and the VAV feeding itself are not linked to any other
+        // instance; the thing's class is not declared
+        let data = "
+            ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 .
+            ex:vav1 a brick:VAV ; brick:hasPoint ex:sensor1 .
+            ex:sensor1 a brick:Sensor .
+            ex:sensor2 a brick:Sensor .
+            ex:ahu2 a brick:AHU ; rdfs:label \"AHU 2\" .
+            ex:vav2 a brick:VAV ; brick:feeds ex:vav2 .
+            ex:thing1 a brick:Undeclared_Thing ; brick:feeds ex:vav1 .
+        ";
+        let mut v = visualizer(ontology, data);
+        let stats = v.stats().unwrap();
+        assert_eq!(stats.instances, BTreeMap::from([(brick("AHU"), 2), (brick("Sensor"), 2), (brick("VAV"), 2)]));
+        assert_eq!(stats.predicates[&brick("feeds")], 2);
+        assert_eq!(stats.predicates[&brick("hasPoint")], 1);
+        assert_eq!(stats.undefined_classes, BTreeMap::from([(brick("Undeclared_Thing"), 1)]));
+        assert_eq!(stats.orphans, vec!["urn:ex#ahu2", "urn:ex#sensor2", "urn:ex#vav2"]);
+        // nothing about the class graph before it is built
+        assert!(stats.metrics.is_empty() && stats.cycles.is_empty());
+
+        v.build_graph().unwrap();
+        let stats = v.stats().unwrap();
+        assert_eq!(stats.metrics[&brick("VAV")].in_degree, 2);
+        assert_eq!(stats.cycles.len(), 1);
+        assert_eq!(stats.cycles[0].classes, vec![brick("VAV")]);
+    }
+}
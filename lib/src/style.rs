@@ -0,0 +1,267 @@
+use crate::{expand_curie, Visualizer};
+use petgraph::graph::EdgeIndex;
+use std::str::FromStr;
+
+/// Visual attributes of a class node. Unset fields fall through to the next less specific
+/// style: per-class overrides sit on top of the category defaults from the class color map.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeStyle {
+    pub fill: Option<String>,
+    pub stroke: Option<String>,
+    pub font_color: Option<String>,
+    pub shape: Option<NodeShape>,
+}
+
+/// Outline of a class node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeShape {
+    Rectangle,
+    Circle,
+    Oval,
+    Hexagon,
+    /// A pentagon with its point up; d2 has no such shape and draws a hexagon instead
+    House,
+    Diamond,
+    Cylinder,
+}
+
+impl NodeShape {
+    /// Value of the Graphviz `shape` attribute
+    pub fn dot_name(&self) -> &'static str {
+        match self {
+            NodeShape::Rectangle => "box",
+            NodeShape::Circle => "circle",
+            NodeShape::Oval => "ellipse",
+            NodeShape::Hexagon => "hexagon",
+            NodeShape::House => "house",
+            NodeShape::Diamond => "diamond",
+            NodeShape::Cylinder => "cylinder",
+        }
+    }
+
+    /// Value of the d2 `shape` keyword
+    pub fn d2_name(&self) -> &'static str {
+        match self {
+            NodeShape::Rectangle => "rectangle",
+            NodeShape::Circle => "circle",
+            NodeShape::Oval => "oval",
+            NodeShape::Hexagon | NodeShape::House => "hexagon",
+            NodeShape::Diamond => "diamond",
+            NodeShape::Cylinder => "cylinder",
+        }
+    }
+
+    /// Style keys of the shape in draw.io, for the mxGraph XML export
+    pub fn drawio_style(&self) -> &'static str {
+        match self {
+            NodeShape::Rectangle => "rounded=0;",
+            NodeShape::Circle => "ellipse;aspect=fixed;",
+            NodeShape::Oval => "ellipse;",
+            NodeShape::Hexagon => "shape=hexagon;perimeter=hexagonPerimeter2;",
+            NodeShape::House => "shape=mxgraph.basic.pentagon;",
+            NodeShape::Diamond => "rhombus;",
+            NodeShape::Cylinder => "shape=cylinder3;",
+        }
+    }
+}
+
+impl FromStr for NodeShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rectangle" | "box" => Ok(NodeShape::Rectangle),
+            "circle" => Ok(NodeShape::Circle),
+            "oval" | "ellipse" => Ok(NodeShape::Oval),
+            "hexagon" => Ok(NodeShape::Hexagon),
+            "house" => Ok(NodeShape::House),
+            "diamond" => Ok(NodeShape::Diamond),
+            "cylinder" => Ok(NodeShape::Cylinder),
+            _ => Err(format!(
+                "unknown shape '{}', expected one of rectangle, circle, oval, hexagon, house, diamond, cylinder",
+                s
+            )),
+        }
+    }
+}
+
+impl NodeStyle {
+    pub fn fill(color: impl Into<String>) -> Self {
+        NodeStyle {
+            fill: Some(color.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns this style with every field set in `over` replaced by its value
+    pub fn merge(&self, over: &NodeStyle) -> NodeStyle {
+        NodeStyle {
+            fill: over.fill.clone().or_else(|| self.fill.clone()),
+            stroke: over.stroke.clone().or_else(|| self.stroke.clone()),
+            font_color: over.font_color.clone().or_else(|| self.font_color.clone()),
+            shape: over.shape.or(self.shape),
+        }
+    }
+}
+
+/// Parses `fill:SkyBlue,stroke:Navy,font-color:white,shape:circle`
+impl FromStr for NodeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = NodeStyle::default();
+        for attr in s.split(',').filter(|attr| !attr.is_empty()) {
+            let (key, value) = attr
+                .split_once(':')
+                .ok_or_else(|| format!("expected key:value in style, got '{}'", attr))?;
+            let value = value.trim();
+            match key.trim() {
+                "fill" => style.fill = Some(value.to_owned()),
+                "stroke" => style.stroke = Some(value.to_owned()),
+                "font-color" => style.font_color = Some(value.to_owned()),
+                "shape" => style.shape = Some(value.parse()?),
+                key => return Err(format!("unknown style attribute '{}'", key)),
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// How the line of an edge is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineStyle {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl FromStr for LineStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solid" => Ok(LineStyle::Solid),
+            "dashed" => Ok(LineStyle::Dashed),
+            "dotted" => Ok(LineStyle::Dotted),
+            _ => Err(format!("unknown line style '{}', expected solid, dashed or dotted", s)),
+        }
+    }
+}
+
+/// Visual attributes of the edges of a predicate, or of every predicate in a namespace
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdgeStyle {
+    pub stroke: Option<String>,
+    pub font_color: Option<String>,
+    pub line: Option<LineStyle>,
+}
+
+impl EdgeStyle {
+    /// Returns this style with every field set in `over` replaced by its value
+    pub fn merge(&self, over: &EdgeStyle) -> EdgeStyle {
+        EdgeStyle {
+            stroke: over.stroke.clone().or_else(|| self.stroke.clone()),
+            font_color: over.font_color.clone().or_else(|| self.font_color.clone()),
+            line: over.line.or(self.line),
+        }
+    }
+}
+
+/// Parses `stroke:blue,font-color:gray,line:dashed`
+impl FromStr for EdgeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = EdgeStyle::default();
+        for attr in s.split(',').filter(|attr| !attr.is_empty()) {
+            let (key, value) = attr
+                .split_once(':')
+                .ok_or_else(|| format!("expected key:value in style, got '{}'", attr))?;
+            let value = value.trim();
+            match key.trim() {
+                "stroke" => style.stroke = Some(value.to_owned()),
+                "font-color" => style.font_color = Some(value.to_owned()),
+                "line" => style.line = Some(value.parse()?),
+                key => return Err(format!("unknown edge style attribute '{}'", key)),
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// The node and edge styles of a style file. Each line is `class IRI STYLE` (a
+/// [`NodeStyle`]) or `edge IRI STYLE` (an [`EdgeStyle`]); IRIs may be CURIEs of the known
+/// prefixes, and an edge IRI ending in `#`, `/` or `:` styles every predicate in that
+/// namespace. Blank lines and lines starting with `#` are skipped:
+///
+/// ```text
+/// class https://brickschema.org/schema/Brick#AHU fill:SkyBlue,stroke:Navy
+/// edge brick:feeds stroke:blue
+/// edge brick:hasPoint stroke:gray,line:dashed
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyleSheet {
+    pub classes: Vec<(String, NodeStyle)>,
+    pub edges: Vec<(String, EdgeStyle)>,
+}
+
+impl FromStr for StyleSheet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sheet = StyleSheet::default();
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |e: String| format!("line {}: {}", number + 1, e);
+            let mut parts = line.split_whitespace();
+            let (Some(kind), Some(iri)) = (parts.next(), parts.next()) else {
+                return Err(error(format!("expected `class IRI STYLE` or `edge IRI STYLE`, got '{}'", line)));
+            };
+            let style = parts.collect::<Vec<_>>().join("");
+            let iri = expand_curie(iri);
+            match kind {
+                "class" => sheet.classes.push((iri, style.parse().map_err(error)?)),
+                "edge" => sheet.edges.push((iri, style.parse().map_err(error)?)),
+                _ => return Err(error(format!("unknown rule '{}', expected class or edge", kind))),
+            }
+        }
+        Ok(sheet)
+    }
+}
+
+impl Visualizer<'_> {
+    /// Styles the edges of `predicate`, a predicate IRI or a namespace IRI (or known prefix,
+    /// e.g. `brick:`) to style all of its predicates; the longest matching IRI applies
+    pub fn with_edge_style(mut self, predicate: impl Into<String>, style: EdgeStyle) -> Self {
+        self.edge_styles.push((expand_curie(&predicate.into()), style));
+        self
+    }
+
+    /// Adds every class and edge style of a [`StyleSheet`]
+    pub fn with_style_sheet(mut self, sheet: StyleSheet) -> Self {
+        for (class, style) in sheet.classes {
+            self = self.with_class_style(class, style);
+        }
+        for (predicate, style) in sheet.edges {
+            self = self.with_edge_style(predicate, style);
+        }
+        self
+    }
+
+    /// The style of an edge of [`Visualizer::graph`], from the most specific
+    /// [`Visualizer::with_edge_style`] matching its predicate
+    pub fn edge_style(&self, edge: EdgeIndex) -> EdgeStyle {
+        let Some(predicate) = self.edge_predicates.get(&edge) else {
+            return EdgeStyle::default();
+        };
+        self.edge_styles
+            .iter()
+            .filter(|(iri, _)| predicate.starts_with(iri.as_str()))
+            .max_by_key(|(iri, _)| iri.len())
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default()
+    }
+}
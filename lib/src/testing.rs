@@ -0,0 +1,62 @@
+//! Tiny ontologies and data graphs the unit tests build class graphs from
+
+use crate::Visualizer;
+use oxigraph::io::GraphFormat;
+use std::collections::HashMap;
+
+/// Prefixes every Turtle snippet given to [`visualizer`] and [`add_data`] may use
+const PREFIXES: &str = "
+    @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+    @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+    @prefix owl: <http://www.w3.org/2002/07/owl#> .
+    @prefix brick: <https://brickschema.org/schema/Brick#> .
+    @prefix ex: <urn:ex#> .
+";
+
+/// A visualizer keeping every edge, with `ontology` and `data` loaded but no graph built yet.
+/// Blank snippets are not loaded, the bulk loader refusing empty input.
+pub(crate) fn visualizer(ontology: &str, data: &str) -> Visualizer<'static> {
+    let mut v = Visualizer::new(|_: &str, _: &str, _: &str| true, HashMap::new()).unwrap();
+    if !ontology.trim().is_empty() {
+        v.add_ontology(turtle(ontology).as_bytes(), GraphFormat::Turtle).unwrap();
+    }
+    if !data.trim().is_empty() {
+        add_data(&mut v, data);
+    }
+    v
+}
+
+pub(crate) fn add_data(v: &mut Visualizer, data: &str) {
+    v.add_data(turtle(data).as_bytes(), GraphFormat::Turtle).unwrap();
+}
+
+/// `snippet` with the [`PREFIXES`] declared, for tests loading it themselves
+pub(crate) fn turtle(snippet: &str) -> String {
+    format!("{}{}", PREFIXES, snippet)
+}
+
+/// (from, predicate, to) labels of the edges in the graph, sorted
+pub(crate) fn edges(v: &Visualizer) -> Vec<(String, String, String)> {
+    let g = v.graph();
+    let mut edges: Vec<_> = g
+        .edge_indices()
+        .map(|idx| {
+            let (from, to) = g.edge_endpoints(idx).unwrap();
+            (g[from].to_string(), g[idx].to_string(), g[to].to_string())
+        })
+        .collect();
+    edges.sort();
+    edges
+}
+
+/// [`edges`] written out, to compare with a literal list
+pub(crate) fn edge(from: &str, p: &str, to: &str) -> (String, String, String) {
+    (from.to_owned(), p.to_owned(), to.to_owned())
+}
+
+/// Labels of the classes in the graph, sorted
+pub(crate) fn nodes(v: &Visualizer) -> Vec<String> {
+    let mut nodes: Vec<String> = v.graph().node_weights().map(|node| node.to_string()).collect();
+    nodes.sort();
+    nodes
+}
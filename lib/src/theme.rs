@@ -0,0 +1,102 @@
+//! Curated palettes for the background, nodes, fonts and edges of every renderer
+
+use crate::{ColorMap, Visualizer};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// A palette the renderers draw the graph in. The class color map still fills the classes
+/// it covers; [`ColorMap::themed`] recolors a built-in one to suit the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+    /// The Okabe-Ito palette, distinguishable with every common form of color blindness
+    Colorblind,
+}
+
+static DARK_PALETTE: &[&str] = &["#2E7D5B", "#8C3B3B", "#8A7000", "#3A5F8A", "#6B4C8A", "#8A5A2E"];
+
+static OKABE_ITO: &[&str] = &["#009E73", "#E69F00", "#56B4E9", "#F0E442", "#0072B2", "#D55E00", "#CC79A7"];
+
+impl Theme {
+    pub fn background(&self) -> &'static str {
+        match self {
+            Theme::Light | Theme::Colorblind => "#ffffff",
+            Theme::Dark => "#1e1e1e",
+        }
+    }
+
+    /// Fill of the classes outside every category
+    pub fn node_fill(&self) -> &'static str {
+        match self {
+            Theme::Light | Theme::Colorblind => "#ffffff",
+            Theme::Dark => "#3c3c3c",
+        }
+    }
+
+    pub fn font_color(&self) -> &'static str {
+        match self {
+            Theme::Light | Theme::Colorblind => "#000000",
+            Theme::Dark => "#eeeeee",
+        }
+    }
+
+    /// Color of node outlines and edges
+    pub fn line_color(&self) -> &'static str {
+        match self {
+            Theme::Light | Theme::Colorblind => "#555555",
+            Theme::Dark => "#bbbbbb",
+        }
+    }
+
+    /// Category fills replacing those of the built-in color maps, in their order
+    pub fn palette(&self) -> Option<&'static [&'static str]> {
+        match self {
+            Theme::Light => None,
+            Theme::Dark => Some(DARK_PALETTE),
+            Theme::Colorblind => Some(OKABE_ITO),
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            "colorblind" => Ok(Theme::Colorblind),
+            _ => Err(format!("unknown theme '{}', expected light, dark or colorblind", s)),
+        }
+    }
+}
+
+impl ColorMap {
+    /// [`ColorMap::to_map`] with its categories filled from the palette of `theme`, if it has one
+    pub fn themed(&self, theme: Theme) -> HashMap<&'static str, &'static str> {
+        match theme.palette() {
+            Some(palette) => self
+                .entries()
+                .iter()
+                .zip(palette.iter().cycle())
+                .map(|((class, _), color)| (*class, *color))
+                .collect(),
+            None => self.to_map(),
+        }
+    }
+}
+
+impl Visualizer<'_> {
+    /// Draw the graph in the background, default node fill, font and line colors of `theme`,
+    /// in every renderer. Class colors and styles still apply on top of it.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Fill of the classes outside every category of the class color map
+    pub(crate) fn default_fill(&self) -> &'static str {
+        self.theme.map_or("#ffffff", |theme| theme.node_fill())
+    }
+}
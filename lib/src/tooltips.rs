@@ -0,0 +1,71 @@
+use crate::{Result, Visualizer};
+use oxigraph::model::Term;
+use oxigraph::sparql::QueryResults;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+impl Visualizer<'_> {
+    /// Attach a tooltip to every class node with its full IRI and its `skos:definition` (or
+    /// `rdfs:comment`) from the ontology, so labels can stay short: DOT `tooltip`, d2
+    /// `tooltip` and an SVG `<title>` in the builtin engine
+    pub fn with_tooltips(mut self, tooltips: bool) -> Self {
+        self.tooltips = tooltips;
+        self
+    }
+
+    /// The `skos:definition`, or else `rdfs:comment`, of each class label in
+    /// [`Visualizer::graph`] that has one, when tooltips are enabled
+    pub fn definitions(&self) -> &HashMap<String, String> {
+        &self.definitions
+    }
+
+    /// Records the definitions of the classes in the graph, preferring English and untagged
+    /// ones
+    pub(crate) fn find_definitions(&mut self) -> Result<()> {
+        let q = "PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX skos: <http://www.w3.org/2004/02/skos/core#>
+                 SELECT ?class ?text ?rank WHERE {
+                     { ?class skos:definition ?text . BIND(0 AS ?kind) }
+                     UNION
+                     { ?class rdfs:comment ?text . BIND(1 AS ?kind) }
+                     FILTER(isLiteral(?text))
+                     BIND(?kind * 2 + IF(LANG(?text) = \"\" || LANGMATCHES(LANG(?text), \"en\"), 0, 1) AS ?rank)
+                 }";
+
+        let mut best: HashMap<Arc<str>, (i64, String)> = HashMap::new();
+        if let QueryResults::Solutions(solutions) = self.query(q)? {
+            for row in solutions {
+                let row = row?;
+                let class = self.interner.label(row.get("class").unwrap());
+                if !self.node_iris.contains_key(&class) {
+                    continue;
+                }
+                let (Some(Term::Literal(text)), Some(Term::Literal(rank))) = (row.get("text"), row.get("rank")) else {
+                    continue;
+                };
+                let rank = rank.value().parse().unwrap_or(i64::MAX);
+                let text = text.value().trim().to_owned();
+                let entry = best.entry(class).or_insert_with(|| (rank, text.clone()));
+                if rank < entry.0 {
+                    *entry = (rank, text);
+                }
+            }
+        }
+        self.definitions = best.into_iter().map(|(class, (_, text))| (class.to_string(), text)).collect();
+        Ok(())
+    }
+
+    /// Hover text of a class label: its IRI and definition when tooltips are enabled, followed
+    /// by how to migrate away from it if it is deprecated
+    pub(crate) fn tooltip(&self, class: &str) -> Option<String> {
+        let mut lines = Vec::new();
+        if self.tooltips {
+            lines.extend(self.node_iris.get(class).map(|iri| iri.to_string()));
+            lines.extend(self.definitions.get(class).cloned());
+        }
+        if let Some(deprecation) = self.deprecations.get(class) {
+            lines.push(deprecation.suggestion(class));
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
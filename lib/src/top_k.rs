@@ -0,0 +1,138 @@
+//! Top-K pruning: keeping the most important classes of giant graphs and merging the rest into
+//! a single "other" node
+
+use crate::{NodeStyle, Visualizer};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Label of the node the classes beyond [`Visualizer::with_max_nodes`] merge into
+pub const OTHER_NODE: &str = "other";
+
+/// What [`Visualizer::with_max_nodes`] ranks classes by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeRank {
+    /// Number of instances of the class
+    #[default]
+    Instances,
+    /// Number of edges from or to the class
+    Degree,
+}
+
+impl FromStr for NodeRank {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "instances" => Ok(NodeRank::Instances),
+            "degree" => Ok(NodeRank::Degree),
+            _ => Err(format!("unknown ranking '{}', expected instances or degree", s)),
+        }
+    }
+}
+
+impl Visualizer<'_> {
+    /// Keep only the `max` highest ranked classes, ties broken by label, and merge every other
+    /// class into one [`OTHER_NODE`] node carrying their edges and instance counts
+    pub fn with_max_nodes(mut self, max: usize, rank: NodeRank) -> Self {
+        self.max_nodes = Some((max, rank));
+        self
+    }
+
+    pub(crate) fn keep_top_classes(&mut self, max: usize, rank: NodeRank) {
+        if self.g.node_count() <= max {
+            return;
+        }
+        let mut ranked: Vec<(usize, &Arc<str>)> = self
+            .g
+            .node_indices()
+            .map(|idx| {
+                let label = &self.g[idx];
+                let score = match rank {
+                    NodeRank::Instances => self.instance_counts.get(&**label).copied().unwrap_or(0),
+                    NodeRank::Degree => self.g.edges_directed(idx, petgraph::Direction::Outgoing).count()
+                        + self.g.edges_directed(idx, petgraph::Direction::Incoming).count(),
+                };
+                (score, label)
+            })
+            .collect();
+        ranked.sort_by(|(a, a_label), (b, b_label)| b.cmp(a).then_with(|| a_label.cmp(b_label)));
+        let merged: HashMap<String, String> = ranked
+            .into_iter()
+            .skip(max)
+            .map(|(_, label)| (label.to_string(), OTHER_NODE.to_owned()))
+            .collect();
+        tracing::debug!(merged = merged.len(), max, "merged classes into {}", OTHER_NODE);
+
+        let fill = self.default_fill();
+        let other = self.interner.intern(OTHER_NODE);
+        self.colors.insert(other.clone(), fill.to_owned());
+        self.styles.insert(other, NodeStyle::fill(fill));
+        self.merge_classes(&merged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{edge, edges, nodes, visualizer};
+
+    /// Three AHUs and two VAVs; by degree the VAV leads and the zone ties the sensor
+    fn built(max: usize, rank: NodeRank) -> Visualizer<'static> {
+        let ontology = "
+            brick:AHU a owl:Class .
+            brick:VAV a owl:Class .
+            brick:Damper a owl:Class .
+            brick:Sensor a owl:Class .
+            brick:HVAC_Zone a owl:Class .
+        ";
+        let data = "
+            ex:ahu1 a brick:AHU ; brick:feeds ex:vav1 .
+            ex:ahu2 a brick:AHU ; brick:feeds ex:vav2 .
+            ex:ahu3 a brick:AHU .
+            ex:vav1 a brick:VAV ; brick:feeds ex:zone1 ; brick:hasPart ex:damper1 ; brick:hasPoint ex:sensor1 .
+            ex:vav2 a brick:VAV .
+            ex:zone1 a brick:HVAC_Zone ; brick:hasPoint ex:sensor1 .
+            ex:damper1 a brick:Damper .
+            ex:sensor1 a brick:Sensor .
+        ";
+        let mut v = visualizer(ontology, data).with_max_nodes(max, rank);
+        v.build_graph().unwrap();
+        v
+    }
+
+    #[test]
+    fn classes_with_the_most_instances_are_kept() {
+        let v = built(2, NodeRank::Instances);
+        assert_eq!(nodes(&v), vec!["brick_AHU", "brick_VAV", OTHER_NODE]);
+        assert_eq!(edges(&v), vec![
+            edge("brick_AHU", "brick_feeds", "brick_VAV"),
+            edge("brick_VAV", "brick_feeds", OTHER_NODE),
+            edge("brick_VAV", "brick_hasPart", OTHER_NODE),
+            edge("brick_VAV", "brick_hasPoint", OTHER_NODE),
+            edge(OTHER_NODE, "brick_hasPoint", OTHER_NODE),
+        ]);
+        assert_eq!(v.instance_counts()[OTHER_NODE], 3);
+        assert!(v.styles().contains_key(OTHER_NODE));
+    }
+
+    #[test]
+    fn classes_with_the_most_edges_are_kept_ties_by_label() {
+        let v = built(2, NodeRank::Degree);
+        assert_eq!(nodes(&v), vec!["brick_HVAC_Zone", "brick_VAV", OTHER_NODE]);
+        assert_eq!(edges(&v), vec![
+            edge("brick_HVAC_Zone", "brick_hasPoint", OTHER_NODE),
+            edge("brick_VAV", "brick_feeds", "brick_HVAC_Zone"),
+            edge("brick_VAV", "brick_hasPart", OTHER_NODE),
+            edge("brick_VAV", "brick_hasPoint", OTHER_NODE),
+            edge(OTHER_NODE, "brick_feeds", "brick_VAV"),
+        ]);
+    }
+
+    #[test]
+    fn small_graphs_are_left_alone() {
+        let v = built(5, NodeRank::Instances);
+        assert_eq!(nodes(&v).len(), 5);
+        assert!(!v.node_iris().contains_key(OTHER_NODE));
+    }
+}
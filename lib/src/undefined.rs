@@ -0,0 +1,66 @@
+use crate::{Result, Visualizer};
+use std::collections::BTreeMap;
+
+/// Namespaces of the vocabularies ontologies are written in; their classes (`owl:Class`,
+/// `sh:NodeShape`) are the types of schema resources, never undefined model classes
+static META_NAMESPACES: &[&str] = &[
+    "http://www.w3.org/2002/07/owl#",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+    "http://www.w3.org/2000/01/rdf-schema#",
+    "http://www.w3.org/ns/shacl#",
+];
+
+impl Visualizer<'_> {
+    /// Also draw the types of instances that no ontology declares as a class, as dashed
+    /// "unknown class" nodes, instead of dropping their instances' edges
+    pub fn with_unknown_class_nodes(mut self, unknown_class_nodes: bool) -> Self {
+        self.unknown_class_nodes = unknown_class_nodes;
+        self
+    }
+
+    /// Number of instances of each type (by IRI) the data graph uses but no ontology declares
+    /// as a class, so typos and missing imports do not silently drop parts of the graph
+    pub fn undefined_classes(&self) -> &BTreeMap<String, usize> {
+        &self.undefined_classes
+    }
+
+    /// Whether the class label is drawn for an undefined class
+    pub(crate) fn is_unknown_class(&self, label: &str) -> bool {
+        self.unknown_class_nodes
+            && self
+                .node_iris
+                .get(label)
+                .is_some_and(|iri| self.undefined_classes.contains_key(&**iri))
+    }
+
+    pub(crate) fn find_undefined_classes(&mut self) -> Result<()> {
+        self.undefined_classes = match &self.cache {
+            Some(cache) => cache.undefined_classes.clone(),
+            None => self.query_undefined_classes("")?,
+        };
+        if let Some(summary) = self.summary.as_mut() {
+            summary.undefined_classes = self.undefined_classes.clone();
+        }
+        Ok(())
+    }
+
+    /// Counts the instances of every non-trivial type that is not a declared class; `scope` is a
+    /// pattern binding `?class` to the types to count, or empty for all of them
+    pub(crate) fn query_undefined_classes(&self, scope: &str) -> Result<BTreeMap<String, usize>> {
+        let q = format!(
+            "SELECT ?class (COUNT(DISTINCT ?x) AS ?count) WHERE {{
+                 {}
+                 ?x a ?class .
+                 FILTER(isIRI(?class))
+                 FILTER NOT EXISTS {{ {} }}
+             }} GROUP BY ?class",
+            scope,
+            self.declared_class_pattern("class")
+        );
+        Ok(self
+            .counts(&q)?
+            .into_iter()
+            .filter(|(class, _)| !META_NAMESPACES.iter().any(|ns| class.starts_with(ns)))
+            .collect())
+    }
+}
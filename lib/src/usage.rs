@@ -0,0 +1,45 @@
+use std::fmt;
+use std::time::Duration;
+
+/// What a run cost: how much was loaded, how many queries ran and how long each stage took
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceUsage {
+    /// Triples in the store after the last load
+    pub triples: usize,
+    /// SPARQL queries evaluated against the store so far
+    pub queries: usize,
+    /// Wall time of each load and graph building stage, in the order they ran
+    pub stages: Vec<(&'static str, Duration)>,
+    /// Peak resident memory of the process in bytes, where the platform reports it
+    pub peak_memory: Option<u64>,
+}
+
+/// Peak resident set size of this process, read from `/proc/self/status` on Linux
+pub(crate) fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+impl ResourceUsage {
+    /// Total wall time of all stages
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+}
+
+impl fmt::Display for ResourceUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "triples loaded: {}", self.triples)?;
+        writeln!(f, "queries executed: {}", self.queries)?;
+        match self.peak_memory {
+            Some(bytes) => writeln!(f, "peak memory: {:.1} MiB", bytes as f64 / (1024.0 * 1024.0))?,
+            None => writeln!(f, "peak memory: unknown")?,
+        }
+        for (stage, elapsed) in self.stages.iter() {
+            writeln!(f, "{:>18}: {:.3}s", stage, elapsed.as_secs_f64())?;
+        }
+        write!(f, "{:>18}: {:.3}s", "total", self.total().as_secs_f64())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{Result, Visualizer};
+use oxigraph::io::GraphFormat;
+use oxigraph::model::{NamedNodeRef, Term};
+use oxigraph::sparql::QueryResults;
+use std::io::BufRead;
+
+/// Named graph validation reports are loaded into, keeping them out of the class queries
+static REPORT_GRAPH: NamedNodeRef<'static> =
+    NamedNodeRef::new_unchecked("urn:rdf_class_viz:validation-report");
+
+impl Visualizer<'_> {
+    /// Loads a SHACL validation report (an `sh:ValidationReport` and its results) produced for
+    /// the data graph. Classes whose instances are the focus of `sh:Violation` results get a red
+    /// border and their violation count in the label.
+    pub fn add_validation_report(&mut self, content: impl BufRead, format: GraphFormat) -> Result<()> {
+        self.load_into("load validation report", content, format, REPORT_GRAPH.into())
+    }
+
+    /// Counts the violations whose focus nodes are instances of each class in the graph
+    pub(crate) fn count_violations(&mut self) -> Result<()> {
+        let q = format!("PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+                 PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                 PREFIX owl: <http://www.w3.org/2002/07/owl#>
+                 PREFIX sh: <http://www.w3.org/ns/shacl#>
+                 SELECT ?class (COUNT(DISTINCT ?result) AS ?count) WHERE {{
+                     GRAPH {} {{
+                         ?result a sh:ValidationResult ;
+                             sh:focusNode ?x ;
+                             sh:resultSeverity sh:Violation .
+                     }}
+                     ?x rdf:type ?class .
+                     {}
+                     {}
+                 }} GROUP BY ?class", REPORT_GRAPH, self.class_pattern("class"), self.type_policy("x", "class"));
+
+        if let QueryResults::Solutions(solutions) = self.query(&q)? {
+            for row in solutions {
+                let row = row?;
+                let class = self.interner.label(row.get("class").unwrap());
+                if !self.node_iris.contains_key(&class) {
+                    continue;
+                }
+                let Some(Term::Literal(count)) = row.get("count") else {
+                    continue;
+                };
+                let count = count.value().parse().unwrap_or(0);
+                if count == 0 {
+                    continue;
+                }
+                if let Some(style) = self.styles.get_mut(&class) {
+                    style.stroke = Some("red".to_owned());
+                }
+                self.violations.insert(class.to_string(), count);
+            }
+        }
+        Ok(())
+    }
+}
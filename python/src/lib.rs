@@ -0,0 +1,198 @@
+//! Python bindings: `pip install ./python` (or `maturin develop -m python/Cargo.toml`), then
+//! `from rdf_class_viz import Visualizer`.
+
+use oxigraph::io::GraphFormat;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rdf_class_viz::{ClassDeclaration, ColorMap, Engine, GraphSource, ImageFormat, LabelStyle, TermCharset};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+fn py_err(e: impl Display) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// The RDF format of a file extension or name: ttl, nt or rdf
+fn graph_format(format: &str) -> PyResult<GraphFormat> {
+    GraphFormat::from_extension(format)
+        .ok_or_else(|| py_err(format!("unknown RDF format '{}', expected ttl, nt or rdf", format)))
+}
+
+/// Builds class graphs from ontologies and a data graph, and serializes them
+#[pyclass(name = "Visualizer", unsendable)]
+struct PyVisualizer {
+    inner: rdf_class_viz::Visualizer<'static>,
+    engine: Engine,
+}
+
+#[pymethods]
+impl PyVisualizer {
+    /// `color_map` maps class IRIs to the fill of the class and its subclasses; without one,
+    /// `colors` picks a built-in map: "brick", "s223", "sosa" or "prov". `source` is
+    /// one of "instances", "schema" or "shapes", `labels` one of "prefixed", "curie", "local"
+    /// or "human" and `classes` one of "owl", "rdfs", "shapes" or "any". `engine` lays out the
+    /// SVG of `to_svg`, `show` and notebook cells: "builtin" needs no external tools, "d2"
+    /// and "dot" shell out to those.
+    #[new]
+    // pyo3 maps every keyword argument of the Python constructor to one parameter
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (color_map=None, *, colors=None, source="instances", labels="prefixed", classes="owl", literals=false, restrictions=false, engine="builtin"))]
+    fn new(
+        color_map: Option<HashMap<String, String>>,
+        colors: Option<&str>,
+        source: &str,
+        labels: &str,
+        classes: &str,
+        literals: bool,
+        restrictions: bool,
+        engine: &str,
+    ) -> PyResult<Self> {
+        let source = match source {
+            "instances" => GraphSource::Instances,
+            "schema" => GraphSource::Schema,
+            "shapes" => GraphSource::Shapes,
+            _ => return Err(py_err(format!("unknown source '{}', expected instances, schema or shapes", source))),
+        };
+        let builtin_colors = match (&color_map, colors) {
+            (None, Some(colors)) => colors.parse::<ColorMap>().map_err(py_err)?.to_map(),
+            _ => HashMap::new(),
+        };
+        let inner = rdf_class_viz::Visualizer::new(|_: &str, _: &str, _: &str| true, builtin_colors)
+            .map_err(py_err)?
+            .with_color_map(color_map.unwrap_or_default())
+            .with_source(source)
+            .with_label_style(labels.parse::<LabelStyle>().map_err(py_err)?)
+            .with_class_declaration(classes.parse::<ClassDeclaration>().map_err(py_err)?)
+            .with_literals(literals)
+            .with_restrictions(restrictions);
+        let engine = engine.parse().map_err(py_err)?;
+        Ok(PyVisualizer { inner, engine })
+    }
+
+    /// Loads an ontology from its text, in `format` (ttl, nt or rdf)
+    #[pyo3(signature = (content, format="ttl"))]
+    fn add_ontology(&mut self, content: &str, format: &str) -> PyResult<()> {
+        self.inner
+            .add_ontology(content.as_bytes(), graph_format(format)?)
+            .map_err(py_err)
+    }
+
+    /// Loads a data graph from its text and builds the class graph, returning it as d2. Called
+    /// again, the new triples update the graph built so far.
+    #[pyo3(signature = (content, format="ttl"))]
+    fn create_graph(&mut self, content: &str, format: &str) -> PyResult<String> {
+        let built = self.inner.is_built();
+        self.inner
+            .add_data(content.as_bytes(), graph_format(format)?)
+            .map_err(py_err)?;
+        if !built {
+            self.inner.build_graph().map_err(py_err)?;
+        }
+        self.to_d2()
+    }
+
+    fn to_d2(&self) -> PyResult<String> {
+        self.inner.graph_to_d2lang().map_err(py_err)
+    }
+
+    fn to_dot(&self) -> PyResult<String> {
+        self.inner.graph_to_dot().map_err(py_err)
+    }
+
+    /// The `{nodes, edges}` JSON document of the class graph
+    fn to_json(&self) -> PyResult<String> {
+        let mut json = Vec::new();
+        self.inner.write_json(&mut json).map_err(py_err)?;
+        String::from_utf8(json).map_err(py_err)
+    }
+
+    fn to_graphml(&self) -> PyResult<String> {
+        let mut graphml = Vec::new();
+        self.inner.write_graphml(&mut graphml).map_err(py_err)?;
+        String::from_utf8(graphml).map_err(py_err)
+    }
+
+    fn to_gexf(&self) -> PyResult<String> {
+        self.inner.graph_to_gexf().map_err(py_err)
+    }
+
+    fn to_vowl(&self) -> PyResult<String> {
+        self.inner.graph_to_vowl().map_err(py_err)
+    }
+
+    /// Text rendering of the class graph for a terminal; `ascii` avoids box-drawing characters
+    #[pyo3(signature = (ascii=false))]
+    fn to_term(&self, ascii: bool) -> PyResult<String> {
+        let charset = if ascii { TermCharset::Ascii } else { TermCharset::Unicode };
+        self.inner.graph_to_term(charset).map_err(py_err)
+    }
+
+    /// The class-to-class adjacency matrix as CSV
+    fn to_matrix_csv(&self) -> PyResult<String> {
+        self.inner.matrix_csv().map_err(py_err)
+    }
+
+    /// Mermaid flowchart of the class graph
+    fn to_mermaid(&self) -> PyResult<String> {
+        self.inner.graph_to_mermaid().map_err(py_err)
+    }
+
+    /// diagrams.net (draw.io) file of the class graph
+    fn to_drawio(&self) -> PyResult<String> {
+        self.inner.graph_to_drawio().map_err(py_err)
+    }
+
+    /// GraphSON 3.0 of the class graph, one vertex per line
+    fn to_graphson(&self) -> PyResult<String> {
+        self.inner.graph_to_graphson().map_err(py_err)
+    }
+
+    /// Cypher script creating the class graph in Neo4j
+    fn to_cypher(&self) -> PyResult<String> {
+        self.inner.graph_to_cypher().map_err(py_err)
+    }
+
+    /// The class graph as a VoID description in Turtle
+    fn to_void(&self) -> PyResult<String> {
+        self.inner.graph_to_void().map_err(py_err)
+    }
+
+    /// The nodes.csv and edges.csv contents
+    fn to_csv(&self) -> PyResult<(String, String)> {
+        Ok((
+            self.inner.nodes_csv().map_err(py_err)?,
+            self.inner.edges_csv().map_err(py_err)?,
+        ))
+    }
+
+    /// The class graph laid out as an SVG document
+    fn to_svg(&self) -> PyResult<String> {
+        let svg = self.inner.render_with(self.engine, ImageFormat::Svg).map_err(py_err)?;
+        String::from_utf8(svg).map_err(py_err)
+    }
+
+    /// Notebooks render the visualizer as its SVG
+    fn _repr_html_(&self) -> PyResult<String> {
+        Ok(format!("<div class=\"rdf-class-viz\">{}</div>", self.to_svg()?))
+    }
+
+    /// Displays the class graph inline in a Jupyter notebook
+    fn show(&self, py: Python<'_>) -> PyResult<()> {
+        let display = py.import("IPython.display")?;
+        let html = display.getattr("HTML")?.call1((self._repr_html_()?,))?;
+        display.getattr("display")?.call1((html,))?;
+        Ok(())
+    }
+
+    /// Number of instances of each class label in the graph
+    fn instance_counts(&self) -> HashMap<String, usize> {
+        self.inner.instance_counts().clone()
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "rdf_class_viz")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyVisualizer>()?;
+    Ok(())
+}
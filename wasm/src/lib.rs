@@ -0,0 +1,34 @@
+//! Browser bindings: build with `wasm-pack build wasm --target web` and call
+//! `summarize(turtle)` from JavaScript to get the class summary of a graph without a server.
+
+use oxigraph::io::GraphFormat;
+use rdf_class_viz::Visualizer;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+fn js_error(e: impl std::fmt::Display) -> JsValue {
+    js_sys::Error::new(&e.to_string()).into()
+}
+
+/// Summarizes a Turtle data graph, with the ontologies its classes are declared in either
+/// included or passed as `ontology`. Returns `{ d2, dot, graph }`, where `graph` is the
+/// `{ nodes, edges }` object of the JSON export.
+#[wasm_bindgen]
+pub fn summarize(turtle: &str, ontology: Option<String>) -> Result<JsValue, JsValue> {
+    let mut v = Visualizer::new(|_: &str, _: &str, _: &str| true, HashMap::new()).map_err(js_error)?;
+    if let Some(ontology) = ontology {
+        v.add_ontology(ontology.as_bytes(), GraphFormat::Turtle).map_err(js_error)?;
+    }
+    v.add_data(turtle.as_bytes(), GraphFormat::Turtle).map_err(js_error)?;
+    v.build_graph().map_err(js_error)?;
+
+    let mut graph = Vec::new();
+    v.write_json(&mut graph).map_err(js_error)?;
+    let graph: serde_json::Value = serde_json::from_slice(&graph).map_err(js_error)?;
+    let summary = serde_json::json!({
+        "d2": v.graph_to_d2lang().map_err(js_error)?,
+        "dot": v.graph_to_dot().map_err(js_error)?,
+        "graph": graph,
+    });
+    js_sys::JSON::parse(&summary.to_string())
+}